@@ -0,0 +1,8 @@
+//! Addresses of module accounts shared between modules.
+
+/// Address of the account receiving fees paid for transactions and minted tokens.
+pub(crate) const FEE_COLLECTOR: &str = "fee_collector";
+
+/// Address of the distribution module account, holding minted rewards
+/// and validator commissions until they are withdrawn.
+pub(crate) const DISTRIBUTION_MODULE: &str = "distribution_module";
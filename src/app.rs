@@ -1,24 +1,50 @@
+use crate::accounts::FEE_COLLECTOR;
 use crate::bank::{Bank, BankKeeper, BankSudo};
 use crate::contracts::Contract;
-use crate::error::{bail, AnyResult};
+use crate::diff::StateDiff;
+use crate::error::{bail, AnyContext, AnyResult, Error};
 use crate::executor::{AppResponse, Executor};
+use crate::genesis::{ContractStateExport, UnboundContract, WasmGenesis};
 use crate::gov::Gov;
+use crate::history::{HistoricalQuerier, StateHistory};
 use crate::ibc::Ibc;
 use crate::module::{FailingModule, Module};
+use crate::prefixed_storage::{prefixed, prefixed_read};
+use crate::registry::{
+    ModuleRegistration, ModuleRegistry, RegisteredMsg, RegisteredQuery, RegisteredSudo,
+};
+use crate::search::{event_tags, BlockHistory, BlockResults, EventQuery};
 use crate::staking::{Distribution, DistributionKeeper, StakeKeeper, Staking, StakingSudo};
 use crate::stargate::{Stargate, StargateFailingModule, StargateMsg, StargateQuery};
-use crate::transactions::transactional;
+use crate::tracer::{msg_target, query_target, TraceKind, TraceNode, Tracer};
+use crate::transactions::{transactional, StorageChange, StorageTransaction};
+use crate::tx::{
+    with_msg_index, GasMeteredStorage, SimulationResponse, Tx, TxHistory, TxResponse,
+    CODESPACE_SDK, CODESPACE_UNDEFINED, CODE_INSUFFICIENT_FEE, CODE_OUT_OF_GAS, CODE_UNDEFINED,
+};
 use crate::wasm::{ContractData, Wasm, WasmKeeper, WasmSudo};
 use crate::{AppBuilder, GovFailingModule, IbcFailingModule};
 use cosmwasm_std::testing::{MockApi, MockStorage};
 use cosmwasm_std::{
-    from_json, to_json_binary, Addr, Api, Binary, BlockInfo, ContractResult, CosmosMsg, CustomMsg,
-    CustomQuery, Empty, Querier, QuerierResult, QuerierWrapper, QueryRequest, Record, Storage,
-    SystemError, SystemResult,
+    from_json, to_json_binary, to_json_string, to_json_vec, Addr, Api, BankMsg, Binary, BlockInfo,
+    ContractResult, CosmosMsg, CustomMsg, CustomQuery, Empty, HexBinary, Order, Querier,
+    QuerierResult, QuerierWrapper, QueryRequest, Record, Storage, SystemError, SystemResult,
 };
-use serde::{de::DeserializeOwned, Serialize};
+use cw_storage_plus::Item;
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cell::Cell;
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Namespace of the application's own data in the storage.
+const NAMESPACE_APP: &[u8] = b"app";
+
+/// Contracts registered to receive [BlockSudoMsg], in order of registration.
+const BLOCK_HOOK_CONTRACTS: Item<Vec<Addr>> = Item::new("block_hook_contracts");
 
 /// Advances the blockchain environment to the next block in tests, enabling developers to simulate
 /// time-dependent contract behaviors and block-related triggers efficiently.
@@ -27,6 +53,49 @@ pub fn next_block(block: &mut BlockInfo) {
     block.height += 1;
 }
 
+/// Automatic block production, see [App::set_block_production].
+///
+/// Every transaction executed with [execute](App::execute) or [execute_multi](App::execute_multi)
+/// is placed in the current block, until the block holds [txs_per_block](Self::txs_per_block)
+/// transactions. Then a new block is produced before executing the next transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockProduction {
+    /// Maximum number of transactions placed in a single block.
+    pub txs_per_block: u32,
+    /// Time between two consecutive blocks.
+    pub block_time: Duration,
+    /// Maximum time randomly added to the block time.
+    /// The added time is deterministic, derived from the height of the produced block.
+    pub jitter: Duration,
+}
+
+impl Default for BlockProduction {
+    /// Creates block production with a single transaction per block
+    /// and the same block time as in [next_block].
+    fn default() -> Self {
+        Self {
+            txs_per_block: 1,
+            block_time: Duration::from_secs(5),
+            jitter: Duration::ZERO,
+        }
+    }
+}
+
+impl BlockProduction {
+    /// Advances the block to the next one, according to the block time and jitter.
+    fn next_block(&self, block: &mut BlockInfo) {
+        block.height += 1;
+        let mut nanos = self.block_time.as_nanos() as u64;
+        let jitter = self.jitter.as_nanos() as u64;
+        if jitter > 0 {
+            let hash = Sha256::digest(block.height.to_be_bytes());
+            let random = u64::from_be_bytes(hash[..8].try_into().unwrap());
+            nanos += random % (jitter + 1);
+        }
+        block.time = block.time.plus_nanos(nanos);
+    }
+}
+
 /// A type alias for the default-built App. It simplifies storage and handling in typical scenarios,
 /// streamlining the use of the App structure in standard test setups.
 pub type BasicApp<ExecC = Empty, QueryC = Empty> = App<
@@ -62,8 +131,17 @@ pub struct App<
     pub(crate) api: Api,
     pub(crate) storage: Storage,
     pub(crate) block: BlockInfo,
+    pub(crate) block_production: Option<BlockProduction>,
+    pub(crate) state_history: Option<StateHistory>,
+    pub(crate) tx_history: TxHistory,
+    pub(crate) execution_history: bool,
+    pub(crate) block_history: Option<BlockHistory>,
+    pub(crate) invariants: Vec<(String, Rc<Invariant<Self>>)>,
 }
 
+/// Invariant of the application state, see [App::add_invariant].
+type Invariant<AppT> = dyn Fn(&AppT) -> AnyResult<()>;
+
 /// No-op application initialization function.
 pub fn no_init<BankT, CustomT, WasmT, StakingT, DistrT, IbcT, GovT, StargateT>(
     router: &mut Router<BankT, CustomT, WasmT, StakingT, DistrT, IbcT, GovT, StargateT>,
@@ -206,6 +284,7 @@ where
 
     /// Returns a mutable reference to application's storage.
     pub fn storage_mut(&mut self) -> &mut StorageT {
+        self.untrack_state_changes();
         &mut self.storage
     }
 
@@ -218,9 +297,18 @@ where
             &mut dyn Storage,
         ) -> T,
     {
+        self.untrack_state_changes();
         init_fn(&mut self.router, &self.api, &mut self.storage)
     }
 
+    /// Makes the state history compare the whole storage at the end of the block,
+    /// when the storage is changed directly, without tracking the changes.
+    fn untrack_state_changes(&mut self) {
+        if let Some(history) = &mut self.state_history {
+            history.untrack();
+        }
+    }
+
     /// Queries a module.
     pub fn read_module<F, T>(&self, query_fn: F) -> T
     where
@@ -234,6 +322,381 @@ where
     }
 }
 
+/// Captured state of the [App], see [App::snapshot].
+#[derive(Clone)]
+pub struct AppSnapshot<WasmT> {
+    /// All key-value pairs of the application's storage.
+    storage: Vec<Record>,
+    /// Block info at the moment of taking the snapshot.
+    block: BlockInfo,
+    /// Wasm module, holding the registry of stored codes.
+    wasm: WasmT,
+    /// Index of the next transaction within the block.
+    transaction_index: u32,
+    /// Transactions delivered before taking the snapshot.
+    tx_history: TxHistory,
+    /// Results of blocks processed before taking the snapshot, when recorded.
+    block_history: Option<BlockHistory>,
+}
+
+impl<BankT, ApiT, StorageT, CustomT, WasmT, StakingT, DistrT, IbcT, GovT, StargateT>
+    App<BankT, ApiT, StorageT, CustomT, WasmT, StakingT, DistrT, IbcT, GovT, StargateT>
+where
+    WasmT: Wasm<CustomT::ExecT, CustomT::QueryT> + Clone,
+    BankT: Bank,
+    ApiT: Api,
+    StorageT: Storage,
+    CustomT: Module,
+    StakingT: Staking,
+    DistrT: Distribution,
+    IbcT: Ibc,
+    GovT: Gov,
+    StargateT: Stargate,
+{
+    /// Captures the current state of the application: the whole storage,
+    /// block info and the codes stored in the wasm module.
+    ///
+    /// The state can be brought back with [restore](Self::restore), so many test scenarios
+    /// can branch from the same, expensive to prepare, state.
+    pub fn snapshot(&self) -> AppSnapshot<WasmT> {
+        AppSnapshot {
+            storage: self.storage.range(None, None, Order::Ascending).collect(),
+            block: self.block.clone(),
+            wasm: self.router.wasm.clone(),
+            transaction_index: self.router.transaction_index,
+            tx_history: self.tx_history.clone(),
+            block_history: self.block_history.clone(),
+        }
+    }
+
+    /// Restores the state of the application captured with [snapshot](Self::snapshot).
+    /// The same snapshot can be restored any number of times.
+    pub fn restore(&mut self, snapshot: &AppSnapshot<WasmT>) {
+        let keys: Vec<Vec<u8>> = self
+            .storage
+            .range(None, None, Order::Ascending)
+            .map(|(key, _)| key)
+            .collect();
+        for key in keys {
+            self.storage.remove(&key);
+        }
+        for (key, value) in snapshot.storage.iter() {
+            self.storage.set(key, value);
+        }
+        self.block = snapshot.block.clone();
+        self.router.wasm = snapshot.wasm.clone();
+        self.router.transaction_index = snapshot.transaction_index;
+        self.tx_history = snapshot.tx_history.clone();
+        self.block_history = snapshot.block_history.clone();
+        if self.state_history.is_some() {
+            self.state_history = Some(StateHistory::default());
+        }
+    }
+}
+
+impl<ApiT, StorageT, CustomT, DistrT, IbcT, GovT, StargateT>
+    App<
+        BankKeeper,
+        ApiT,
+        StorageT,
+        CustomT,
+        WasmKeeper<CustomT::ExecT, CustomT::QueryT>,
+        StakeKeeper,
+        DistrT,
+        IbcT,
+        GovT,
+        StargateT,
+    >
+where
+    ApiT: Api + 'static,
+    StorageT: Storage + 'static,
+    CustomT: Module + 'static,
+    DistrT: Distribution + 'static,
+    IbcT: Ibc + 'static,
+    GovT: Gov + 'static,
+    StargateT: Stargate + 'static,
+    CustomT::ExecT: CustomMsg + DeserializeOwned + 'static,
+    CustomT::QueryT: CustomQuery + DeserializeOwned + 'static,
+{
+    /// Registers built-in invariants (see [add_invariant](Self::add_invariant)):
+    /// - `bank supply`: the supply of every denomination, tracked by the bank module when minting
+    ///   and burning tokens, equals the sum of all balances, and no account holds zero amount
+    ///   of any denomination,
+    /// - `staking module balance`: the staking module holds exactly the tokens
+    ///   bonded to all validators, the tokens waiting for the completion of unbonding
+    ///   and the tokens slashed without burning,
+    /// - `contract code ids`: every instantiated contract refers to a stored code.
+    ///
+    /// The [DynApp](crate::DynApp) has its own `add_builtin_invariants`,
+    /// checking the boxed modules that are the default keepers.
+    pub fn add_builtin_invariants(&mut self) {
+        self.add_invariant("bank supply", |app| {
+            app.router.bank.check_supply(&app.storage)
+        });
+        self.add_invariant("staking module balance", |app| {
+            app.router.staking.check_module_balance(&app.storage, app)
+        });
+        self.add_invariant("contract code ids", |app| {
+            app.router.wasm.check_code_ids(&app.storage)
+        });
+    }
+}
+
+/// Complete state of the [App] in a serializable form, see [App::export_state].
+///
+/// The state can be saved into a JSON fixture file with [to_json](Self::to_json)
+/// and loaded back with [from_json](Self::from_json).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AppState {
+    /// Block info at the moment of exporting the state.
+    pub block: BlockInfo,
+    /// Codes stored in the wasm module, ordered by code identifier.
+    pub codes: Vec<CodeState>,
+    /// All key-value pairs of the application's storage, ordered by key.
+    pub storage: Vec<StorageEntry>,
+}
+
+/// Exported contract code, see [AppState].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct CodeState {
+    /// Identifier of the code.
+    pub code_id: u64,
+    /// Name used to bind the code to the contract implementation when importing the state.
+    pub name: Option<String>,
+    /// Address of an account that initially stored the code.
+    pub creator: Addr,
+    /// Checksum of the code.
+    pub checksum: HexBinary,
+}
+
+/// Exported key-value pair of the application's storage, see [AppState].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct StorageEntry {
+    /// Storage key.
+    pub key: Binary,
+    /// Value stored under the key.
+    pub value: Binary,
+}
+
+impl AppState {
+    /// Serializes the state into JSON.
+    pub fn to_json(&self) -> AnyResult<String> {
+        Ok(to_json_string(self)?)
+    }
+
+    /// Deserializes the state from JSON.
+    pub fn from_json(json: &str) -> AnyResult<Self> {
+        Ok(from_json(json)?)
+    }
+}
+
+impl<BankT, ApiT, StorageT, CustomT, StakingT, DistrT, IbcT, GovT, StargateT>
+    App<
+        BankT,
+        ApiT,
+        StorageT,
+        CustomT,
+        WasmKeeper<CustomT::ExecT, CustomT::QueryT>,
+        StakingT,
+        DistrT,
+        IbcT,
+        GovT,
+        StargateT,
+    >
+where
+    BankT: Bank,
+    ApiT: Api,
+    StorageT: Storage,
+    CustomT: Module,
+    StakingT: Staking,
+    DistrT: Distribution,
+    IbcT: Ibc,
+    GovT: Gov,
+    StargateT: Stargate,
+    CustomT::ExecT: CustomMsg + DeserializeOwned + 'static,
+    CustomT::QueryT: CustomQuery + DeserializeOwned + 'static,
+{
+    /// Registers contract code (like [store_code](Self::store_code)) under the specified name.
+    ///
+    /// The name is exported with the [state](Self::export_state) of the application
+    /// and used to bind the code to the contract implementation when [importing](Self::import_state).
+    pub fn store_code_with_name(
+        &mut self,
+        name: impl Into<String>,
+        code: Box<dyn Contract<CustomT::ExecT, CustomT::QueryT>>,
+    ) -> u64 {
+        let code_id = self.store_code(code);
+        self.router
+            .wasm
+            .set_code_info(code_id, Some(name.into()), None)
+            .expect("code was just stored");
+        code_id
+    }
+
+    /// Exports the complete state of the application: the whole storage
+    /// (all modules and contracts), block info and the codes stored in the wasm module.
+    pub fn export_state(&self) -> AppState {
+        AppState {
+            block: self.block.clone(),
+            codes: self
+                .router
+                .wasm
+                .code_infos()
+                .into_iter()
+                .zip(1..)
+                .map(|((name, creator, checksum), code_id)| CodeState {
+                    code_id,
+                    name,
+                    creator,
+                    checksum,
+                })
+                .collect(),
+            storage: self
+                .storage
+                .range(None, None, Order::Ascending)
+                .map(|(key, value)| StorageEntry {
+                    key: key.into(),
+                    value: value.into(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Imports the state [exported](Self::export_state) from another application.
+    ///
+    /// Stored codes are bound to contract implementations by name, using the provided `contracts`
+    /// function. The application must not have any codes stored yet, so the imported codes
+    /// keep their original identifiers. The current storage and block info are replaced.
+    pub fn import_state<F>(&mut self, state: &AppState, mut contracts: F) -> AnyResult<()>
+    where
+        F: FnMut(&str) -> Option<Box<dyn Contract<CustomT::ExecT, CustomT::QueryT>>>,
+    {
+        if !self.router.wasm.code_infos().is_empty() {
+            bail!("state can be imported only into an application without stored codes");
+        }
+        // all codes are resolved before the application is modified
+        let mut resolved = Vec::with_capacity(state.codes.len());
+        for (code, expected_code_id) in state.codes.iter().zip(1..) {
+            if code.code_id != expected_code_id {
+                bail!(
+                    "code id {}: expected code id {}",
+                    code.code_id,
+                    expected_code_id
+                );
+            }
+            let Some(name) = &code.name else {
+                bail!("code id {}: code has no name", code.code_id);
+            };
+            let Some(contract) = contracts(name) else {
+                bail!("code id {}: no contract named '{}'", code.code_id, name);
+            };
+            resolved.push((code, contract));
+        }
+        for (code, contract) in resolved {
+            let code_id = self.store_code_with_creator(code.creator.clone(), contract);
+            self.router.wasm.set_code_info(
+                code_id,
+                code.name.clone(),
+                Some(code.checksum.clone()),
+            )?;
+        }
+        self.untrack_state_changes();
+        let keys: Vec<Vec<u8>> = self
+            .storage
+            .range(None, None, Order::Ascending)
+            .map(|(key, _)| key)
+            .collect();
+        for key in keys {
+            self.storage.remove(&key);
+        }
+        for entry in state.storage.iter() {
+            self.storage
+                .set(entry.key.as_slice(), entry.value.as_slice());
+        }
+        self.block = state.block.clone();
+        Ok(())
+    }
+
+    /// Imports the state of the `wasm` module exported from a `wasmd` chain:
+    /// codes, contract infos, admins, labels and raw contract states.
+    ///
+    /// On-chain code identifiers are bound to contract implementations using the provided
+    /// `contracts` function. Every call to a contract of a code that is not bound fails,
+    /// but its raw state can still be queried. The application must not have any codes
+    /// stored yet, so the imported codes keep their on-chain identifiers.
+    pub fn import_wasm_genesis<F>(
+        &mut self,
+        genesis: &WasmGenesis,
+        mut contracts: F,
+    ) -> AnyResult<()>
+    where
+        F: FnMut(u64) -> Option<Box<dyn Contract<CustomT::ExecT, CustomT::QueryT>>>,
+    {
+        if !self.router.wasm.code_infos().is_empty() {
+            bail!("wasm genesis can be imported only into an application without stored codes");
+        }
+        for code_id in 1..=genesis.max_code_id() {
+            let contract =
+                contracts(code_id).unwrap_or_else(|| Box::new(UnboundContract::new(code_id)));
+            match genesis
+                .codes
+                .iter()
+                .find(|code| code.code_id.u64() == code_id)
+            {
+                Some(code) => {
+                    let creator = Addr::unchecked(&code.code_info.creator);
+                    self.store_code_with_creator(creator, contract);
+                    let checksum = HexBinary::from(code.code_info.code_hash.as_slice());
+                    self.router
+                        .wasm
+                        .set_code_info(code_id, None, Some(checksum))?;
+                }
+                None => {
+                    self.store_code(contract);
+                }
+            }
+        }
+        self.init_modules(|router, _, storage| {
+            for contract in genesis.contracts.iter() {
+                let info = &contract.contract_info;
+                let contract_data = ContractData {
+                    code_id: info.code_id.u64(),
+                    creator: Addr::unchecked(&info.creator),
+                    admin: (!info.admin.is_empty()).then(|| Addr::unchecked(&info.admin)),
+                    label: info.label.clone(),
+                    created: info
+                        .created
+                        .as_ref()
+                        .map(|created| created.block_height.u64())
+                        .unwrap_or_default(),
+                };
+                router.wasm.import_contract(
+                    storage,
+                    &Addr::unchecked(&contract.contract_address),
+                    &contract_data,
+                    &contract.contract_state,
+                )?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Replaces the whole state of the existing contract with the raw state exported
+    /// from a `wasmd` chain, e.g. with `wasmd query wasm contract-state all`.
+    pub fn import_contract_state(
+        &mut self,
+        address: &Addr,
+        state: &ContractStateExport,
+    ) -> AnyResult<()> {
+        self.init_modules(|router, _, storage| {
+            let contract_data = router.wasm.contract_data(storage, address)?;
+            router
+                .wasm
+                .import_contract(storage, address, &contract_data, &state.models)
+        })
+    }
+}
+
 // Helper functions to call some custom WasmKeeper logic.
 // They show how we can easily add such calls to other custom keepers (CustomT, StakingT, etc)
 impl<BankT, ApiT, StorageT, CustomT, WasmT, StakingT, DistrT, IbcT, GovT, StargateT>
@@ -354,20 +817,161 @@ where
 {
     /// Sets the initial block properties.
     pub fn set_block(&mut self, block: BlockInfo) {
-        self.router
-            .staking
-            .process_queue(&self.api, &mut self.storage, &self.router, &self.block)
-            .unwrap();
-        self.block = block;
+        self.advance_block(|current| *current = block).unwrap();
     }
 
     /// Updates the current block applying the specified closure, usually [next_block].
     pub fn update_block<F: Fn(&mut BlockInfo)>(&mut self, action: F) {
-        self.router
-            .staking
-            .process_queue(&self.api, &mut self.storage, &self.router, &self.block)
-            .unwrap();
-        action(&mut self.block);
+        self.advance_block(action).unwrap();
+    }
+
+    /// Updates the current block applying the specified closure, like [update_block](Self::update_block),
+    /// but returns the events emitted while ending the current block and beginning the new one.
+    ///
+    /// The staking queue is processed first, then [end_block](Module::end_block) is called
+    /// for every module and contract registered for block hooks, the block is updated,
+    /// and finally [begin_block](Module::begin_block) is called for every module and contract.
+    /// No state changes are persisted and the block is not updated, if any of these steps fails.
+    pub fn advance_block<F: FnOnce(&mut BlockInfo)>(
+        &mut self,
+        action: F,
+    ) -> AnyResult<AppResponse> {
+        let Self {
+            block,
+            router,
+            api,
+            storage,
+            state_history,
+            block_history,
+            ..
+        } = self;
+
+        let mut next = block.clone();
+        action(&mut next);
+        let mut committed_state = None;
+        let mut block_events = None;
+        let mut cache = StorageTransaction::new(&*storage);
+        let mut transition = |write_cache: &mut StorageTransaction| -> AnyResult<AppResponse> {
+            let mut end_block_events = router
+                .staking
+                .process_queue(&*api, write_cache, router, block)?
+                .events;
+            end_block_events.extend(router.end_block(&*api, write_cache, block)?.events);
+            if let Some(history) = state_history {
+                committed_state = Some(history.changed_state(write_cache));
+            }
+            let begin_block_events = router.begin_block(&*api, write_cache, &next)?.events;
+            if block_history.is_some() {
+                block_events = Some((end_block_events.clone(), begin_block_events.clone()));
+            }
+            let mut events = end_block_events;
+            events.extend(begin_block_events);
+            Ok(AppResponse {
+                events,
+                ..Default::default()
+            })
+        };
+        let response = transition(&mut cache)?;
+        let changes = cache.changes();
+        cache.prepare().commit(&mut *storage);
+        if let (Some(history), Some(state)) = (state_history, committed_state) {
+            history.commit(block, state);
+            // changes made while beginning the new block are committed with it
+            history.track(&changes);
+        }
+        if let (Some(history), Some((end_block_events, begin_block_events))) =
+            (block_history, block_events)
+        {
+            history.record(block, &next, end_block_events, begin_block_events);
+        }
+        router.transaction_index = 0;
+        *block = next;
+        Ok(response)
+    }
+
+    /// Registers the contract to receive [BlockSudoMsg] at the beginning
+    /// and at the end of every block, like begin and end blockers of the chain.
+    pub fn register_block_hooks(&mut self, contract: &Addr) -> AnyResult<()> {
+        self.init_modules(|router, _, storage| {
+            router.wasm.contract_data(storage, contract)?;
+            let mut storage = prefixed(storage, NAMESPACE_APP);
+            let mut contracts = BLOCK_HOOK_CONTRACTS.may_load(&storage)?.unwrap_or_default();
+            if contracts.contains(contract) {
+                bail!("block hooks already registered: {}", contract);
+            }
+            contracts.push(contract.clone());
+            BLOCK_HOOK_CONTRACTS.save(&mut storage, &contracts)?;
+            Ok(())
+        })
+    }
+
+    /// Unregisters the contract registered with [register_block_hooks](Self::register_block_hooks).
+    pub fn unregister_block_hooks(&mut self, contract: &Addr) -> AnyResult<()> {
+        self.untrack_state_changes();
+        let mut storage = prefixed(&mut self.storage, NAMESPACE_APP);
+        let mut contracts = BLOCK_HOOK_CONTRACTS.may_load(&storage)?.unwrap_or_default();
+        if !contracts.contains(contract) {
+            bail!("block hooks not registered: {}", contract);
+        }
+        contracts.retain(|registered| registered != contract);
+        BLOCK_HOOK_CONTRACTS.save(&mut storage, &contracts)?;
+        Ok(())
+    }
+
+    /// Registers the invariant of the application state, checked after every message
+    /// or transaction executed and every privileged action run by the application.
+    ///
+    /// A violated invariant is reported as an error of the action that violated it,
+    /// pointing at the executed messages. State changes made by the action are reverted,
+    /// like when the action itself fails.
+    pub fn add_invariant(
+        &mut self,
+        name: impl Into<String>,
+        invariant: impl Fn(&Self) -> AnyResult<()> + 'static,
+    ) {
+        self.invariants.push((name.into(), Rc::new(invariant)));
+    }
+
+    /// Checks all registered invariants, reporting the violated one along with the action.
+    fn check_invariants(&self, action: impl FnOnce() -> String) -> AnyResult<()> {
+        for (name, invariant) in self.invariants.iter() {
+            if let Err(err) = invariant(self) {
+                bail!(
+                    "invariant '{}' violated after {}: {:#}",
+                    name,
+                    action(),
+                    err
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the action on a cache of the storage and commits the changes it made, then checks
+    /// all invariants. The changes are reverted when any invariant is violated.
+    /// Returns the result of the action along with the committed changes.
+    fn commit_checked<T>(
+        &mut self,
+        action: impl FnOnce(&Self, &mut dyn Storage) -> AnyResult<T>,
+        describe: impl FnOnce(&T) -> String,
+    ) -> AnyResult<(T, Vec<StorageChange>)> {
+        let mut cache = StorageTransaction::new(&self.storage);
+        let result = action(self, &mut cache)?;
+        let changes = cache.changes();
+        cache.prepare().commit(&mut self.storage);
+        if let Err(err) = self.check_invariants(|| describe(&result)) {
+            for change in changes.iter() {
+                match &change.old_value {
+                    Some(value) => self.storage.set(&change.key, value),
+                    None => self.storage.remove(&change.key),
+                }
+            }
+            return Err(err);
+        }
+        if let Some(history) = &mut self.state_history {
+            history.track(&changes);
+        }
+        Ok((result, changes))
     }
 
     /// Returns a copy of the current block_info
@@ -377,7 +981,7 @@ where
 
     /// Simple helper so we get access to all the QuerierWrapper helpers,
     /// e.g. wrap().query_wasm_smart, query_all_balances, ...
-    pub fn wrap(&self) -> QuerierWrapper<CustomT::QueryT> {
+    pub fn wrap(&self) -> QuerierWrapper<'_, CustomT::QueryT> {
         QuerierWrapper::new(self)
     }
 
@@ -393,20 +997,430 @@ where
         // meaning, wrap current state, all writes go to a cache, only when execute
         // returns a success do we flush it (otherwise drop it)
 
+        let (responses, _) = self.execute_multi_checked(sender, msgs)?;
+        Ok(responses)
+    }
+
+    /// Delivers the transaction: pays the fee and executes all messages atomically,
+    /// in the same way as [execute_multi](Self::execute_multi).
+    ///
+    /// Failures of the messages are reported in the returned [TxResponse], with the state changes
+    /// made by the messages reverted, but the fee paid. Storage operations consume gas
+    /// like in cosmos-sdk, and the messages fail when the gas used exceeds the gas limit.
+    /// Contract execution itself does not consume gas. Every delivered transaction is stored
+    /// in the history, see [query_tx](Self::query_tx).
+    pub fn deliver_tx(&mut self, tx: Tx<CustomT::ExecT>) -> AnyResult<TxResponse> {
+        if tx.msgs.is_empty() {
+            bail!("transaction must contain at least one message");
+        }
+        let Some(sender) = tx.signers.first().cloned() else {
+            bail!("transaction must have at least one signer");
+        };
+        let (response, _) = self.place_in_block(|app| {
+            app.commit_checked(
+                |app, storage| app.deliver_tx_in_block(storage, tx, sender),
+                |response| format!("delivering transaction {}", response.txhash),
+            )
+        })?;
+        self.tx_history.record(response.clone());
+        Ok(response)
+    }
+
+    /// Simulates the execution of messages, like [execute_multi](Self::execute_multi),
+    /// but always discards the state changes.
+    ///
+    /// Returns the responses of the messages, gas used by storage operations (see [deliver_tx](Self::deliver_tx))
+    /// and all changes of the storage that would have been made.
+    pub fn simulate(
+        &self,
+        sender: Addr,
+        msgs: Vec<CosmosMsg<CustomT::ExecT>>,
+    ) -> AnyResult<SimulationResponse> {
+        let mut cache = StorageTransaction::new(&self.storage);
+        let gas_used = Cell::new(0);
+        let responses = {
+            let mut metered = GasMeteredStorage::new(&mut cache, &gas_used);
+            msgs.into_iter()
+                .map(|msg| {
+                    self.router
+                        .execute(&self.api, &mut metered, &self.block, sender.clone(), msg)
+                })
+                .collect::<AnyResult<Vec<_>>>()?
+        };
+        Ok(SimulationResponse {
+            responses,
+            gas_used: gas_used.get(),
+            changes: cache.changes(),
+        })
+    }
+
+    /// Returns the delivered transaction with specified hash. Messages executed outside
+    /// of transactions are found only when recorded, see [enable_execution_history](Self::enable_execution_history).
+    pub fn query_tx(&self, txhash: &str) -> AnyResult<TxResponse> {
+        let Some(tx) = self.tx_history.get(txhash) else {
+            bail!("tx not found: {}", txhash);
+        };
+        Ok(tx.clone())
+    }
+
+    /// Returns transactions delivered with [deliver_tx](Self::deliver_tx) matching the query,
+    /// in order of delivery, together with recorded executions of messages,
+    /// see [enable_execution_history](Self::enable_execution_history).
+    /// See the [search](crate::search) module for the syntax of the query.
+    pub fn search_txs(&self, query: &str) -> AnyResult<Vec<TxResponse>> {
+        let query: EventQuery = query.parse()?;
+        let mut txs = vec![];
+        for tx in self.tx_history.txs() {
+            let mut tags = event_tags(&tx.events);
+            tags.push(("tx.height".to_string(), tx.height.to_string()));
+            tags.push(("tx.hash".to_string(), tx.txhash.clone()));
+            if query.matches(&tags) {
+                txs.push(tx.clone());
+            }
+        }
+        Ok(txs)
+    }
+
+    /// Returns events emitted at the beginning and at the end of the block with specified height,
+    /// see [enable_block_history](Self::enable_block_history).
+    pub fn query_block_results(&self, height: u64) -> AnyResult<BlockResults> {
+        let Some(history) = &self.block_history else {
+            bail!("block history is not enabled");
+        };
+        let Some(results) = history.get(height) else {
+            bail!("block results not found: {}", height);
+        };
+        Ok(results.clone())
+    }
+
+    /// Returns recorded blocks with events matching the query, in order of height.
+    /// See the [search](crate::search) module for the syntax of the query.
+    pub fn search_blocks(&self, query: &str) -> AnyResult<Vec<BlockResults>> {
+        let query: EventQuery = query.parse()?;
+        let Some(history) = &self.block_history else {
+            bail!("block history is not enabled");
+        };
+        let mut blocks = vec![];
+        for results in history.blocks() {
+            let mut tags = event_tags(&results.begin_block_events);
+            tags.extend(event_tags(&results.end_block_events));
+            tags.push(("block.height".to_string(), results.height.to_string()));
+            if query.matches(&tags) {
+                blocks.push(results.clone());
+            }
+        }
+        Ok(blocks)
+    }
+
+    /// Returns the hash of the next delivered transaction.
+    fn next_tx_hash(&self, tx: &Tx<CustomT::ExecT>) -> AnyResult<String> {
+        let mut hasher = Sha256::new();
+        hasher.update(to_json_vec(tx)?);
+        hasher.update(self.tx_history.count().to_be_bytes());
+        Ok(HexBinary::from(hasher.finalize().as_slice())
+            .to_hex()
+            .to_uppercase())
+    }
+
+    /// Delivers the transaction in the current block.
+    fn deliver_tx_in_block(
+        &self,
+        storage: &mut dyn Storage,
+        tx: Tx<CustomT::ExecT>,
+        sender: Addr,
+    ) -> AnyResult<TxResponse> {
+        let txhash = self.next_tx_hash(&tx)?;
+
         let Self {
-            block,
-            router,
-            api,
-            storage,
+            block, router, api, ..
         } = self;
 
-        transactional(&mut *storage, |write_cache, _| {
-            msgs.into_iter()
-                .map(|msg| router.execute(&*api, write_cache, block, sender.clone(), msg))
-                .collect()
+        let mut response = TxResponse {
+            height: block.height,
+            timestamp: block.time,
+            txhash,
+            code: 0,
+            codespace: String::new(),
+            raw_log: String::new(),
+            gas_wanted: tx.gas_limit,
+            gas_used: 0,
+            events: vec![],
+            msg_responses: vec![],
+        };
+
+        if !tx.fee.is_empty() {
+            let fee = BankMsg::Send {
+                to_address: FEE_COLLECTOR.to_string(),
+                amount: tx.fee.clone(),
+            };
+            let result = transactional(&mut *storage, |write_cache, _| {
+                router.execute(&*api, write_cache, block, sender.clone(), fee.into())
+            });
+            match result {
+                Ok(fee_response) => response.events.extend(fee_response.events),
+                Err(err) => {
+                    response.code = CODE_INSUFFICIENT_FEE;
+                    response.codespace = CODESPACE_SDK.to_string();
+                    response.raw_log = format!("insufficient fee: {:#}", err);
+                }
+            }
+        }
+
+        if response.is_ok() {
+            let gas_used = Cell::new(0);
+            let result = transactional(&mut *storage, |write_cache, _| {
+                let mut metered = GasMeteredStorage::new(write_cache, &gas_used);
+                let responses = tx
+                    .msgs
+                    .into_iter()
+                    .enumerate()
+                    .map(|(msg_index, msg)| {
+                        router
+                            .execute(&*api, &mut metered, block, sender.clone(), msg)
+                            .with_context(|| {
+                                format!("failed to execute message; message index: {}", msg_index)
+                            })
+                    })
+                    .collect::<AnyResult<Vec<_>>>()?;
+                if gas_used.get() > tx.gas_limit {
+                    bail!(
+                        "out of gas: gas wanted: {}, gas used: {}",
+                        tx.gas_limit,
+                        gas_used.get()
+                    );
+                }
+                Ok(responses)
+            });
+            response.gas_used = gas_used.get();
+            match result {
+                Ok(responses) => {
+                    for (msg_index, msg_response) in responses.iter().enumerate() {
+                        response
+                            .events
+                            .extend(with_msg_index(msg_response.events.clone(), msg_index));
+                    }
+                    response.msg_responses = responses;
+                }
+                Err(err) => {
+                    if response.gas_used > tx.gas_limit {
+                        response.code = CODE_OUT_OF_GAS;
+                        response.codespace = CODESPACE_SDK.to_string();
+                    } else {
+                        response.code = CODE_UNDEFINED;
+                        response.codespace = CODESPACE_UNDEFINED.to_string();
+                    }
+                    response.raw_log = format!("{:#}", err);
+                }
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Places the transaction executed by the action in the block,
+    /// producing a new block first when needed, see [BlockProduction].
+    fn place_in_block<T, F>(&mut self, action: F) -> AnyResult<T>
+    where
+        F: FnOnce(&mut Self) -> AnyResult<T>,
+    {
+        let Some(production) = self.block_production.clone() else {
+            return action(self);
+        };
+        if self.router.transaction_index >= production.txs_per_block {
+            self.advance_block(|block| production.next_block(block))?;
+        }
+        let result = action(self);
+        self.router.transaction_index += 1;
+        result
+    }
+
+    /// Executes messages atomically in the current block, checking invariants,
+    /// and records the execution in the history when enabled,
+    /// see [enable_execution_history](Self::enable_execution_history).
+    fn execute_multi_checked(
+        &mut self,
+        sender: Addr,
+        msgs: Vec<CosmosMsg<CustomT::ExecT>>,
+    ) -> AnyResult<(Vec<AppResponse>, Vec<StorageChange>)> {
+        self.place_in_block(|app| {
+            let (responses, changes) = app.commit_checked(
+                |app, storage| app.execute_in_block(storage, sender.clone(), msgs.clone()),
+                |_| format!("executing {:?}", msgs),
+            )?;
+            if app.execution_history {
+                let txhash = app.next_tx_hash(&Tx::new(sender, msgs))?;
+                let mut events = vec![];
+                for (msg_index, response) in responses.iter().enumerate() {
+                    events.extend(with_msg_index(response.events.clone(), msg_index));
+                }
+                app.tx_history.record(TxResponse {
+                    height: app.block.height,
+                    timestamp: app.block.time,
+                    txhash,
+                    code: 0,
+                    codespace: String::new(),
+                    raw_log: String::new(),
+                    gas_wanted: 0,
+                    gas_used: 0,
+                    events,
+                    msg_responses: responses.clone(),
+                });
+            }
+            Ok((responses, changes))
         })
     }
 
+    /// Executes messages in the current block, writing to the given storage cache.
+    fn execute_in_block(
+        &self,
+        storage: &mut dyn Storage,
+        sender: Addr,
+        msgs: Vec<CosmosMsg<CustomT::ExecT>>,
+    ) -> AnyResult<Vec<AppResponse>> {
+        msgs.into_iter()
+            .map(|msg| {
+                self.router
+                    .execute(&self.api, storage, &self.block, sender.clone(), msg)
+            })
+            .collect()
+    }
+
+    /// Executes the message like [execute](Executor::execute), returning also
+    /// the [state diff](StateDiff) with all changes of the storage made by the message.
+    pub fn execute_with_diff(
+        &mut self,
+        sender: Addr,
+        msg: CosmosMsg<CustomT::ExecT>,
+    ) -> AnyResult<(AppResponse, StateDiff)> {
+        let (mut responses, diff) = self.execute_multi_with_diff(sender, vec![msg])?;
+        Ok((responses.pop().unwrap(), diff))
+    }
+
+    /// Executes messages atomically like [execute_multi](Self::execute_multi), returning also
+    /// the [state diff](StateDiff) with all changes of the storage made by all messages.
+    pub fn execute_multi_with_diff(
+        &mut self,
+        sender: Addr,
+        msgs: Vec<CosmosMsg<CustomT::ExecT>>,
+    ) -> AnyResult<(Vec<AppResponse>, StateDiff)> {
+        let (responses, changes) = self.execute_multi_checked(sender, msgs)?;
+        Ok((responses, StateDiff::new(changes)))
+    }
+
+    /// Enables recording of the state committed at the end of every block,
+    /// so it can be queried later with [wrap_at_height](Self::wrap_at_height),
+    /// like on an archive node. The state is recorded starting from the current block.
+    pub fn enable_state_history(&mut self) {
+        if self.state_history.is_none() {
+            self.state_history = Some(StateHistory::default());
+        }
+    }
+
+    /// Disables recording of the state and drops all recorded states.
+    pub fn disable_state_history(&mut self) {
+        self.state_history = None;
+    }
+
+    /// Enables recording of messages executed successfully outside of transactions,
+    /// with [execute_multi](Self::execute_multi) and all helpers built on it, like
+    /// [execute_contract](Executor::execute_contract), so they can be queried
+    /// with [query_tx](Self::query_tx) and [search_txs](Self::search_txs)
+    /// like delivered transactions. The executions are recorded without gas,
+    /// which is metered only for transactions, see [deliver_tx](Self::deliver_tx).
+    pub fn enable_execution_history(&mut self) {
+        self.execution_history = true;
+    }
+
+    /// Disables recording of executed messages. Already recorded executions are kept.
+    pub fn disable_execution_history(&mut self) {
+        self.execution_history = false;
+    }
+
+    /// Enables recording of the events emitted at the beginning and at the end of every block,
+    /// so they can be queried with [query_block_results](Self::query_block_results)
+    /// and [search_blocks](Self::search_blocks). The events are recorded starting from
+    /// the end of the current block.
+    pub fn enable_block_history(&mut self) {
+        if self.block_history.is_none() {
+            self.block_history = Some(BlockHistory::default());
+        }
+    }
+
+    /// Disables recording of the block events and drops all recorded block results.
+    pub fn disable_block_history(&mut self) {
+        self.block_history = None;
+    }
+
+    /// Returns the querier reading the state committed at the end of the block with specified height,
+    /// see [enable_state_history](Self::enable_state_history).
+    ///
+    /// Both raw and smart queries are supported, contracts are queried with the block info
+    /// of the specified block. The current block is not committed yet, query it with [wrap](Self::wrap).
+    pub fn wrap_at_height(
+        &self,
+        height: u64,
+    ) -> AnyResult<HistoricalQuerier<'_, CustomT::ExecT, CustomT::QueryT>>
+    where
+        CustomT::ExecT: CustomMsg + DeserializeOwned + 'static,
+        CustomT::QueryT: CustomQuery + DeserializeOwned + 'static,
+    {
+        let Some(history) = &self.state_history else {
+            bail!("state history is not enabled");
+        };
+        if height >= self.block.height {
+            bail!(
+                "height {} is not committed yet, current height is {}",
+                height,
+                self.block.height
+            );
+        }
+        let (storage, block) = history.state_at(height)?;
+        Ok(HistoricalQuerier::new(
+            &self.router,
+            &self.api,
+            storage,
+            block,
+        ))
+    }
+
+    /// Registers an additional module, processing the messages, queries and privileged actions
+    /// claimed by the registration, see [Router::register_module].
+    pub fn register_module(
+        &mut self,
+        registration: ModuleRegistration<CustomT::ExecT, CustomT::QueryT>,
+    ) -> AnyResult<()> {
+        self.router.register_module(registration)
+    }
+
+    /// Enables tracing of the call tree of all processed messages,
+    /// submessages, replies, privileged actions and queries, see [Tracer].
+    pub fn enable_tracing(&mut self) {
+        if self.router.tracer.is_none() {
+            self.router.tracer = Some(Rc::new(Tracer::default()));
+        }
+    }
+
+    /// Disables tracing and drops all recorded calls.
+    pub fn disable_tracing(&mut self) {
+        self.router.tracer = None;
+    }
+
+    /// Returns the tracer, when tracing is enabled.
+    pub fn tracer(&self) -> Option<&Tracer> {
+        self.router.tracer.as_deref()
+    }
+
+    /// Enables automatic production of blocks, or disables it when `None` is passed.
+    /// See [BlockProduction] for details. The next executed transaction is placed
+    /// in the current block, as the first one.
+    ///
+    /// While enabled, [Env::transaction](cosmwasm_std::Env::transaction) passed to contracts
+    /// holds the index of the transaction within the block.
+    pub fn set_block_production(&mut self, production: impl Into<Option<BlockProduction>>) {
+        self.block_production = production.into();
+        self.router.transaction_index = 0;
+    }
+
     /// Call a smart contract in "sudo" mode.
     /// This will create a cache before the execution, so no state changes are persisted if this
     /// returns an error, but all are persisted on success.
@@ -416,38 +1430,52 @@ where
         msg: &T,
     ) -> AnyResult<AppResponse> {
         let msg = to_json_binary(msg)?;
+        let contract_addr = contract_addr.into();
 
-        let Self {
-            block,
-            router,
-            api,
-            storage,
-        } = self;
-
-        transactional(&mut *storage, |write_cache, _| {
-            router
-                .wasm
-                .sudo(&*api, contract_addr.into(), write_cache, router, block, msg)
-        })
+        let (response, _) = self.commit_checked(
+            |app, storage| {
+                app.router.wasm.sudo(
+                    &app.api,
+                    contract_addr.clone(),
+                    storage,
+                    &app.router,
+                    &app.block,
+                    msg.clone(),
+                )
+            },
+            |_| {
+                format!(
+                    "sudo of contract {}: {}",
+                    contract_addr,
+                    String::from_utf8_lossy(msg.as_slice())
+                )
+            },
+        )?;
+        Ok(response)
     }
 
-    /// Runs arbitrary SudoMsg.
+    /// Runs arbitrary SudoMsg, including privileged actions of the custom module.
     /// This will create a cache before the execution, so no state changes are persisted if this
     /// returns an error, but all are persisted on success.
-    pub fn sudo(&mut self, msg: SudoMsg) -> AnyResult<AppResponse> {
+    pub fn sudo(&mut self, msg: SudoMsg<CustomT::SudoT>) -> AnyResult<AppResponse>
+    where
+        CustomT::SudoT: Debug,
+    {
         // we need to do some caching of storage here, once in the entry point:
         // meaning, wrap current state, all writes go to a cache, only when execute
         // returns a success do we flush it (otherwise drop it)
-        let Self {
-            block,
-            router,
-            api,
-            storage,
-        } = self;
 
-        transactional(&mut *storage, |write_cache, _| {
-            router.sudo(&*api, write_cache, block, msg)
-        })
+        // the message is described up front, as it is consumed by the execution
+        let action = if self.invariants.is_empty() {
+            String::new()
+        } else {
+            format!("sudo {:?}", msg)
+        };
+        let (response, _) = self.commit_checked(
+            |app, storage| app.router.sudo_typed(&app.api, storage, &app.block, msg),
+            |_| action,
+        )?;
+        Ok(response)
     }
 }
 /// The Router plays a critical role in managing and directing
@@ -470,6 +1498,12 @@ pub struct Router<Bank, Custom, Wasm, Staking, Distr, Ibc, Gov, Stargate> {
     pub gov: Gov,
     /// Stargate module instance to be used in this [Router].
     pub stargate: Stargate,
+    /// Tracer recording the call tree, when tracing is enabled.
+    pub(crate) tracer: Option<Rc<Tracer>>,
+    /// Additional modules claiming messages by their type, see [register_module](Self::register_module).
+    pub(crate) registry: ModuleRegistry,
+    /// Index of the current transaction within the block, used only when block production is enabled.
+    pub(crate) transaction_index: u32,
 }
 
 impl<BankT, CustomT, WasmT, StakingT, DistrT, IbcT, GovT, StargateT>
@@ -486,6 +1520,93 @@ where
     GovT: Gov,
     StargateT: Stargate,
 {
+    /// Registers an additional module, processing the messages, queries and privileged actions
+    /// claimed by the registration instead of the modules in the fixed slots of this [Router].
+    ///
+    /// Fails when any type URL, query path or sudo namespace of the registration
+    /// is already claimed by another registered module. Custom messages and queries
+    /// are claimed with predicates, so a message claimed by more than one module
+    /// is rejected when it is executed or queried.
+    pub fn register_module(
+        &mut self,
+        registration: ModuleRegistration<CustomT::ExecT, CustomT::QueryT>,
+    ) -> AnyResult<()> {
+        self.registry.register(registration)
+    }
+
+    /// Evaluates privileged actions, including privileged actions of the custom module.
+    pub(crate) fn sudo_typed(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        msg: SudoMsg<CustomT::SudoT>,
+    ) -> AnyResult<AppResponse>
+    where
+        CustomT::SudoT: Debug,
+    {
+        self.route_sudo(api, storage, block, msg, |storage, msg| {
+            self.custom.sudo(api, storage, self, block, msg)
+        })
+    }
+
+    /// Routes privileged actions to modules, passing custom privileged actions to `custom`.
+    fn route_sudo<C: Debug>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        msg: SudoMsg<C>,
+        custom: impl FnOnce(&mut dyn Storage, C) -> AnyResult<AppResponse>,
+    ) -> AnyResult<AppResponse> {
+        let tracer = self.tracer.as_deref();
+        if let Some(tracer) = tracer {
+            let (target, msg) = match &msg {
+                SudoMsg::Wasm(msg) => (
+                    msg.contract_addr.to_string(),
+                    String::from_utf8_lossy(msg.msg.as_slice()).to_string(),
+                ),
+                SudoMsg::Bank(msg) => ("bank".to_string(), format!("{:?}", msg)),
+                SudoMsg::Staking(msg) => ("staking".to_string(), format!("{:?}", msg)),
+                SudoMsg::Custom(msg) => ("custom".to_string(), format!("{:?}", msg)),
+                SudoMsg::Distribution(msg) => ("distribution".to_string(), format!("{:?}", msg)),
+                SudoMsg::Gov(msg) => ("gov".to_string(), format!("{:?}", msg)),
+                SudoMsg::Ibc(msg) => ("ibc".to_string(), format!("{:?}", msg)),
+                SudoMsg::Stargate(msg) => ("stargate".to_string(), format!("{:?}", msg)),
+                SudoMsg::Module(msg) => (
+                    msg.namespace.clone(),
+                    String::from_utf8_lossy(msg.msg.as_slice()).to_string(),
+                ),
+            };
+            tracer.enter(TraceNode::new(TraceKind::Sudo, None, target, msg, None));
+        }
+        let res = match msg {
+            SudoMsg::Wasm(msg) => {
+                self.wasm
+                    .sudo(api, msg.contract_addr, storage, self, block, msg.msg)
+            }
+            SudoMsg::Bank(msg) => self.bank.sudo(api, storage, self, block, msg),
+            SudoMsg::Staking(msg) => self.staking.sudo(api, storage, self, block, msg),
+            SudoMsg::Custom(msg) => custom(storage, msg),
+            SudoMsg::Distribution(msg) => self.distribution.sudo(api, storage, self, block, msg),
+            SudoMsg::Gov(msg) => self.gov.sudo(api, storage, self, block, msg),
+            SudoMsg::Ibc(msg) => self.ibc.sudo(api, storage, self, block, msg),
+            SudoMsg::Stargate(msg) => self.stargate.sudo(api, storage, self, block, msg),
+            SudoMsg::Module(msg) => match self
+                .registry
+                .by_sudo_namespace::<CustomT::ExecT, CustomT::QueryT>(&msg.namespace)
+            {
+                Ok(Some(module)) => module.sudo(api, storage, self, block, msg),
+                Ok(None) => Err(Error::unsupported_sudo_message(msg).into()),
+                Err(err) => Err(err),
+            },
+        };
+        if let Some(tracer) = tracer {
+            tracer.exit_response(&res);
+        }
+        res
+    }
+
     /// Returns a querier populated with the instance of this [Router].
     pub fn querier<'a>(
         &'a self,
@@ -500,38 +1621,140 @@ where
             block_info,
         }
     }
+
+    /// Calls [begin_block](Module::begin_block) of every module, and then sends
+    /// [BlockSudoMsg::BeginBlock] to every contract registered for block hooks.
+    pub(crate) fn begin_block(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+    ) -> AnyResult<AppResponse> {
+        let responses = [
+            self.bank.begin_block(api, storage, self, block)?,
+            self.custom.begin_block(api, storage, self, block)?,
+            self.staking.begin_block(api, storage, self, block)?,
+            self.distribution.begin_block(api, storage, self, block)?,
+            self.ibc.begin_block(api, storage, self, block)?,
+            self.gov.begin_block(api, storage, self, block)?,
+            self.stargate.begin_block(api, storage, self, block)?,
+            self.sudo_block_hooks(api, storage, block, BlockSudoMsg::BeginBlock {})?,
+        ];
+        Ok(AppResponse {
+            events: responses.into_iter().flat_map(|r| r.events).collect(),
+            ..Default::default()
+        })
+    }
+
+    /// Calls [end_block](Module::end_block) of every module, and then sends
+    /// [BlockSudoMsg::EndBlock] to every contract registered for block hooks.
+    pub(crate) fn end_block(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+    ) -> AnyResult<AppResponse> {
+        let responses = [
+            self.bank.end_block(api, storage, self, block)?,
+            self.custom.end_block(api, storage, self, block)?,
+            self.staking.end_block(api, storage, self, block)?,
+            self.distribution.end_block(api, storage, self, block)?,
+            self.ibc.end_block(api, storage, self, block)?,
+            self.gov.end_block(api, storage, self, block)?,
+            self.stargate.end_block(api, storage, self, block)?,
+            self.sudo_block_hooks(api, storage, block, BlockSudoMsg::EndBlock {})?,
+        ];
+        Ok(AppResponse {
+            events: responses.into_iter().flat_map(|r| r.events).collect(),
+            ..Default::default()
+        })
+    }
+
+    /// Sends the message to every contract registered for block hooks, in order of registration.
+    fn sudo_block_hooks(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        msg: BlockSudoMsg,
+    ) -> AnyResult<AppResponse> {
+        let contracts = BLOCK_HOOK_CONTRACTS
+            .may_load(&prefixed_read(storage, NAMESPACE_APP))?
+            .unwrap_or_default();
+        let mut events = vec![];
+        for contract in contracts {
+            let msg = WasmSudo::new(&contract, &msg)?;
+            events.extend(self.sudo(api, storage, block, msg.into())?.events);
+        }
+        Ok(AppResponse {
+            events,
+            ..Default::default()
+        })
+    }
+}
+
+/// Privileged message sent to every contract registered with
+/// [register_block_hooks](App::register_block_hooks), at the beginning and at the end of every block.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockSudoMsg {
+    /// Sent at the beginning of the block, with the info of the new block in the environment.
+    BeginBlock {},
+    /// Sent at the end of the block, with the info of the ending block in the environment.
+    EndBlock {},
 }
 
 /// We use it to allow calling into modules from another module in sudo mode.
 /// Things like gov proposals belong here.
-pub enum SudoMsg {
+///
+/// The type parameter is the type of privileged messages of the custom module,
+/// see [Module::SudoT]. Custom privileged actions can be run only with [App::sudo],
+/// [CosmosRouter::sudo] rejects them, as the type of these actions is not known there.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SudoMsg<CustomSudoT = Empty> {
     /// Bank privileged actions.
     Bank(BankSudo),
     /// Custom privileged actions.
-    Custom(Empty),
+    Custom(CustomSudoT),
     /// Staking privileged actions.
     Staking(StakingSudo),
     /// Wasm privileged actions.
     Wasm(WasmSudo),
+    /// Distribution privileged actions.
+    Distribution(Empty),
+    /// Governance privileged actions.
+    Gov(Empty),
+    /// IBC privileged actions.
+    Ibc(Empty),
+    /// Stargate privileged actions.
+    Stargate(Empty),
+    /// Privileged actions of modules registered with [Router::register_module].
+    Module(RegisteredSudo),
 }
 
-impl From<WasmSudo> for SudoMsg {
+impl<CustomSudoT> From<WasmSudo> for SudoMsg<CustomSudoT> {
     fn from(wasm: WasmSudo) -> Self {
         SudoMsg::Wasm(wasm)
     }
 }
 
-impl From<BankSudo> for SudoMsg {
+impl<CustomSudoT> From<BankSudo> for SudoMsg<CustomSudoT> {
     fn from(bank: BankSudo) -> Self {
         SudoMsg::Bank(bank)
     }
 }
 
-impl From<StakingSudo> for SudoMsg {
+impl<CustomSudoT> From<StakingSudo> for SudoMsg<CustomSudoT> {
     fn from(staking: StakingSudo) -> Self {
         SudoMsg::Staking(staking)
     }
 }
+
+impl<CustomSudoT> From<RegisteredSudo> for SudoMsg<CustomSudoT> {
+    fn from(module: RegisteredSudo) -> Self {
+        SudoMsg::Module(module)
+    }
+}
 /// A trait representing the Cosmos based chain's router.
 ///
 /// This trait is designed for routing messages within the Cosmos ecosystem.
@@ -570,6 +1793,16 @@ pub trait CosmosRouter {
         block: &BlockInfo,
         msg: SudoMsg,
     ) -> AnyResult<AppResponse>;
+
+    /// Returns the tracer recording the call tree, when tracing is enabled.
+    fn tracer(&self) -> Option<&Tracer> {
+        None
+    }
+
+    /// Returns the index of the currently executed transaction within the block.
+    fn transaction_index(&self) -> u32 {
+        0
+    }
 }
 
 impl<BankT, CustomT, WasmT, StakingT, DistrT, IbcT, GovT, StargateT> CosmosRouter
@@ -597,26 +1830,65 @@ where
         sender: Addr,
         msg: CosmosMsg<Self::ExecC>,
     ) -> AnyResult<AppResponse> {
-        match msg {
+        if let Some(tracer) = self.tracer() {
+            tracer.enter(TraceNode::new(
+                TraceKind::Execute,
+                Some(sender.to_string()),
+                msg_target(&msg),
+                to_json_string(&msg).unwrap_or_default(),
+                None,
+            ));
+        }
+        let res = match msg {
             CosmosMsg::Wasm(msg) => self.wasm.execute(api, storage, self, block, sender, msg),
             CosmosMsg::Bank(msg) => self.bank.execute(api, storage, self, block, sender, msg),
-            CosmosMsg::Custom(msg) => self.custom.execute(api, storage, self, block, sender, msg),
+            CosmosMsg::Custom(msg) => match self
+                .registry
+                .by_custom_msg::<CustomT::ExecT, CustomT::QueryT>(&msg)
+            {
+                Ok(Some(module)) => module.execute(
+                    api,
+                    storage,
+                    self,
+                    block,
+                    sender,
+                    RegisteredMsg::Custom(msg),
+                ),
+                Ok(None) => self.custom.execute(api, storage, self, block, sender, msg),
+                Err(err) => Err(err),
+            },
             CosmosMsg::Staking(msg) => self.staking.execute(api, storage, self, block, sender, msg),
             CosmosMsg::Distribution(msg) => self
                 .distribution
                 .execute(api, storage, self, block, sender, msg),
             CosmosMsg::Ibc(msg) => self.ibc.execute(api, storage, self, block, sender, msg),
             CosmosMsg::Gov(msg) => self.gov.execute(api, storage, self, block, sender, msg),
-            CosmosMsg::Stargate { type_url, value } => self.stargate.execute(
-                api,
-                storage,
-                self,
-                block,
-                sender,
-                StargateMsg { type_url, value },
-            ),
-            _ => bail!("Cannot execute {:?}", msg),
+            CosmosMsg::Stargate { type_url, value } => {
+                let msg = StargateMsg { type_url, value };
+                match self
+                    .registry
+                    .by_type_url::<CustomT::ExecT, CustomT::QueryT>(&msg.type_url)
+                {
+                    Ok(Some(module)) => module.execute(
+                        api,
+                        storage,
+                        self,
+                        block,
+                        sender,
+                        RegisteredMsg::Stargate(msg),
+                    ),
+                    Ok(None) => self
+                        .stargate
+                        .execute(api, storage, self, block, sender, msg),
+                    Err(err) => Err(err),
+                }
+            }
+            _ => Err(Error::unsupported_message(msg).into()),
+        };
+        if let Some(tracer) = self.tracer() {
+            tracer.exit_response(&res);
         }
+        res
     }
 
     /// This is used by `RouterQuerier` to actual implement the `Querier` interface.
@@ -633,14 +1905,36 @@ where
         match request {
             QueryRequest::Wasm(req) => self.wasm.query(api, storage, &querier, block, req),
             QueryRequest::Bank(req) => self.bank.query(api, storage, &querier, block, req),
-            QueryRequest::Custom(req) => self.custom.query(api, storage, &querier, block, req),
+            QueryRequest::Custom(req) => match self
+                .registry
+                .by_custom_query::<CustomT::ExecT, CustomT::QueryT>(&req)?
+            {
+                Some(module) => {
+                    module.query(api, storage, &querier, block, RegisteredQuery::Custom(req))
+                }
+                None => self.custom.query(api, storage, &querier, block, req),
+            },
             QueryRequest::Staking(req) => self.staking.query(api, storage, &querier, block, req),
             QueryRequest::Ibc(req) => self.ibc.query(api, storage, &querier, block, req),
             QueryRequest::Stargate { path, data } => {
-                self.stargate
-                    .query(api, storage, &querier, block, StargateQuery { path, data })
+                let request = StargateQuery { path, data };
+                match self
+                    .registry
+                    .by_query_path::<CustomT::ExecT, CustomT::QueryT>(&request.path)?
+                {
+                    Some(module) => module.query(
+                        api,
+                        storage,
+                        &querier,
+                        block,
+                        RegisteredQuery::Stargate(request),
+                    ),
+                    None => self.stargate.query(api, storage, &querier, block, request),
+                }
             }
-            _ => unimplemented!(),
+            _ => bail!(Error::unsupported_query(
+                to_json_string(&request).unwrap_or_default()
+            )),
         }
     }
 
@@ -651,15 +1945,17 @@ where
         block: &BlockInfo,
         msg: SudoMsg,
     ) -> AnyResult<AppResponse> {
-        match msg {
-            SudoMsg::Wasm(msg) => {
-                self.wasm
-                    .sudo(api, msg.contract_addr, storage, self, block, msg.msg)
-            }
-            SudoMsg::Bank(msg) => self.bank.sudo(api, storage, self, block, msg),
-            SudoMsg::Staking(msg) => self.staking.sudo(api, storage, self, block, msg),
-            SudoMsg::Custom(_) => unimplemented!(),
-        }
+        self.route_sudo(api, storage, block, msg, |_, msg| {
+            Err(Error::unsupported_sudo_message(SudoMsg::<Empty>::Custom(msg)).into())
+        })
+    }
+
+    fn tracer(&self) -> Option<&Tracer> {
+        self.tracer.as_deref()
+    }
+
+    fn transaction_index(&self) -> u32 {
+        self.transaction_index
     }
 }
 
@@ -757,10 +2053,23 @@ where
                 })
             }
         };
-        let contract_result: ContractResult<Binary> = self
+        let tracer = self.router.tracer();
+        if let Some(tracer) = tracer {
+            tracer.enter(TraceNode::new(
+                TraceKind::Query,
+                None,
+                query_target(&request),
+                String::from_utf8_lossy(bin_request),
+                None,
+            ));
+        }
+        let res = self
             .router
-            .query(self.api, self.storage, self.block_info, request)
-            .into();
+            .query(self.api, self.storage, self.block_info, request);
+        if let Some(tracer) = tracer {
+            tracer.exit_query(&res);
+        }
+        let contract_result: ContractResult<Binary> = res.into();
         SystemResult::Ok(contract_result)
     }
 }
@@ -1,9 +1,9 @@
 //!AppBuilder helps you set up your test blockchain environment step by step [App].
 
 use crate::{
-    App, Bank, BankKeeper, Distribution, DistributionKeeper, FailingModule, Gov, GovFailingModule,
-    Ibc, IbcFailingModule, Module, Router, StakeKeeper, Staking, Stargate, StargateFailingModule,
-    Wasm, WasmKeeper,
+    App, Bank, BankKeeper, Distribution, DistributionKeeper, DynApp, FailingModule, Gov,
+    GovFailingModule, Ibc, IbcFailingModule, Module, Router, StakeKeeper, Staking, Stargate,
+    StargateFailingModule, Wasm, WasmKeeper,
 };
 use cosmwasm_std::testing::{mock_env, MockApi, MockStorage};
 use cosmwasm_std::{Api, BlockInfo, CustomMsg, CustomQuery, Empty, Storage};
@@ -563,6 +563,9 @@ where
             ibc: self.ibc,
             gov: self.gov,
             stargate: self.stargate,
+            tracer: None,
+            registry: Default::default(),
+            transaction_index: 0,
         };
 
         let mut app = App {
@@ -570,8 +573,45 @@ where
             api: self.api,
             block: self.block,
             storage: self.storage,
+            block_production: None,
+            state_history: None,
+            tx_history: Default::default(),
+            execution_history: false,
+            block_history: None,
+            invariants: vec![],
         };
         app.init_modules(init_fn);
         app
     }
+
+    /// Builds final [DynApp] with all modules boxed, so the type of the application
+    /// depends only on custom messages. The initialization function still receives
+    /// the router with concrete modules, like the one passed to [build](Self::build).
+    ///
+    /// Unlike [build](Self::build), this requires the wasm module to implement [Clone],
+    /// because the boxed wasm module is cloned when taking [snapshots](App::snapshot)
+    /// of the [DynApp]. The default [WasmKeeper] is cloneable.
+    pub fn build_dyn<F>(self, init_fn: F) -> DynApp<CustomT::ExecT, CustomT::QueryT, CustomT::SudoT>
+    where
+        CustomT::ExecT: CustomMsg + DeserializeOwned + 'static,
+        CustomT::QueryT: CustomQuery + DeserializeOwned + 'static,
+        BankT: Bank + 'static,
+        ApiT: Api + 'static,
+        StorageT: Storage + 'static,
+        CustomT: Module + 'static,
+        WasmT: Wasm<CustomT::ExecT, CustomT::QueryT> + Clone + 'static,
+        StakingT: Staking + 'static,
+        DistrT: Distribution + 'static,
+        IbcT: Ibc + 'static,
+        GovT: Gov + 'static,
+        StargateT: Stargate + 'static,
+        F: FnOnce(
+            &mut Router<BankT, CustomT, WasmT, StakingT, DistrT, IbcT, GovT, StargateT>,
+            &dyn Api,
+            &mut dyn Storage,
+        ),
+    {
+        // the freshly built application has no invariants, so it can always be boxed
+        self.build(init_fn).into_dyn().unwrap()
+    }
 }
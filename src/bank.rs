@@ -1,24 +1,28 @@
 use crate::app::CosmosRouter;
-use crate::error::{bail, AnyResult};
+use crate::error::{anyhow, bail, AnyContext, AnyResult, Error};
 use crate::executor::AppResponse;
 use crate::module::Module;
 use crate::prefixed_storage::{prefixed, prefixed_read};
+#[cfg(feature = "cosmwasm_1_1")]
+use cosmwasm_std::SupplyResponse;
 use cosmwasm_std::{
     coin, to_json_binary, Addr, AllBalanceResponse, Api, BalanceResponse, BankMsg, BankQuery,
-    Binary, BlockInfo, Coin, Event, Querier, Storage,
+    Binary, BlockInfo, Coin, Event, Order, Querier, StdResult, Storage, Uint128,
 };
 #[cfg(feature = "cosmwasm_1_3")]
 use cosmwasm_std::{AllDenomMetadataResponse, DenomMetadata, DenomMetadataResponse};
-#[cfg(feature = "cosmwasm_1_1")]
-use cosmwasm_std::{Order, StdResult, SupplyResponse, Uint128};
 use cw_storage_plus::Map;
 use cw_utils::NativeBalance;
 use itertools::Itertools;
 use schemars::JsonSchema;
+use std::collections::BTreeMap;
 
 /// Collection of bank balances.
 const BALANCES: Map<&Addr, NativeBalance> = Map::new("balances");
 
+/// Total supply of every denomination, updated whenever a balance changes.
+const SUPPLY: Map<&str, Uint128> = Map::new("supply");
+
 /// Collection of metadata for denomination.
 #[cfg(feature = "cosmwasm_1_3")]
 const DENOM_METADATA: Map<String, DenomMetadata> = Map::new("metadata");
@@ -79,11 +83,40 @@ impl BankKeeper {
     ) -> AnyResult<()> {
         let mut balance = NativeBalance(amount);
         balance.normalize();
+        for coin in self.get_balance(bank_storage, account)? {
+            self.update_supply(bank_storage, &coin.denom, |supply| {
+                supply.saturating_sub(coin.amount)
+            })?;
+        }
+        for coin in balance.0.iter() {
+            self.update_supply(bank_storage, &coin.denom, |supply| {
+                supply.saturating_add(coin.amount)
+            })?;
+        }
         BALANCES
             .save(bank_storage, account, &balance)
             .map_err(Into::into)
     }
 
+    /// Updates the tracked supply of the denomination, removing it when it drops to zero.
+    fn update_supply<F>(
+        &self,
+        bank_storage: &mut dyn Storage,
+        denom: &str,
+        action: F,
+    ) -> AnyResult<()>
+    where
+        F: FnOnce(Uint128) -> Uint128,
+    {
+        let supply = action(SUPPLY.may_load(bank_storage, denom)?.unwrap_or_default());
+        if supply.is_zero() {
+            SUPPLY.remove(bank_storage, denom);
+        } else {
+            SUPPLY.save(bank_storage, denom, &supply)?;
+        }
+        Ok(())
+    }
+
     /// Administration function for adjusting denomination metadata.
     #[cfg(feature = "cosmwasm_1_3")]
     pub fn set_denom_metadata(
@@ -97,6 +130,41 @@ impl BankKeeper {
             .map_err(Into::into)
     }
 
+    /// Checks that the tracked supply of every denomination equals the sum of all balances
+    /// and that no account holds zero amount of any denomination.
+    pub(crate) fn check_supply(&self, storage: &dyn Storage) -> AnyResult<()> {
+        let bank_storage = prefixed_read(storage, NAMESPACE_BANK);
+        let mut supply: BTreeMap<String, Uint128> = BTreeMap::new();
+        for item in BALANCES.range(&bank_storage, None, None, Order::Ascending) {
+            let (account, balance) = item?;
+            for coin in balance.into_vec() {
+                if coin.amount.is_zero() {
+                    bail!("balance of {} holds zero amount of {}", account, coin.denom);
+                }
+                let total = supply.entry(coin.denom.clone()).or_default();
+                *total = total
+                    .checked_add(coin.amount)
+                    .map_err(|_| anyhow!("supply of {} overflows", coin.denom))?;
+            }
+        }
+        let tracked = SUPPLY
+            .range(&bank_storage, None, None, Order::Ascending)
+            .collect::<StdResult<BTreeMap<_, _>>>()?;
+        for denom in supply.keys().chain(tracked.keys()) {
+            let expected = supply.get(denom).copied().unwrap_or_default();
+            let actual = tracked.get(denom).copied().unwrap_or_default();
+            if actual != expected {
+                bail!(
+                    "supply of {} is {}, but balances sum up to {}",
+                    denom,
+                    actual,
+                    expected
+                );
+            }
+        }
+        Ok(())
+    }
+
     /// Returns balance for specified address.
     fn get_balance(&self, bank_storage: &dyn Storage, addr: &Addr) -> AnyResult<Vec<Coin>> {
         let val = BALANCES.may_load(bank_storage, addr)?;
@@ -153,7 +221,8 @@ impl BankKeeper {
     ) -> AnyResult<()> {
         let amount = self.normalize_amount(amount)?;
         let a = self.get_balance(bank_storage, &from_address)?;
-        let a = (NativeBalance(a) - amount)?;
+        let a = (NativeBalance(a) - amount.clone())
+            .context(Error::insufficient_funds(from_address.clone(), amount))?;
         self.set_balance(bank_storage, &from_address, a.into_vec())
     }
 
@@ -161,7 +230,7 @@ impl BankKeeper {
     fn normalize_amount(&self, amount: Vec<Coin>) -> AnyResult<Vec<Coin>> {
         let res: Vec<_> = amount.into_iter().filter(|x| !x.amount.is_zero()).collect();
         if res.is_empty() {
-            bail!("Cannot transfer empty coins amount")
+            bail!(Error::zero_amount())
         } else {
             Ok(res)
         }
@@ -212,7 +281,7 @@ impl Module for BankKeeper {
                 self.burn(&mut bank_storage, sender, amount)?;
                 Ok(AppResponse::default())
             }
-            other => unimplemented!("bank message: {other:?}"),
+            other => bail!(Error::unsupported_bank_message(other)),
         }
     }
 
@@ -263,7 +332,7 @@ impl Module for BankKeeper {
                 let res = AllDenomMetadataResponse::new(metadata, None);
                 to_json_binary(&res).map_err(Into::into)
             }
-            other => unimplemented!("bank query: {other:?}"),
+            other => bail!(Error::unsupported_bank_query(other)),
         }
     }
 
@@ -56,10 +56,14 @@ type PermissionedFn<T, C, E, Q> = fn(deps: DepsMut<Q>, env: Env, msg: T) -> Resu
 type ReplyFn<C, E, Q> = fn(deps: DepsMut<Q>, env: Env, msg: Reply) -> Result<Response<C>, E>;
 type QueryFn<T, E, Q> = fn(deps: Deps<Q>, env: Env, msg: T) -> Result<Binary, E>;
 
+/// Result of the entry-point returning a response. The outer error is reported when the response
+/// can not be converted to the custom message type, see [customize_response].
+type ClosureResult<C, E> = AnyResult<Result<Response<C>, E>>;
+
 type ContractClosure<T, C, E, Q> =
-    Box<dyn Fn(DepsMut<Q>, Env, MessageInfo, T) -> Result<Response<C>, E>>;
-type PermissionedClosure<T, C, E, Q> = Box<dyn Fn(DepsMut<Q>, Env, T) -> Result<Response<C>, E>>;
-type ReplyClosure<C, E, Q> = Box<dyn Fn(DepsMut<Q>, Env, Reply) -> Result<Response<C>, E>>;
+    Box<dyn Fn(DepsMut<Q>, Env, MessageInfo, T) -> ClosureResult<C, E>>;
+type PermissionedClosure<T, C, E, Q> = Box<dyn Fn(DepsMut<Q>, Env, T) -> ClosureResult<C, E>>;
+type ReplyClosure<C, E, Q> = Box<dyn Fn(DepsMut<Q>, Env, Reply) -> ClosureResult<C, E>>;
 type QueryClosure<T, E, Q> = Box<dyn Fn(Deps<Q>, Env, T) -> Result<Binary, E>>;
 
 /// Standardizes interactions with contracts in CosmWasm tests, especially useful for contracts that
@@ -120,8 +124,10 @@ where
         query_fn: QueryFn<T3, E3, Q>,
     ) -> Self {
         Self {
-            execute_fn: Box::new(execute_fn),
-            instantiate_fn: Box::new(instantiate_fn),
+            execute_fn: Box::new(move |deps, env, info, msg| Ok(execute_fn(deps, env, info, msg))),
+            instantiate_fn: Box::new(move |deps, env, info, msg| {
+                Ok(instantiate_fn(deps, env, info, msg))
+            }),
             query_fn: Box::new(query_fn),
             sudo_fn: None,
             reply_fn: None,
@@ -177,7 +183,7 @@ where
             execute_fn: self.execute_fn,
             instantiate_fn: self.instantiate_fn,
             query_fn: self.query_fn,
-            sudo_fn: Some(Box::new(sudo_fn)),
+            sudo_fn: Some(Box::new(move |deps, env, msg| Ok(sudo_fn(deps, env, msg)))),
             reply_fn: self.reply_fn,
             migrate_fn: self.migrate_fn,
         }
@@ -215,7 +221,7 @@ where
             instantiate_fn: self.instantiate_fn,
             query_fn: self.query_fn,
             sudo_fn: self.sudo_fn,
-            reply_fn: Some(Box::new(reply_fn)),
+            reply_fn: Some(Box::new(move |deps, env, msg| Ok(reply_fn(deps, env, msg)))),
             migrate_fn: self.migrate_fn,
         }
     }
@@ -253,7 +259,9 @@ where
             query_fn: self.query_fn,
             sudo_fn: self.sudo_fn,
             reply_fn: self.reply_fn,
-            migrate_fn: Some(Box::new(migrate_fn)),
+            migrate_fn: Some(Box::new(move |deps, env, msg| {
+                Ok(migrate_fn(deps, env, msg))
+            })),
         }
     }
 
@@ -284,14 +292,11 @@ where
     C: CustomMsg + 'static,
     Q: CustomQuery + DeserializeOwned + 'static,
 {
-    let customized = move |mut deps: DepsMut<Q>,
-                           env: Env,
-                           info: MessageInfo,
-                           msg: T|
-          -> Result<Response<C>, E> {
-        let deps = decustomize_deps_mut(&mut deps);
-        raw_fn(deps, env, info, msg).map(customize_response::<C>)
-    };
+    let customized =
+        move |mut deps: DepsMut<Q>, env: Env, info: MessageInfo, msg: T| -> ClosureResult<C, E> {
+            let deps = decustomize_deps_mut(&mut deps);
+            customize_result(raw_fn(deps, env, info, msg))
+        };
     Box::new(customized)
 }
 
@@ -339,43 +344,59 @@ where
     C: CustomMsg + 'static,
     Q: CustomQuery + DeserializeOwned + 'static,
 {
-    let customized = move |deps: DepsMut<Q>, env: Env, msg: T| -> Result<Response<C>, E> {
-        raw_fn(deps, env, msg).map(customize_response::<C>)
+    let customized = move |deps: DepsMut<Q>, env: Env, msg: T| -> ClosureResult<C, E> {
+        customize_result(raw_fn(deps, env, msg))
     };
     Box::new(customized)
 }
 
-fn customize_response<C>(resp: Response<Empty>) -> Response<C>
+fn customize_result<C, E>(result: Result<Response<Empty>, E>) -> ClosureResult<C, E>
 where
     C: CustomMsg,
 {
+    match result {
+        Ok(resp) => Ok(Ok(customize_response(resp)?)),
+        Err(err) => Ok(Err(err)),
+    }
+}
+
+fn customize_response<C>(resp: Response<Empty>) -> AnyResult<Response<C>>
+where
+    C: CustomMsg,
+{
+    let messages = resp
+        .messages
+        .into_iter()
+        .map(customize_msg::<C>)
+        .collect::<AnyResult<Vec<_>>>()?;
     let mut customized_resp = Response::<C>::new()
-        .add_submessages(resp.messages.into_iter().map(customize_msg::<C>))
+        .add_submessages(messages)
         .add_events(resp.events)
         .add_attributes(resp.attributes);
     customized_resp.data = resp.data;
-    customized_resp
+    Ok(customized_resp)
 }
 
-fn customize_msg<C>(msg: SubMsg<Empty>) -> SubMsg<C>
+fn customize_msg<C>(msg: SubMsg<Empty>) -> AnyResult<SubMsg<C>>
 where
     C: CustomMsg,
 {
-    SubMsg {
+    Ok(SubMsg {
         msg: match msg.msg {
             CosmosMsg::Wasm(wasm) => CosmosMsg::Wasm(wasm),
             CosmosMsg::Bank(bank) => CosmosMsg::Bank(bank),
             CosmosMsg::Staking(staking) => CosmosMsg::Staking(staking),
             CosmosMsg::Distribution(distribution) => CosmosMsg::Distribution(distribution),
-            CosmosMsg::Custom(_) => unreachable!(),
             CosmosMsg::Ibc(ibc) => CosmosMsg::Ibc(ibc),
+            CosmosMsg::Gov(gov) => CosmosMsg::Gov(gov),
             CosmosMsg::Stargate { type_url, value } => CosmosMsg::Stargate { type_url, value },
-            _ => panic!("unknown message variant {:?}", msg),
+            // custom messages of contracts returning `Empty` can not be converted
+            other => bail!(crate::error::Error::unsupported_message(other)),
         },
         id: msg.id,
         gas_limit: msg.gas_limit,
         reply_on: msg.reply_on,
-    }
+    })
 }
 
 impl<T1, T2, T3, E1, E2, E3, C, T4, E4, E5, T6, E6, Q> Contract<C, Q>
@@ -403,7 +424,7 @@ where
         msg: Vec<u8>,
     ) -> AnyResult<Response<C>> {
         let msg: T1 = from_json(msg)?;
-        (self.execute_fn)(deps, env, info, msg).map_err(|err| anyhow!(err))
+        (self.execute_fn)(deps, env, info, msg)?.map_err(|err| anyhow!(err))
     }
 
     fn instantiate(
@@ -414,7 +435,7 @@ where
         msg: Vec<u8>,
     ) -> AnyResult<Response<C>> {
         let msg: T2 = from_json(msg)?;
-        (self.instantiate_fn)(deps, env, info, msg).map_err(|err| anyhow!(err))
+        (self.instantiate_fn)(deps, env, info, msg)?.map_err(|err| anyhow!(err))
     }
 
     fn query(&self, deps: Deps<Q>, env: Env, msg: Vec<u8>) -> AnyResult<Binary> {
@@ -426,7 +447,7 @@ where
     fn sudo(&self, deps: DepsMut<Q>, env: Env, msg: Vec<u8>) -> AnyResult<Response<C>> {
         let msg = from_json(msg)?;
         match &self.sudo_fn {
-            Some(sudo) => sudo(deps, env, msg).map_err(|err| anyhow!(err)),
+            Some(sudo) => sudo(deps, env, msg)?.map_err(|err| anyhow!(err)),
             None => bail!("sudo not implemented for contract"),
         }
     }
@@ -434,7 +455,7 @@ where
     // this returns an error if the contract doesn't implement reply
     fn reply(&self, deps: DepsMut<Q>, env: Env, reply_data: Reply) -> AnyResult<Response<C>> {
         match &self.reply_fn {
-            Some(reply) => reply(deps, env, reply_data).map_err(|err| anyhow!(err)),
+            Some(reply) => reply(deps, env, reply_data)?.map_err(|err| anyhow!(err)),
             None => bail!("reply not implemented for contract"),
         }
     }
@@ -443,7 +464,7 @@ where
     fn migrate(&self, deps: DepsMut<Q>, env: Env, msg: Vec<u8>) -> AnyResult<Response<C>> {
         let msg = from_json(msg)?;
         match &self.migrate_fn {
-            Some(migrate) => migrate(deps, env, msg).map_err(|err| anyhow!(err)),
+            Some(migrate) => migrate(deps, env, msg)?.map_err(|err| anyhow!(err)),
             None => bail!("migrate not implemented for contract"),
         }
     }
@@ -0,0 +1,111 @@
+//! State diff, grouping changes of the storage by module namespace and by contract address.
+
+use crate::transactions::StorageChange;
+use crate::wasm::{CONTRACT_NAMESPACE_PREFIX, NAMESPACE_WASM};
+use cosmwasm_std::Binary;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Changes of the storage made by messages, see [App::execute_multi_with_diff](crate::App::execute_multi_with_diff).
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct StateDiff {
+    /// Changes of the modules' storage, by module namespace, like `bank` or `staking`.
+    /// Changes of keys outside any namespace are grouped under an empty namespace.
+    pub modules: BTreeMap<String, Vec<KeyChange>>,
+    /// Changes of the contracts' storage, by contract address.
+    pub contracts: BTreeMap<String, Vec<KeyChange>>,
+}
+
+/// Change of a single key-value pair in the storage of a module or a contract, see [StateDiff].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct KeyChange {
+    /// Changed key, relative to the storage of the module or the contract.
+    pub key: Binary,
+    /// Value before the change, `None` when the key was added.
+    pub old_value: Option<Binary>,
+    /// Value after the change, `None` when the key was removed.
+    pub new_value: Option<Binary>,
+}
+
+/// Key decoded as a `cw-storage-plus` key, see [KeyChange::decode_key].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct DecodedKey {
+    /// Namespace of the `Item`, `Map` or other storage type holding the key.
+    pub namespace: String,
+    /// Components of the key within the namespace, empty for an `Item`.
+    pub components: Vec<Binary>,
+}
+
+impl StateDiff {
+    /// Groups changes of the root storage by module namespace and by contract address.
+    pub(crate) fn new(changes: Vec<StorageChange>) -> Self {
+        let mut diff = StateDiff::default();
+        for change in changes {
+            let (namespace, key) = split_namespace(change.key.as_slice());
+            let (group, name, key) = match (namespace, key) {
+                (Some(NAMESPACE_WASM), key) => match split_namespace(key) {
+                    (Some(namespace), key) if namespace.starts_with(CONTRACT_NAMESPACE_PREFIX) => {
+                        let address = &namespace[CONTRACT_NAMESPACE_PREFIX.len()..];
+                        (&mut diff.contracts, address, key)
+                    }
+                    _ => (&mut diff.modules, NAMESPACE_WASM, key),
+                },
+                (Some(namespace), key) => (&mut diff.modules, namespace, key),
+                (None, key) => (&mut diff.modules, &b""[..], key),
+            };
+            group
+                .entry(String::from_utf8_lossy(name).to_string())
+                .or_default()
+                .push(KeyChange {
+                    key: key.to_vec().into(),
+                    old_value: change.old_value,
+                    new_value: change.new_value,
+                });
+        }
+        diff
+    }
+
+    /// Returns `true` when nothing has changed.
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty() && self.contracts.is_empty()
+    }
+}
+
+impl KeyChange {
+    /// Decodes the key as a `cw-storage-plus` key: length-prefixed namespace and key components,
+    /// with the last component not prefixed. Keys of an `Item` are not prefixed at all.
+    pub fn decode_key(&self) -> DecodedKey {
+        let mut components = vec![];
+        let mut rest = self.key.as_slice();
+        while let (Some(component), remaining) = split_namespace(rest) {
+            if remaining.is_empty() {
+                break;
+            }
+            components.push(component);
+            rest = remaining;
+        }
+        components.push(rest);
+        let namespace = String::from_utf8_lossy(components.remove(0)).to_string();
+        DecodedKey {
+            namespace,
+            components: components
+                .into_iter()
+                .map(|component| component.to_vec().into())
+                .collect(),
+        }
+    }
+}
+
+/// Splits the key into the length-prefixed namespace and the rest of the key.
+/// Returns no namespace when the key does not start with a valid length prefix.
+fn split_namespace(key: &[u8]) -> (Option<&[u8]>, &[u8]) {
+    if key.len() < 2 {
+        return (None, key);
+    }
+    let len = u16::from_be_bytes([key[0], key[1]]) as usize;
+    if len == 0 || key.len() < 2 + len {
+        return (None, key);
+    }
+    (Some(&key[2..2 + len]), &key[2 + len..])
+}
@@ -0,0 +1,1020 @@
+//! Type-erased application, see [DynApp].
+//!
+//! All modules, the API and the storage of the [DynApp] are boxed behind object-safe traits,
+//! so the application has a nameable type, depending only on custom messages.
+
+use crate::app::CosmosRouter;
+use crate::error::{bail, AnyResult, Error};
+use crate::{
+    App, AppResponse, Bank, BankKeeper, BankSudo, Contract, ContractData, Distribution, Gov, Ibc,
+    Module, Router, StakeKeeper, Staking, StakingSudo, Stargate, StargateMsg, StargateQuery,
+    SudoMsg, Tracer, Wasm, WasmKeeper,
+};
+use cosmwasm_std::{
+    from_json, to_json_string, to_json_vec, Addr, Api, BankMsg, BankQuery, Binary, BlockInfo,
+    CanonicalAddr, CosmosMsg, CustomMsg, CustomQuery, DistributionMsg, Empty, GovMsg, IbcMsg,
+    IbcQuery, Order, Querier, QueryRequest, Record, RecoverPubkeyError, StakingMsg, StakingQuery,
+    StdResult, Storage, VerificationError, WasmMsg, WasmQuery,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::any::Any;
+use std::marker::PhantomData;
+
+/// Application with type-erased modules, built with [AppBuilder::build_dyn](crate::AppBuilder::build_dyn).
+///
+/// Unlike the [App] with ten type parameters, the type of this application depends only
+/// on custom messages, so it can be easily named in test fixtures and helper functions.
+///
+/// Concrete modules are accessible by downcasting, e.g. `app.router().bank.downcast_ref::<BankKeeper>()`.
+///
+/// # Example
+///
+/// ```
+/// use cosmwasm_std::{coin, Addr};
+/// use cw_multi_test::{no_init, AppBuilder, DynApp};
+///
+/// fn balance(app: &DynApp, address: &Addr) -> u128 {
+///     app.wrap().query_balance(address, "LUNA").unwrap().amount.u128()
+/// }
+///
+/// let owner = Addr::unchecked("owner");
+/// let app = AppBuilder::default().build_dyn(|router, _, storage| {
+///     router
+///         .bank
+///         .init_balance(storage, &owner, vec![coin(100, "LUNA")])
+///         .unwrap();
+/// });
+/// assert_eq!(100, balance(&app, &owner));
+/// ```
+pub type DynApp<ExecC = Empty, QueryC = Empty, SudoC = Empty> = App<
+    BoxedModule<ExecC, QueryC, BankMsg, BankQuery, BankSudo>,
+    BoxedApi,
+    BoxedStorage,
+    BoxedModule<ExecC, QueryC, ExecC, QueryC, SudoC>,
+    BoxedWasm<ExecC, QueryC>,
+    BoxedStaking<ExecC, QueryC>,
+    BoxedModule<ExecC, QueryC, DistributionMsg, Empty, Empty>,
+    BoxedModule<ExecC, QueryC, IbcMsg, IbcQuery, Empty>,
+    BoxedModule<ExecC, QueryC, GovMsg, Empty, Empty>,
+    BoxedModule<ExecC, QueryC, StargateMsg, StargateQuery, Empty>,
+>;
+
+/// Object-safe version of the [Module] trait, with custom messages of the router fixed.
+///
+/// Implemented for all modules.
+pub trait DynModule<ExecC, QueryC> {
+    /// Type of messages processed by the module instance.
+    type ExecT;
+    /// Type of queries processed by the module instance.
+    type QueryT;
+    /// Type of privileged messages used by the module instance.
+    type SudoT;
+
+    /// Runs any [ExecT](Self::ExecT) message, see [Module::execute].
+    fn execute(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        sender: Addr,
+        msg: Self::ExecT,
+    ) -> AnyResult<AppResponse>;
+
+    /// Runs any [QueryT](Self::QueryT) message, see [Module::query].
+    fn query(
+        &self,
+        api: &dyn Api,
+        storage: &dyn Storage,
+        querier: &dyn Querier,
+        block: &BlockInfo,
+        request: Self::QueryT,
+    ) -> AnyResult<Binary>;
+
+    /// Runs privileged actions, see [Module::sudo].
+    fn sudo(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        msg: Self::SudoT,
+    ) -> AnyResult<AppResponse>;
+
+    /// Called at the beginning of every block, see [Module::begin_block].
+    fn begin_block(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+    ) -> AnyResult<AppResponse>;
+
+    /// Called at the end of every block, see [Module::end_block].
+    fn end_block(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+    ) -> AnyResult<AppResponse>;
+
+    /// Returns the module for downcasting to its concrete type.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<M, ExecC, QueryC> DynModule<ExecC, QueryC> for M
+where
+    M: Module + 'static,
+    ExecC: CustomMsg + DeserializeOwned + 'static,
+    QueryC: CustomQuery + DeserializeOwned + 'static,
+{
+    type ExecT = M::ExecT;
+    type QueryT = M::QueryT;
+    type SudoT = M::SudoT;
+
+    fn execute(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        sender: Addr,
+        msg: Self::ExecT,
+    ) -> AnyResult<AppResponse> {
+        Module::execute(self, api, storage, router, block, sender, msg)
+    }
+
+    fn query(
+        &self,
+        api: &dyn Api,
+        storage: &dyn Storage,
+        querier: &dyn Querier,
+        block: &BlockInfo,
+        request: Self::QueryT,
+    ) -> AnyResult<Binary> {
+        Module::query(self, api, storage, querier, block, request)
+    }
+
+    fn sudo(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        msg: Self::SudoT,
+    ) -> AnyResult<AppResponse> {
+        Module::sudo(self, api, storage, router, block, msg)
+    }
+
+    fn begin_block(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+    ) -> AnyResult<AppResponse> {
+        Module::begin_block(self, api, storage, router, block)
+    }
+
+    fn end_block(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+    ) -> AnyResult<AppResponse> {
+        Module::end_block(self, api, storage, router, block)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Object-safe version of the [Staking] trait, with custom messages of the router fixed.
+///
+/// Implemented for all staking modules.
+pub trait DynStaking<ExecC, QueryC>:
+    DynModule<ExecC, QueryC, ExecT = StakingMsg, QueryT = StakingQuery, SudoT = StakingSudo>
+{
+    /// Processes the staking queue, see [Staking::process_queue].
+    fn process_queue(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+    ) -> AnyResult<AppResponse>;
+}
+
+impl<S, ExecC, QueryC> DynStaking<ExecC, QueryC> for S
+where
+    S: Staking + 'static,
+    ExecC: CustomMsg + DeserializeOwned + 'static,
+    QueryC: CustomQuery + DeserializeOwned + 'static,
+{
+    fn process_queue(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+    ) -> AnyResult<AppResponse> {
+        Staking::process_queue(self, api, storage, router, block)
+    }
+}
+
+/// Extension of the [Wasm] trait, allowing to clone and downcast the boxed wasm module.
+///
+/// Implemented for all cloneable wasm modules.
+pub trait DynWasm<ExecC, QueryC>: Wasm<ExecC, QueryC> {
+    /// Clones the module into a new box.
+    fn clone_box(&self) -> Box<dyn DynWasm<ExecC, QueryC>>;
+
+    /// Returns the module for downcasting to its concrete type.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Returns the module for mutable downcasting to its concrete type.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<W, ExecC, QueryC> DynWasm<ExecC, QueryC> for W
+where
+    W: Wasm<ExecC, QueryC> + Clone + 'static,
+{
+    fn clone_box(&self) -> Box<dyn DynWasm<ExecC, QueryC>> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Extension of the [Api] trait, allowing to downcast the boxed API.
+///
+/// Implemented for all APIs.
+pub trait DynApi: Api {
+    /// Returns the API for downcasting to its concrete type.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<A: Api + 'static> DynApi for A {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Extension of the [Storage] trait, allowing to downcast the boxed storage.
+///
+/// Implemented for all storages.
+pub trait DynStorage: Storage {
+    /// Returns the storage for downcasting to its concrete type.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Returns the storage for mutable downcasting to its concrete type.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<S: Storage + 'static> DynStorage for S {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Boxed module of the [DynApp].
+pub struct BoxedModule<ExecC, QueryC, ExecT, QueryT, SudoT>(
+    Box<dyn DynModule<ExecC, QueryC, ExecT = ExecT, QueryT = QueryT, SudoT = SudoT>>,
+);
+
+impl<ExecC, QueryC, ExecT, QueryT, SudoT> BoxedModule<ExecC, QueryC, ExecT, QueryT, SudoT> {
+    /// Boxes the module.
+    pub fn new(
+        module: impl DynModule<ExecC, QueryC, ExecT = ExecT, QueryT = QueryT, SudoT = SudoT> + 'static,
+    ) -> Self {
+        Self(Box::new(module))
+    }
+
+    /// Returns a reference to the boxed module, when it has the specified type.
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        self.0.as_any().downcast_ref()
+    }
+}
+
+impl<ExecC, QueryC, ExecT, QueryT, SudoT> Module
+    for BoxedModule<ExecC, QueryC, ExecT, QueryT, SudoT>
+where
+    ExecC: CustomMsg + 'static,
+    QueryC: CustomQuery + 'static,
+{
+    type ExecT = ExecT;
+    type QueryT = QueryT;
+    type SudoT = SudoT;
+
+    fn execute<RExecC, RQueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = RExecC, QueryC = RQueryC>,
+        block: &BlockInfo,
+        sender: Addr,
+        msg: Self::ExecT,
+    ) -> AnyResult<AppResponse>
+    where
+        RExecC: CustomMsg + DeserializeOwned + 'static,
+        RQueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        let router = RouterAdapter::new(router);
+        self.0.execute(api, storage, &router, block, sender, msg)
+    }
+
+    fn query(
+        &self,
+        api: &dyn Api,
+        storage: &dyn Storage,
+        querier: &dyn Querier,
+        block: &BlockInfo,
+        request: Self::QueryT,
+    ) -> AnyResult<Binary> {
+        self.0.query(api, storage, querier, block, request)
+    }
+
+    fn sudo<RExecC, RQueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = RExecC, QueryC = RQueryC>,
+        block: &BlockInfo,
+        msg: Self::SudoT,
+    ) -> AnyResult<AppResponse>
+    where
+        RExecC: CustomMsg + DeserializeOwned + 'static,
+        RQueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        let router = RouterAdapter::new(router);
+        self.0.sudo(api, storage, &router, block, msg)
+    }
+
+    fn begin_block<RExecC, RQueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = RExecC, QueryC = RQueryC>,
+        block: &BlockInfo,
+    ) -> AnyResult<AppResponse>
+    where
+        RExecC: CustomMsg + DeserializeOwned + 'static,
+        RQueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        let router = RouterAdapter::new(router);
+        self.0.begin_block(api, storage, &router, block)
+    }
+
+    fn end_block<RExecC, RQueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = RExecC, QueryC = RQueryC>,
+        block: &BlockInfo,
+    ) -> AnyResult<AppResponse>
+    where
+        RExecC: CustomMsg + DeserializeOwned + 'static,
+        RQueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        let router = RouterAdapter::new(router);
+        self.0.end_block(api, storage, &router, block)
+    }
+}
+
+impl<ExecC, QueryC> Bank for BoxedModule<ExecC, QueryC, BankMsg, BankQuery, BankSudo>
+where
+    ExecC: CustomMsg + 'static,
+    QueryC: CustomQuery + 'static,
+{
+}
+
+impl<ExecC, QueryC> Distribution for BoxedModule<ExecC, QueryC, DistributionMsg, Empty, Empty>
+where
+    ExecC: CustomMsg + 'static,
+    QueryC: CustomQuery + 'static,
+{
+}
+
+impl<ExecC, QueryC> Ibc for BoxedModule<ExecC, QueryC, IbcMsg, IbcQuery, Empty>
+where
+    ExecC: CustomMsg + 'static,
+    QueryC: CustomQuery + 'static,
+{
+}
+
+impl<ExecC, QueryC> Gov for BoxedModule<ExecC, QueryC, GovMsg, Empty, Empty>
+where
+    ExecC: CustomMsg + 'static,
+    QueryC: CustomQuery + 'static,
+{
+}
+
+impl<ExecC, QueryC> Stargate for BoxedModule<ExecC, QueryC, StargateMsg, StargateQuery, Empty>
+where
+    ExecC: CustomMsg + 'static,
+    QueryC: CustomQuery + 'static,
+{
+}
+
+/// Boxed staking module of the [DynApp].
+pub struct BoxedStaking<ExecC, QueryC>(Box<dyn DynStaking<ExecC, QueryC>>);
+
+impl<ExecC, QueryC> BoxedStaking<ExecC, QueryC> {
+    /// Boxes the staking module.
+    pub fn new(staking: impl DynStaking<ExecC, QueryC> + 'static) -> Self {
+        Self(Box::new(staking))
+    }
+
+    /// Returns a reference to the boxed module, when it has the specified type.
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        self.0.as_any().downcast_ref()
+    }
+}
+
+impl<ExecC, QueryC> Module for BoxedStaking<ExecC, QueryC>
+where
+    ExecC: CustomMsg + 'static,
+    QueryC: CustomQuery + 'static,
+{
+    type ExecT = StakingMsg;
+    type QueryT = StakingQuery;
+    type SudoT = StakingSudo;
+
+    fn execute<RExecC, RQueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = RExecC, QueryC = RQueryC>,
+        block: &BlockInfo,
+        sender: Addr,
+        msg: Self::ExecT,
+    ) -> AnyResult<AppResponse>
+    where
+        RExecC: CustomMsg + DeserializeOwned + 'static,
+        RQueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        let router = RouterAdapter::new(router);
+        self.0.execute(api, storage, &router, block, sender, msg)
+    }
+
+    fn query(
+        &self,
+        api: &dyn Api,
+        storage: &dyn Storage,
+        querier: &dyn Querier,
+        block: &BlockInfo,
+        request: Self::QueryT,
+    ) -> AnyResult<Binary> {
+        self.0.query(api, storage, querier, block, request)
+    }
+
+    fn sudo<RExecC, RQueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = RExecC, QueryC = RQueryC>,
+        block: &BlockInfo,
+        msg: Self::SudoT,
+    ) -> AnyResult<AppResponse>
+    where
+        RExecC: CustomMsg + DeserializeOwned + 'static,
+        RQueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        let router = RouterAdapter::new(router);
+        self.0.sudo(api, storage, &router, block, msg)
+    }
+
+    fn begin_block<RExecC, RQueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = RExecC, QueryC = RQueryC>,
+        block: &BlockInfo,
+    ) -> AnyResult<AppResponse>
+    where
+        RExecC: CustomMsg + DeserializeOwned + 'static,
+        RQueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        let router = RouterAdapter::new(router);
+        self.0.begin_block(api, storage, &router, block)
+    }
+
+    fn end_block<RExecC, RQueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = RExecC, QueryC = RQueryC>,
+        block: &BlockInfo,
+    ) -> AnyResult<AppResponse>
+    where
+        RExecC: CustomMsg + DeserializeOwned + 'static,
+        RQueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        let router = RouterAdapter::new(router);
+        self.0.end_block(api, storage, &router, block)
+    }
+}
+
+impl<ExecC, QueryC> Staking for BoxedStaking<ExecC, QueryC>
+where
+    ExecC: CustomMsg + 'static,
+    QueryC: CustomQuery + 'static,
+{
+    fn process_queue<RExecC, RQueryC: CustomQuery>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = RExecC, QueryC = RQueryC>,
+        block: &BlockInfo,
+    ) -> AnyResult<AppResponse> {
+        let router = QueueRouterAdapter::new(router);
+        self.0.process_queue(api, storage, &router, block)
+    }
+}
+
+/// Boxed wasm module of the [DynApp].
+pub struct BoxedWasm<ExecC, QueryC>(Box<dyn DynWasm<ExecC, QueryC>>);
+
+impl<ExecC, QueryC> BoxedWasm<ExecC, QueryC> {
+    /// Boxes the wasm module.
+    pub fn new(wasm: impl DynWasm<ExecC, QueryC> + 'static) -> Self {
+        Self(Box::new(wasm))
+    }
+
+    /// Returns a reference to the boxed module, when it has the specified type.
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        self.0.as_any().downcast_ref()
+    }
+
+    /// Returns a mutable reference to the boxed module, when it has the specified type.
+    pub fn downcast_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.0.as_any_mut().downcast_mut()
+    }
+}
+
+impl<ExecC, QueryC> Clone for BoxedWasm<ExecC, QueryC> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone_box())
+    }
+}
+
+impl<ExecC, QueryC> Wasm<ExecC, QueryC> for BoxedWasm<ExecC, QueryC> {
+    fn query(
+        &self,
+        api: &dyn Api,
+        storage: &dyn Storage,
+        querier: &dyn Querier,
+        block: &BlockInfo,
+        request: WasmQuery,
+    ) -> AnyResult<Binary> {
+        self.0.query(api, storage, querier, block, request)
+    }
+
+    fn execute(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        sender: Addr,
+        msg: WasmMsg,
+    ) -> AnyResult<AppResponse> {
+        self.0.execute(api, storage, router, block, sender, msg)
+    }
+
+    fn sudo(
+        &self,
+        api: &dyn Api,
+        contract_addr: Addr,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        msg: Binary,
+    ) -> AnyResult<AppResponse> {
+        self.0.sudo(api, contract_addr, storage, router, block, msg)
+    }
+
+    fn store_code(&mut self, creator: Addr, code: Box<dyn Contract<ExecC, QueryC>>) -> u64 {
+        self.0.store_code(creator, code)
+    }
+
+    fn duplicate_code(&mut self, code_id: u64) -> AnyResult<u64> {
+        self.0.duplicate_code(code_id)
+    }
+
+    fn contract_data(&self, storage: &dyn Storage, address: &Addr) -> AnyResult<ContractData> {
+        self.0.contract_data(storage, address)
+    }
+
+    fn dump_wasm_raw(&self, storage: &dyn Storage, address: &Addr) -> Vec<Record> {
+        self.0.dump_wasm_raw(storage, address)
+    }
+}
+
+/// Boxed API of the [DynApp].
+pub struct BoxedApi(Box<dyn DynApi>);
+
+impl BoxedApi {
+    /// Boxes the API.
+    pub fn new(api: impl Api + 'static) -> Self {
+        Self(Box::new(api))
+    }
+
+    /// Returns a reference to the boxed API, when it has the specified type.
+    ///
+    /// Useful for calling methods of the concrete API, like [MockApi::addr_make](cosmwasm_std::testing::MockApi::addr_make).
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        self.0.as_any().downcast_ref()
+    }
+}
+
+impl Api for BoxedApi {
+    fn addr_validate(&self, human: &str) -> StdResult<Addr> {
+        self.0.addr_validate(human)
+    }
+
+    fn addr_canonicalize(&self, human: &str) -> StdResult<CanonicalAddr> {
+        self.0.addr_canonicalize(human)
+    }
+
+    fn addr_humanize(&self, canonical: &CanonicalAddr) -> StdResult<Addr> {
+        self.0.addr_humanize(canonical)
+    }
+
+    fn secp256k1_verify(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<bool, VerificationError> {
+        self.0.secp256k1_verify(message_hash, signature, public_key)
+    }
+
+    fn secp256k1_recover_pubkey(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        recovery_param: u8,
+    ) -> Result<Vec<u8>, RecoverPubkeyError> {
+        self.0
+            .secp256k1_recover_pubkey(message_hash, signature, recovery_param)
+    }
+
+    fn ed25519_verify(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<bool, VerificationError> {
+        self.0.ed25519_verify(message, signature, public_key)
+    }
+
+    fn ed25519_batch_verify(
+        &self,
+        messages: &[&[u8]],
+        signatures: &[&[u8]],
+        public_keys: &[&[u8]],
+    ) -> Result<bool, VerificationError> {
+        self.0
+            .ed25519_batch_verify(messages, signatures, public_keys)
+    }
+
+    fn debug(&self, message: &str) {
+        self.0.debug(message)
+    }
+}
+
+/// Boxed storage of the [DynApp].
+pub struct BoxedStorage(Box<dyn DynStorage>);
+
+impl BoxedStorage {
+    /// Boxes the storage.
+    pub fn new(storage: impl Storage + 'static) -> Self {
+        Self(Box::new(storage))
+    }
+
+    /// Returns a reference to the boxed storage, when it has the specified type.
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        self.0.as_any().downcast_ref()
+    }
+
+    /// Returns a mutable reference to the boxed storage, when it has the specified type.
+    pub fn downcast_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.0.as_any_mut().downcast_mut()
+    }
+}
+
+impl Storage for BoxedStorage {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.0.get(key)
+    }
+
+    fn range<'a>(
+        &'a self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'a> {
+        self.0.range(start, end, order)
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.0.set(key, value)
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.0.remove(key)
+    }
+}
+
+/// Router passed to boxed modules, when they are called with the router of any custom messages.
+///
+/// Boxed modules are called only by the router of the [DynApp], having the same custom messages,
+/// so messages and queries are converted without loss by serializing them.
+struct RouterAdapter<'a, ExecC, QueryC, RExecC, RQueryC> {
+    router: &'a dyn CosmosRouter<ExecC = RExecC, QueryC = RQueryC>,
+    _marker: PhantomData<(ExecC, QueryC)>,
+}
+
+impl<'a, ExecC, QueryC, RExecC, RQueryC> RouterAdapter<'a, ExecC, QueryC, RExecC, RQueryC> {
+    fn new(router: &'a dyn CosmosRouter<ExecC = RExecC, QueryC = RQueryC>) -> Self {
+        Self {
+            router,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, ExecC, QueryC, RExecC, RQueryC> CosmosRouter
+    for RouterAdapter<'a, ExecC, QueryC, RExecC, RQueryC>
+where
+    ExecC: CustomMsg,
+    QueryC: CustomQuery,
+    RExecC: DeserializeOwned,
+    RQueryC: CustomQuery + DeserializeOwned,
+{
+    type ExecC = ExecC;
+    type QueryC = QueryC;
+
+    fn execute(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        sender: Addr,
+        msg: CosmosMsg<ExecC>,
+    ) -> AnyResult<AppResponse> {
+        let msg = convert(&msg)?;
+        self.router.execute(api, storage, block, sender, msg)
+    }
+
+    fn query(
+        &self,
+        api: &dyn Api,
+        storage: &dyn Storage,
+        block: &BlockInfo,
+        request: QueryRequest<QueryC>,
+    ) -> AnyResult<Binary> {
+        let request = convert(&request)?;
+        self.router.query(api, storage, block, request)
+    }
+
+    fn sudo(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        msg: SudoMsg,
+    ) -> AnyResult<AppResponse> {
+        self.router.sudo(api, storage, block, msg)
+    }
+
+    fn tracer(&self) -> Option<&Tracer> {
+        self.router.tracer()
+    }
+
+    fn transaction_index(&self) -> u32 {
+        self.router.transaction_index()
+    }
+}
+
+/// Router passed to the boxed staking module, when it processes the queue.
+///
+/// [Staking::process_queue] does not require custom messages of the router to be deserializable,
+/// so only messages and queries other than custom ones are forwarded to the router.
+struct QueueRouterAdapter<'a, ExecC, QueryC, RExecC, RQueryC> {
+    router: &'a dyn CosmosRouter<ExecC = RExecC, QueryC = RQueryC>,
+    _marker: PhantomData<(ExecC, QueryC)>,
+}
+
+impl<'a, ExecC, QueryC, RExecC, RQueryC> QueueRouterAdapter<'a, ExecC, QueryC, RExecC, RQueryC> {
+    fn new(router: &'a dyn CosmosRouter<ExecC = RExecC, QueryC = RQueryC>) -> Self {
+        Self {
+            router,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, ExecC, QueryC, RExecC, RQueryC> CosmosRouter
+    for QueueRouterAdapter<'a, ExecC, QueryC, RExecC, RQueryC>
+where
+    ExecC: CustomMsg,
+    QueryC: CustomQuery,
+    RQueryC: CustomQuery,
+{
+    type ExecC = ExecC;
+    type QueryC = QueryC;
+
+    fn execute(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        sender: Addr,
+        msg: CosmosMsg<ExecC>,
+    ) -> AnyResult<AppResponse> {
+        let msg = match msg {
+            CosmosMsg::Wasm(wasm) => CosmosMsg::Wasm(wasm),
+            CosmosMsg::Bank(bank) => CosmosMsg::Bank(bank),
+            CosmosMsg::Staking(staking) => CosmosMsg::Staking(staking),
+            CosmosMsg::Distribution(distribution) => CosmosMsg::Distribution(distribution),
+            CosmosMsg::Ibc(ibc) => CosmosMsg::Ibc(ibc),
+            CosmosMsg::Gov(gov) => CosmosMsg::Gov(gov),
+            CosmosMsg::Stargate { type_url, value } => CosmosMsg::Stargate { type_url, value },
+            other => bail!(Error::unsupported_message(other)),
+        };
+        self.router.execute(api, storage, block, sender, msg)
+    }
+
+    fn query(
+        &self,
+        api: &dyn Api,
+        storage: &dyn Storage,
+        block: &BlockInfo,
+        request: QueryRequest<QueryC>,
+    ) -> AnyResult<Binary> {
+        let request = match request {
+            QueryRequest::Wasm(wasm) => QueryRequest::Wasm(wasm),
+            QueryRequest::Bank(bank) => QueryRequest::Bank(bank),
+            QueryRequest::Staking(staking) => QueryRequest::Staking(staking),
+            #[cfg(feature = "cosmwasm_1_3")]
+            QueryRequest::Distribution(distribution) => QueryRequest::Distribution(distribution),
+            QueryRequest::Ibc(ibc) => QueryRequest::Ibc(ibc),
+            QueryRequest::Stargate { path, data } => QueryRequest::Stargate { path, data },
+            other => bail!(Error::unsupported_query(
+                to_json_string(&other).unwrap_or_default()
+            )),
+        };
+        self.router.query(api, storage, block, request)
+    }
+
+    fn sudo(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        msg: SudoMsg,
+    ) -> AnyResult<AppResponse> {
+        self.router.sudo(api, storage, block, msg)
+    }
+
+    fn tracer(&self) -> Option<&Tracer> {
+        self.router.tracer()
+    }
+
+    fn transaction_index(&self) -> u32 {
+        self.router.transaction_index()
+    }
+}
+
+/// Converts a value into the value of another type with the same serialized form.
+fn convert<T: Serialize, U: DeserializeOwned>(value: &T) -> AnyResult<U> {
+    Ok(from_json(to_json_vec(value)?)?)
+}
+
+impl<BankT, ApiT, StorageT, CustomT, WasmT, StakingT, DistrT, IbcT, GovT, StargateT>
+    App<BankT, ApiT, StorageT, CustomT, WasmT, StakingT, DistrT, IbcT, GovT, StargateT>
+where
+    CustomT::ExecT: CustomMsg + DeserializeOwned + 'static,
+    CustomT::QueryT: CustomQuery + DeserializeOwned + 'static,
+    WasmT: Wasm<CustomT::ExecT, CustomT::QueryT> + Clone + 'static,
+    BankT: Bank + 'static,
+    ApiT: Api + 'static,
+    StorageT: Storage + 'static,
+    CustomT: Module + 'static,
+    StakingT: Staking + 'static,
+    DistrT: Distribution + 'static,
+    IbcT: Ibc + 'static,
+    GovT: Gov + 'static,
+    StargateT: Stargate + 'static,
+{
+    /// Boxes all modules, the API and the storage of the application.
+    ///
+    /// Invariants are registered for the concrete type of the application and can not
+    /// be checked for the boxed one, so this fails when any invariant is registered.
+    pub(crate) fn into_dyn(
+        self,
+    ) -> AnyResult<DynApp<CustomT::ExecT, CustomT::QueryT, CustomT::SudoT>> {
+        if let Some((name, _)) = self.invariants.first() {
+            bail!(
+                "invariant '{}' is registered for the concrete application and can not be boxed",
+                name
+            );
+        }
+        let Router {
+            wasm,
+            bank,
+            custom,
+            staking,
+            distribution,
+            ibc,
+            gov,
+            stargate,
+            tracer,
+            registry,
+            transaction_index,
+        } = self.router;
+        Ok(App {
+            router: Router {
+                wasm: BoxedWasm::new(wasm),
+                bank: BoxedModule::new(bank),
+                custom: BoxedModule::new(custom),
+                staking: BoxedStaking::new(staking),
+                distribution: BoxedModule::new(distribution),
+                ibc: BoxedModule::new(ibc),
+                gov: BoxedModule::new(gov),
+                stargate: BoxedModule::new(stargate),
+                tracer,
+                registry,
+                transaction_index,
+            },
+            api: BoxedApi::new(self.api),
+            storage: BoxedStorage::new(self.storage),
+            block: self.block,
+            block_production: self.block_production,
+            state_history: self.state_history,
+            tx_history: self.tx_history,
+            execution_history: self.execution_history,
+            block_history: self.block_history,
+            invariants: vec![],
+        })
+    }
+}
+
+impl<ExecC, QueryC, SudoC> DynApp<ExecC, QueryC, SudoC>
+where
+    ExecC: CustomMsg + DeserializeOwned + 'static,
+    QueryC: CustomQuery + DeserializeOwned + 'static,
+    SudoC: 'static,
+{
+    /// Registers built-in invariants, like [App::add_builtin_invariants] does for the application
+    /// with concrete modules. Boxed modules are checked only when they are the default
+    /// [BankKeeper], [StakeKeeper] and [WasmKeeper], invariants of other modules are not registered.
+    pub fn add_builtin_invariants(&mut self) {
+        if self.router.bank.downcast_ref::<BankKeeper>().is_some() {
+            self.add_invariant("bank supply", |app| {
+                match app.router.bank.downcast_ref::<BankKeeper>() {
+                    Some(bank) => bank.check_supply(&app.storage),
+                    None => Ok(()),
+                }
+            });
+        }
+        if self.router.staking.downcast_ref::<StakeKeeper>().is_some() {
+            self.add_invariant("staking module balance", |app| {
+                match app.router.staking.downcast_ref::<StakeKeeper>() {
+                    Some(staking) => staking.check_module_balance(&app.storage, app),
+                    None => Ok(()),
+                }
+            });
+        }
+        if self
+            .router
+            .wasm
+            .downcast_ref::<WasmKeeper<ExecC, QueryC>>()
+            .is_some()
+        {
+            self.add_invariant("contract code ids", |app| {
+                match app.router.wasm.downcast_ref::<WasmKeeper<ExecC, QueryC>>() {
+                    Some(wasm) => wasm.check_code_ids(&app.storage),
+                    None => Ok(()),
+                }
+            });
+        }
+    }
+}
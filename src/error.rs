@@ -1,7 +1,11 @@
 //! # Error definitions
 
+use crate::tx::CODESPACE_SDK;
 pub use anyhow::{anyhow, bail, Context as AnyContext, Error as AnyError, Result as AnyResult};
-use cosmwasm_std::{WasmMsg, WasmQuery};
+use cosmwasm_std::{
+    Addr, BankMsg, BankQuery, Coin, DistributionMsg, StakingMsg, StakingQuery, WasmMsg, WasmQuery,
+};
+use std::fmt::Debug;
 use thiserror::Error;
 
 /// An enumeration of errors reported across the **CosmWasm MultiTest** library.
@@ -31,6 +35,146 @@ pub enum Error {
     #[error("Unsupported wasm message: {0:?}")]
     UnsupportedWasmMsg(WasmMsg),
 
+    /// Error variant for reporting that unsupported bank query was encountered during processing.
+    #[error("Unsupported bank query: {0:?}")]
+    UnsupportedBankQuery(BankQuery),
+
+    /// Error variant for reporting that unsupported bank message was encountered during processing.
+    #[error("Unsupported bank message: {0:?}")]
+    UnsupportedBankMsg(BankMsg),
+
+    /// Error variant for reporting that unsupported staking query was encountered during processing.
+    #[error("Unsupported staking query: {0:?}")]
+    UnsupportedStakingQuery(StakingQuery),
+
+    /// Error variant for reporting that unsupported staking message was encountered during processing.
+    #[error("Unsupported staking message: {0:?}")]
+    UnsupportedStakingMsg(StakingMsg),
+
+    /// Error variant for reporting that unsupported distribution message was encountered during processing.
+    #[error("Unsupported distribution message: {0:?}")]
+    UnsupportedDistributionMsg(DistributionMsg),
+
+    /// Error variant for reporting that a query of unsupported kind was encountered during routing.
+    #[error("Unsupported query: {0}")]
+    UnsupportedQuery(String),
+
+    /// Error variant for reporting that a message of unsupported kind was encountered during routing.
+    #[error("Unsupported message: {0}")]
+    UnsupportedMsg(String),
+
+    /// Error variant for reporting that a privileged message of unsupported kind was encountered during routing.
+    #[error("Unsupported sudo message: {0}")]
+    UnsupportedSudoMsg(String),
+
+    /// Error variant for reporting that a message was routed to a module not accepting it.
+    #[error("Unexpected exec msg {msg} from {sender:?}")]
+    UnexpectedExecMsg {
+        /// Debug representation of the message.
+        msg: String,
+        /// Sender of the message.
+        sender: Addr,
+    },
+
+    /// Error variant for reporting that a query was routed to a module not accepting it.
+    #[error("Unexpected custom query {0}")]
+    UnexpectedQuery(String),
+
+    /// Error variant for reporting that a privileged message was routed to a module not accepting it.
+    #[error("Unexpected sudo msg {0}")]
+    UnexpectedSudoMsg(String),
+
+    /// Error variant for reporting that an account has not enough funds to cover a transfer.
+    #[error("Insufficient funds: {address} cannot send {amount:?}")]
+    InsufficientFunds {
+        /// Address of the account.
+        address: Addr,
+        /// Amount that could not be sent.
+        amount: Vec<Coin>,
+    },
+
+    /// Error variant for reporting a transfer of zero coins.
+    #[error("Cannot transfer empty coins amount")]
+    ZeroAmount,
+
+    /// Error variant for reporting an operation on a validator that does not exist.
+    #[error("validator does not exist")]
+    UnknownValidator(String),
+
+    /// Error variant for reporting an operation on a delegation that does not exist.
+    #[error("no delegation for (address, validator) tuple")]
+    NoDelegation,
+
+    /// Error variant for reporting an invalid amount of delegation shares.
+    #[error("invalid shares amount")]
+    InvalidSharesAmount,
+
+    /// Error variant for reporting an invalid amount of delegated coins.
+    #[error("invalid delegation amount")]
+    InvalidDelegationAmount,
+
+    /// Error variant for reporting a jailing of an already jailed validator.
+    #[error("cannot jail already jailed validator")]
+    ValidatorAlreadyJailed,
+
+    /// Error variant for reporting an unjailing of a tombstoned validator.
+    #[error("validator still jailed; cannot be unjailed")]
+    ValidatorStillJailed,
+
+    /// Error variant for reporting an unjailing of a validator that is not jailed.
+    #[error("validator not jailed; cannot be unjailed")]
+    ValidatorNotJailed,
+
+    /// Error variant for reporting a redelegation to the source validator.
+    #[error("cannot redelegate to the same validator")]
+    SelfRedelegation,
+
+    /// Error variant for reporting a redelegation from a validator with a pending incoming redelegation.
+    #[error("redelegation to this validator already in progress; first redelegation to this validator must complete before next redelegation")]
+    TransitiveRedelegation,
+
+    /// Error variant for reporting too many pending redelegations between the same validators.
+    #[error("too many redelegation entries for (delegator, src-validator, dst-validator) tuple")]
+    MaxRedelegationEntries,
+
+    /// Error variant for reporting too many pending unbondings from the same validator.
+    #[error("too many unbonding delegation entries for (delegator, validator) tuple")]
+    MaxUnbondingEntries,
+
+    /// Error variant for reporting a registration of an already registered staking hook.
+    #[error("staking hook already registered: {0}")]
+    StakingHookAlreadyRegistered(Addr),
+
+    /// Error variant for reporting a removal of a staking hook that is not registered.
+    #[error("staking hook not registered: {0}")]
+    StakingHookNotRegistered(Addr),
+
+    /// Error variant for reporting coins of a denomination other than the bonded one.
+    #[error("cannot delegate coins of denominator {denom}, only of {expected}")]
+    WrongDenom {
+        /// Denomination of the coins.
+        denom: String,
+        /// Bonded denomination.
+        expected: String,
+    },
+
+    /// Error variant for reporting an admin action performed by a sender other than the admin.
+    #[error("Only admin can {action}: {admin:?}")]
+    UnauthorizedAdmin {
+        /// Performed admin action.
+        action: String,
+        /// Current admin of the contract.
+        admin: Option<Addr>,
+    },
+
+    /// Error variant for reporting an operation on a contract that does not exist.
+    #[error("contract {0} not found")]
+    UnknownContract(String),
+
+    /// Error variant for reporting an instantiation of a contract without a label.
+    #[error("Label is required on all contracts")]
+    LabelMissing,
+
     /// Error variant for reporting invalid contract code.
     #[error("code id: invalid")]
     InvalidCodeId,
@@ -75,6 +219,166 @@ impl Error {
         Self::UnsupportedWasmMsg(msg)
     }
 
+    /// Creates an instance of the [Error](Self) for unsupported bank queries.
+    pub fn unsupported_bank_query(query: BankQuery) -> Self {
+        Self::UnsupportedBankQuery(query)
+    }
+
+    /// Creates an instance of the [Error](Self) for unsupported bank messages.
+    pub fn unsupported_bank_message(msg: BankMsg) -> Self {
+        Self::UnsupportedBankMsg(msg)
+    }
+
+    /// Creates an instance of the [Error](Self) for unsupported staking queries.
+    pub fn unsupported_staking_query(query: StakingQuery) -> Self {
+        Self::UnsupportedStakingQuery(query)
+    }
+
+    /// Creates an instance of the [Error](Self) for unsupported staking messages.
+    pub fn unsupported_staking_message(msg: StakingMsg) -> Self {
+        Self::UnsupportedStakingMsg(msg)
+    }
+
+    /// Creates an instance of the [Error](Self) for unsupported distribution messages.
+    pub fn unsupported_distribution_message(msg: DistributionMsg) -> Self {
+        Self::UnsupportedDistributionMsg(msg)
+    }
+
+    /// Creates an instance of the [Error](Self) for queries of unsupported kind,
+    /// given as their JSON representation.
+    pub fn unsupported_query(query: impl Into<String>) -> Self {
+        Self::UnsupportedQuery(query.into())
+    }
+
+    /// Creates an instance of the [Error](Self) for messages of unsupported kind.
+    pub fn unsupported_message(msg: impl Debug) -> Self {
+        Self::UnsupportedMsg(format!("{:?}", msg))
+    }
+
+    /// Creates an instance of the [Error](Self) for privileged messages of unsupported kind.
+    pub fn unsupported_sudo_message(msg: impl Debug) -> Self {
+        Self::UnsupportedSudoMsg(format!("{:?}", msg))
+    }
+
+    /// Creates an instance of the [Error](Self) for messages routed to a module not accepting them.
+    pub fn unexpected_exec_msg(msg: impl Debug, sender: Addr) -> Self {
+        Self::UnexpectedExecMsg {
+            msg: format!("{:?}", msg),
+            sender,
+        }
+    }
+
+    /// Creates an instance of the [Error](Self) for queries routed to a module not accepting them.
+    pub fn unexpected_query(query: impl Debug) -> Self {
+        Self::UnexpectedQuery(format!("{:?}", query))
+    }
+
+    /// Creates an instance of the [Error](Self) for privileged messages routed to a module not accepting them.
+    pub fn unexpected_sudo_msg(msg: impl Debug) -> Self {
+        Self::UnexpectedSudoMsg(format!("{:?}", msg))
+    }
+
+    /// Creates an instance of the [Error](Self) for insufficient funds.
+    pub fn insufficient_funds(address: Addr, amount: Vec<Coin>) -> Self {
+        Self::InsufficientFunds { address, amount }
+    }
+
+    /// Creates an instance of the [Error](Self) for transfers of zero coins.
+    pub fn zero_amount() -> Self {
+        Self::ZeroAmount
+    }
+
+    /// Creates an instance of the [Error](Self) for validators that do not exist.
+    pub fn unknown_validator(address: impl Into<String>) -> Self {
+        Self::UnknownValidator(address.into())
+    }
+
+    /// Creates an instance of the [Error](Self) for delegations that do not exist.
+    pub fn no_delegation() -> Self {
+        Self::NoDelegation
+    }
+
+    /// Creates an instance of the [Error](Self) for invalid amounts of delegation shares.
+    pub fn invalid_shares_amount() -> Self {
+        Self::InvalidSharesAmount
+    }
+
+    /// Creates an instance of the [Error](Self) for invalid amounts of delegated coins.
+    pub fn invalid_delegation_amount() -> Self {
+        Self::InvalidDelegationAmount
+    }
+
+    /// Creates an instance of the [Error](Self) for jailing of already jailed validators.
+    pub fn validator_already_jailed() -> Self {
+        Self::ValidatorAlreadyJailed
+    }
+
+    /// Creates an instance of the [Error](Self) for unjailing of tombstoned validators.
+    pub fn validator_still_jailed() -> Self {
+        Self::ValidatorStillJailed
+    }
+
+    /// Creates an instance of the [Error](Self) for unjailing of validators that are not jailed.
+    pub fn validator_not_jailed() -> Self {
+        Self::ValidatorNotJailed
+    }
+
+    /// Creates an instance of the [Error](Self) for redelegations to the source validator.
+    pub fn self_redelegation() -> Self {
+        Self::SelfRedelegation
+    }
+
+    /// Creates an instance of the [Error](Self) for transitive redelegations.
+    pub fn transitive_redelegation() -> Self {
+        Self::TransitiveRedelegation
+    }
+
+    /// Creates an instance of the [Error](Self) for too many pending redelegations.
+    pub fn max_redelegation_entries() -> Self {
+        Self::MaxRedelegationEntries
+    }
+
+    /// Creates an instance of the [Error](Self) for too many pending unbondings.
+    pub fn max_unbonding_entries() -> Self {
+        Self::MaxUnbondingEntries
+    }
+
+    /// Creates an instance of the [Error](Self) for registrations of already registered staking hooks.
+    pub fn staking_hook_already_registered(contract: Addr) -> Self {
+        Self::StakingHookAlreadyRegistered(contract)
+    }
+
+    /// Creates an instance of the [Error](Self) for removals of staking hooks that are not registered.
+    pub fn staking_hook_not_registered(contract: Addr) -> Self {
+        Self::StakingHookNotRegistered(contract)
+    }
+
+    /// Creates an instance of the [Error](Self) for coins of a denomination other than the bonded one.
+    pub fn wrong_denom(denom: impl Into<String>, expected: impl Into<String>) -> Self {
+        Self::WrongDenom {
+            denom: denom.into(),
+            expected: expected.into(),
+        }
+    }
+
+    /// Creates an instance of the [Error](Self) for admin actions performed by a sender other than the admin.
+    pub fn unauthorized_admin(action: impl Into<String>, admin: Option<Addr>) -> Self {
+        Self::UnauthorizedAdmin {
+            action: action.into(),
+            admin,
+        }
+    }
+
+    /// Creates an instance of the [Error](Self) for contracts that do not exist.
+    pub fn unknown_contract(address: impl Into<String>) -> Self {
+        Self::UnknownContract(address.into())
+    }
+
+    /// Creates an instance of the [Error](Self) for contracts instantiated without a label.
+    pub fn label_missing() -> Self {
+        Self::LabelMissing
+    }
+
     /// Creates an instance of the [Error](Self) for invalid contract code identifier.
     pub fn invalid_contract_code_id() -> Self {
         Self::InvalidCodeId
@@ -85,6 +389,51 @@ impl Error {
         Self::UnregisteredCodeId(code_id)
     }
 
+    /// Returns the codespace and code reported by `wasmd` (and Cosmos SDK modules)
+    /// for the corresponding error, used when redacting errors passed to replies.
+    pub(crate) fn abci_info(&self) -> (&'static str, u32) {
+        match self {
+            Self::InsufficientFunds { .. } => (CODESPACE_SDK, 5),
+            Self::ZeroAmount => (CODESPACE_SDK, 10),
+            Self::WrongDenom { .. }
+            | Self::StakingHookAlreadyRegistered(_)
+            | Self::StakingHookNotRegistered(_)
+            | Self::InvalidSharesAmount
+            | Self::InvalidDelegationAmount => (CODESPACE_SDK, 18),
+            Self::UnknownValidator(_) => ("staking", 3),
+            Self::SelfRedelegation => ("staking", 18),
+            Self::NoDelegation => ("staking", 19),
+            Self::TransitiveRedelegation => ("staking", 20),
+            Self::MaxRedelegationEntries => ("staking", 21),
+            Self::MaxUnbondingEntries => ("staking", 22),
+            Self::ValidatorAlreadyJailed => ("staking", 7),
+            Self::ValidatorStillJailed => ("slashing", 4),
+            Self::ValidatorNotJailed => ("slashing", 5),
+            Self::UnauthorizedAdmin { .. } => (CODESPACE_SDK, 4),
+            Self::UnknownContract(_) | Self::UnregisteredCodeId(_) => ("wasm", 8),
+            Self::LabelMissing => ("wasm", 12),
+            Self::InvalidCodeId => ("wasm", 14),
+            Self::DuplicatedContractAddress(_) => ("wasm", 15),
+            Self::EmptyAttributeKey(_)
+            | Self::EmptyAttributeValue(_)
+            | Self::ReservedAttributeKey(_)
+            | Self::EventTypeTooShort(_) => ("wasm", 21),
+            Self::UnsupportedWasmQuery(_)
+            | Self::UnsupportedWasmMsg(_)
+            | Self::UnsupportedBankQuery(_)
+            | Self::UnsupportedBankMsg(_)
+            | Self::UnsupportedStakingQuery(_)
+            | Self::UnsupportedStakingMsg(_)
+            | Self::UnsupportedDistributionMsg(_)
+            | Self::UnsupportedQuery(_)
+            | Self::UnsupportedMsg(_)
+            | Self::UnsupportedSudoMsg(_)
+            | Self::UnexpectedExecMsg { .. }
+            | Self::UnexpectedQuery(_)
+            | Self::UnexpectedSudoMsg(_) => ("wasm", 20),
+        }
+    }
+
     /// Creates an instance of the [Error](Self) for duplicated contract addresses.
     pub fn duplicated_contract_address(address: impl Into<String>) -> Self {
         Self::DuplicatedContractAddress(address.into())
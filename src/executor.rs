@@ -4,12 +4,13 @@ use cosmwasm_std::{
     SubMsgResponse, WasmMsg,
 };
 use cw_utils::{parse_execute_response_data, parse_instantiate_response_data};
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
 /// A subset of data returned as a response of a contract entry point,
 /// such as `instantiate`, `execute` or `migrate`.
-#[derive(Default, Clone, Debug)]
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct AppResponse {
     /// Response events.
     pub events: Vec<Event>,
@@ -0,0 +1,222 @@
+//! Property-based testing of message sequences, built on [proptest](https://docs.rs/proptest).
+//!
+//! The [FuzzHarness] generates random sequences of [Step]s from user-provided strategies,
+//! executes every sequence against a fresh application and checks registered invariants
+//! after each step. A failing sequence is shrunk to a minimal one and reported
+//! as a ready-to-paste Rust test, see [FuzzFailure].
+//!
+//! Available with the `proptest` feature.
+
+use crate::error::AnyResult;
+use crate::executor::Executor;
+use cosmwasm_std::{to_json_string, Addr, CosmosMsg, CustomMsg};
+use proptest::collection::vec;
+use proptest::strategy::Strategy;
+use proptest::test_runner::{Config, TestCaseError, TestError, TestRunner};
+use std::fmt;
+
+/// Default number of test cases run by the [FuzzHarness].
+pub const DEFAULT_CASES: u32 = 256;
+
+/// Default maximum number of steps in a single sequence generated by the [FuzzHarness].
+pub const DEFAULT_MAX_STEPS: usize = 16;
+
+/// Single step of a generated sequence: a message executed on behalf of the sender.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step<ExecC> {
+    /// Address of the message sender.
+    pub sender: Addr,
+    /// Executed message.
+    pub msg: CosmosMsg<ExecC>,
+}
+
+impl<ExecC> Step<ExecC> {
+    /// Creates a step executing the message on behalf of the sender.
+    pub fn new(sender: impl Into<String>, msg: impl Into<CosmosMsg<ExecC>>) -> Self {
+        Self {
+            sender: Addr::unchecked(sender),
+            msg: msg.into(),
+        }
+    }
+}
+
+/// Invariant checked after every step, named for reporting.
+type Invariant<AppT> = (String, Box<dyn Fn(&AppT) -> AnyResult<()>>);
+
+/// Runs generated sequences of messages against fresh applications, checking invariants.
+pub struct FuzzHarness<AppT> {
+    setup: Box<dyn Fn() -> AppT>,
+    invariants: Vec<Invariant<AppT>>,
+    cases: u32,
+    max_steps: usize,
+    fail_on_error: bool,
+}
+
+impl<AppT> FuzzHarness<AppT> {
+    /// Creates a harness, calling `setup` to create a fresh application for every test case.
+    pub fn new(setup: impl Fn() -> AppT + 'static) -> Self {
+        Self {
+            setup: Box::new(setup),
+            invariants: vec![],
+            cases: DEFAULT_CASES,
+            max_steps: DEFAULT_MAX_STEPS,
+            fail_on_error: false,
+        }
+    }
+
+    /// Adds an invariant checked after every step, failing when it returns an error.
+    pub fn with_invariant(
+        mut self,
+        name: impl Into<String>,
+        invariant: impl Fn(&AppT) -> AnyResult<()> + 'static,
+    ) -> Self {
+        self.invariants.push((name.into(), Box::new(invariant)));
+        self
+    }
+
+    /// Sets the number of generated test cases, [DEFAULT_CASES] by default.
+    pub fn with_cases(mut self, cases: u32) -> Self {
+        self.cases = cases;
+        self
+    }
+
+    /// Sets the maximum number of steps in a single sequence, [DEFAULT_MAX_STEPS] by default.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Sets whether a step that fails to execute fails the whole sequence.
+    /// By default, such steps are ignored like rejected transactions.
+    pub fn with_fail_on_error(mut self, fail_on_error: bool) -> Self {
+        self.fail_on_error = fail_on_error;
+        self
+    }
+
+    /// Runs generated sequences of steps and returns the minimal failing sequence, if any.
+    pub fn run<ExecC, S>(&self, steps: S) -> Result<(), FuzzFailure<ExecC>>
+    where
+        AppT: Executor<ExecC>,
+        ExecC: CustomMsg + 'static,
+        S: Strategy<Value = Step<ExecC>>,
+    {
+        let config = Config {
+            cases: self.cases,
+            failure_persistence: None,
+            ..Config::default()
+        };
+        let mut runner = TestRunner::new(config);
+        let result = runner.run(&vec(steps, 1..=self.max_steps.max(1)), |steps| {
+            self.execute(&steps).map_err(TestCaseError::fail)
+        });
+        match result {
+            Ok(()) => Ok(()),
+            Err(TestError::Fail(reason, steps)) => Err(FuzzFailure {
+                reason: reason.message().to_string(),
+                steps,
+            }),
+            Err(TestError::Abort(reason)) => Err(FuzzFailure {
+                reason: format!("aborted: {}", reason.message()),
+                steps: vec![],
+            }),
+        }
+    }
+
+    /// Runs generated sequences of steps, like [run](Self::run),
+    /// and panics with the reproduction of the minimal failing sequence.
+    pub fn check<ExecC, S>(&self, steps: S)
+    where
+        AppT: Executor<ExecC>,
+        ExecC: CustomMsg + 'static,
+        S: Strategy<Value = Step<ExecC>>,
+    {
+        if let Err(failure) = self.run(steps) {
+            panic!("{}", failure);
+        }
+    }
+
+    /// Executes the sequence against a fresh application, checking invariants after each step.
+    fn execute<ExecC>(&self, steps: &[Step<ExecC>]) -> Result<(), String>
+    where
+        AppT: Executor<ExecC>,
+        ExecC: CustomMsg + 'static,
+    {
+        let mut app = (self.setup)();
+        for (index, step) in steps.iter().enumerate() {
+            let result = app.execute(step.sender.clone(), step.msg.clone());
+            if let Err(err) = result {
+                if self.fail_on_error {
+                    return Err(format!("step {}: execution failed: {:#}", index, err));
+                }
+            }
+            for (name, invariant) in self.invariants.iter() {
+                if let Err(err) = invariant(&app) {
+                    return Err(format!(
+                        "step {}: invariant '{}' violated: {:#}",
+                        index, name, err
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Minimal failing sequence found by the [FuzzHarness].
+#[derive(Clone, Debug, PartialEq)]
+pub struct FuzzFailure<ExecC> {
+    /// Description of the failure, with the index of the failing step.
+    pub reason: String,
+    /// Minimal sequence of steps reproducing the failure.
+    pub steps: Vec<Step<ExecC>>,
+}
+
+impl<ExecC> FuzzFailure<ExecC>
+where
+    ExecC: CustomMsg,
+{
+    /// Returns the Rust test reproducing the failure, executing the steps against
+    /// the application created by `setup`, which is the setup function of the harness.
+    /// The test is preceded by the `use` declarations it needs, except for `setup`.
+    pub fn to_rust_test(&self) -> String {
+        let mut code = String::new();
+        code.push_str("use cosmwasm_std::{from_json, Addr};\n");
+        code.push_str("use cw_multi_test::Executor;\n\n");
+        code.push_str("#[test]\n");
+        code.push_str("fn fuzz_reproduction() {\n");
+        code.push_str("    let mut app = setup();\n");
+        for step in self.steps.iter() {
+            let msg = match to_json_string(&step.msg) {
+                Ok(json) => format!("from_json(r##\"{}\"##).unwrap()", json),
+                // the message is still shown, so the test can be completed by hand
+                Err(err) => format!(
+                    "todo!(), // message can not be serialized ({}): {:?}",
+                    err, step.msg
+                ),
+            };
+            code.push_str(&format!(
+                "    let _ = app.execute(\n        Addr::unchecked({:?}),\n        {},\n    );\n",
+                step.sender.as_str(),
+                msg
+            ));
+        }
+        code.push_str(&format!("    // {}\n", self.reason));
+        code.push_str("}\n");
+        code
+    }
+}
+
+impl<ExecC> fmt::Display for FuzzFailure<ExecC>
+where
+    ExecC: CustomMsg,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}; minimal failing sequence of {} steps:",
+            self.reason,
+            self.steps.len()
+        )?;
+        write!(f, "{}", self.to_rust_test())
+    }
+}
@@ -0,0 +1,205 @@
+//! Import of the `wasm` module state exported from a `wasmd` chain.
+//!
+//! The state can be taken from a genesis file (e.g. the output of `wasmd export`),
+//! see [WasmGenesis], or from the output of `wasmd query wasm contract-state all`,
+//! see [ContractStateExport]. Numbers are encoded as strings and storage keys as hex strings,
+//! just like in the JSON produced by `wasmd`.
+
+use crate::contracts::Contract;
+use crate::error::{bail, AnyResult};
+use cosmwasm_std::{
+    from_json, Binary, CustomMsg, CustomQuery, Deps, DepsMut, Env, HexBinary, MessageInfo, Reply,
+    Response, Uint64,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+/// State of the `wasm` module, as exported in the `app_state.wasm` section of a `wasmd` genesis.
+///
+/// See <https://github.com/CosmWasm/wasmd/blob/v0.45.0/proto/cosmwasm/wasm/v1/genesis.proto>
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct WasmGenesis {
+    /// Stored codes.
+    #[serde(default)]
+    pub codes: Vec<GenesisCode>,
+    /// Instantiated contracts with their state.
+    #[serde(default)]
+    pub contracts: Vec<GenesisContract>,
+}
+
+/// Code stored on chain, see [WasmGenesis].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct GenesisCode {
+    /// Identifier of the code.
+    pub code_id: Uint64,
+    /// Metadata of the code.
+    pub code_info: GenesisCodeInfo,
+}
+
+/// Metadata of the code stored on chain, see [GenesisCode].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct GenesisCodeInfo {
+    /// Checksum of the code.
+    pub code_hash: Binary,
+    /// Address of an account that stored the code.
+    pub creator: String,
+}
+
+/// Contract instantiated on chain, see [WasmGenesis].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct GenesisContract {
+    /// Address of the contract.
+    pub contract_address: String,
+    /// Metadata of the contract.
+    pub contract_info: GenesisContractInfo,
+    /// Raw key-value pairs of the contract's state.
+    #[serde(default)]
+    pub contract_state: Vec<Model>,
+}
+
+/// Metadata of the contract instantiated on chain, see [GenesisContract].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct GenesisContractInfo {
+    /// Identifier of the contract's code.
+    pub code_id: Uint64,
+    /// Address of an account that instantiated the contract.
+    pub creator: String,
+    /// Address of an account who can execute migrations, empty when there is no admin.
+    #[serde(default)]
+    pub admin: String,
+    /// Metadata passed while contract instantiation.
+    pub label: String,
+    /// Position of the instantiation transaction.
+    #[serde(default)]
+    pub created: Option<AbsoluteTxPosition>,
+}
+
+/// Position of a transaction in the blockchain, see [GenesisContractInfo].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AbsoluteTxPosition {
+    /// Height of the block containing the transaction.
+    pub block_height: Uint64,
+    /// Index of the transaction within the block.
+    pub tx_index: Uint64,
+}
+
+/// Single raw key-value pair of the contract's state.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Model {
+    /// Storage key, encoded as hex string.
+    pub key: HexBinary,
+    /// Stored value, encoded as base64 string.
+    pub value: Binary,
+}
+
+/// Raw state of a single contract, as returned by `wasmd query wasm contract-state all`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct ContractStateExport {
+    /// Raw key-value pairs of the contract's state.
+    pub models: Vec<Model>,
+}
+
+/// Genesis document, only the parts needed to reach the `wasm` section.
+#[derive(Deserialize)]
+struct GenesisDoc {
+    app_state: GenesisAppState,
+}
+
+#[derive(Deserialize)]
+struct GenesisAppState {
+    wasm: WasmGenesis,
+}
+
+impl WasmGenesis {
+    /// Deserializes the state from JSON, either a complete genesis document
+    /// (like the output of `wasmd export`) or its `app_state.wasm` section only.
+    pub fn from_json(json: &str) -> AnyResult<Self> {
+        if let Ok(genesis) = from_json::<GenesisDoc>(json) {
+            return Ok(genesis.app_state.wasm);
+        }
+        Ok(from_json(json)?)
+    }
+
+    /// Returns the highest code identifier, used by stored codes or instantiated contracts.
+    pub(crate) fn max_code_id(&self) -> u64 {
+        let codes = self.codes.iter().map(|code| code.code_id.u64());
+        let contracts = self
+            .contracts
+            .iter()
+            .map(|contract| contract.contract_info.code_id.u64());
+        codes.chain(contracts).max().unwrap_or_default()
+    }
+}
+
+impl ContractStateExport {
+    /// Deserializes the state from JSON.
+    pub fn from_json(json: &str) -> AnyResult<Self> {
+        Ok(from_json(json)?)
+    }
+}
+
+/// Contract stored in place of an imported code, that was not bound to any contract implementation.
+/// Every call to this contract fails.
+pub(crate) struct UnboundContract<T, Q> {
+    code_id: u64,
+    _marker: PhantomData<(T, Q)>,
+}
+
+impl<T, Q> UnboundContract<T, Q> {
+    pub(crate) fn new(code_id: u64) -> Self {
+        Self {
+            code_id,
+            _marker: PhantomData,
+        }
+    }
+
+    fn fail<R>(&self) -> AnyResult<R> {
+        bail!(
+            "code id {}: no contract bound to imported code",
+            self.code_id
+        )
+    }
+}
+
+impl<T, Q> Contract<T, Q> for UnboundContract<T, Q>
+where
+    T: CustomMsg,
+    Q: CustomQuery,
+{
+    fn execute(
+        &self,
+        _deps: DepsMut<Q>,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Vec<u8>,
+    ) -> AnyResult<Response<T>> {
+        self.fail()
+    }
+
+    fn instantiate(
+        &self,
+        _deps: DepsMut<Q>,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Vec<u8>,
+    ) -> AnyResult<Response<T>> {
+        self.fail()
+    }
+
+    fn query(&self, _deps: Deps<Q>, _env: Env, _msg: Vec<u8>) -> AnyResult<Binary> {
+        self.fail()
+    }
+
+    fn sudo(&self, _deps: DepsMut<Q>, _env: Env, _msg: Vec<u8>) -> AnyResult<Response<T>> {
+        self.fail()
+    }
+
+    fn reply(&self, _deps: DepsMut<Q>, _env: Env, _msg: Reply) -> AnyResult<Response<T>> {
+        self.fail()
+    }
+
+    fn migrate(&self, _deps: DepsMut<Q>, _env: Env, _msg: Vec<u8>) -> AnyResult<Response<T>> {
+        self.fail()
+    }
+}
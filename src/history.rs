@@ -0,0 +1,223 @@
+//! History of the application's state, recorded at the end of every block,
+//! see [App::enable_state_history](crate::App::enable_state_history).
+
+use crate::app::{CosmosRouter, RouterQuerier};
+use crate::error::{bail, AnyResult};
+use crate::transactions::{StorageChange, StorageTransaction};
+use cosmwasm_std::{
+    Api, BlockInfo, CustomMsg, CustomQuery, Order, Querier, QuerierResult, QuerierWrapper, Record,
+    Storage,
+};
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Bound;
+
+/// Versions of all keys of the storage, committed at the end of every block.
+#[derive(Clone, Default)]
+pub(crate) struct StateHistory {
+    /// Values of keys by the height of the block at which they were committed,
+    /// `None` when the key was removed.
+    versions: BTreeMap<Vec<u8>, BTreeMap<u64, Option<Vec<u8>>>>,
+    /// Keys changed since the end of the last committed block, `None` when not known,
+    /// like before the first block or after the storage was changed directly.
+    changed_keys: Option<BTreeSet<Vec<u8>>>,
+    /// Block info of every committed block, by height.
+    blocks: BTreeMap<u64, BlockInfo>,
+}
+
+impl StateHistory {
+    /// Records the keys changed by the committed state changes,
+    /// so only these keys are compared at the end of the block.
+    pub(crate) fn track(&mut self, changes: &[StorageChange]) {
+        if let Some(keys) = &mut self.changed_keys {
+            keys.extend(changes.iter().map(|change| change.key.to_vec()));
+        }
+    }
+
+    /// Forgets the changed keys, so the whole storage is compared at the end of the block.
+    /// Called when the storage is changed directly, without tracking the changes.
+    pub(crate) fn untrack(&mut self) {
+        self.changed_keys = None;
+    }
+
+    /// Returns the values of keys changed since the end of the last committed block,
+    /// read from the storage at the end of the current block, `None` for removed keys.
+    pub(crate) fn changed_state(
+        &self,
+        storage: &StorageTransaction,
+    ) -> Vec<(Vec<u8>, Option<Vec<u8>>)> {
+        match &self.changed_keys {
+            Some(keys) => {
+                let mut keys = keys.clone();
+                keys.extend(
+                    storage
+                        .changes()
+                        .into_iter()
+                        .map(|change| change.key.to_vec()),
+                );
+                keys.into_iter()
+                    .map(|key| {
+                        let value = storage.get(&key);
+                        (key, value)
+                    })
+                    .collect()
+            }
+            None => {
+                let mut state: Vec<_> = storage
+                    .range(None, None, Order::Ascending)
+                    .map(|(key, value)| (key, Some(value)))
+                    .collect();
+                state.extend(
+                    self.versions
+                        .iter()
+                        .filter(|(key, versions)| {
+                            latest(versions).is_some() && storage.get(key).is_none()
+                        })
+                        .map(|(key, _)| (key.clone(), None)),
+                );
+                state
+            }
+        }
+    }
+
+    /// Records the state of the storage at the end of the block,
+    /// given as the values of keys changed since the last committed block,
+    /// see [changed_state](Self::changed_state).
+    pub(crate) fn commit(&mut self, block: &BlockInfo, state: Vec<(Vec<u8>, Option<Vec<u8>>)>) {
+        for (key, value) in state {
+            let committed = self.versions.get(&key).and_then(latest);
+            if committed != value.as_ref() {
+                self.versions
+                    .entry(key)
+                    .or_default()
+                    .insert(block.height, value);
+            }
+        }
+        self.changed_keys = Some(BTreeSet::new());
+        self.blocks.insert(block.height, block.clone());
+    }
+
+    /// Returns the read-only storage with the state committed at the end of the block
+    /// with specified height, along with the info of this block.
+    pub(crate) fn state_at(&self, height: u64) -> AnyResult<(HistoricalStorage<'_>, BlockInfo)> {
+        let Some(block) = self.blocks.get(&height) else {
+            bail!("state at height {} was not recorded", height);
+        };
+        Ok((
+            HistoricalStorage {
+                history: self,
+                height,
+            },
+            block.clone(),
+        ))
+    }
+}
+
+/// Returns the last committed value of the key, `None` when the key was removed.
+fn latest(versions: &BTreeMap<u64, Option<Vec<u8>>>) -> Option<&Vec<u8>> {
+    versions
+        .values()
+        .next_back()
+        .and_then(|value| value.as_ref())
+}
+
+/// Read-only storage with the state committed at the end of a past block.
+pub(crate) struct HistoricalStorage<'a> {
+    history: &'a StateHistory,
+    height: u64,
+}
+
+impl<'a> HistoricalStorage<'a> {
+    fn value_at(&self, versions: &BTreeMap<u64, Option<Vec<u8>>>) -> Option<Vec<u8>> {
+        versions
+            .range(..=self.height)
+            .next_back()
+            .and_then(|(_, value)| value.clone())
+    }
+}
+
+impl<'a> Storage for HistoricalStorage<'a> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.history
+            .versions
+            .get(key)
+            .and_then(|versions| self.value_at(versions))
+    }
+
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        let start = start.map_or(Bound::Unbounded, |key| Bound::Included(key.to_vec()));
+        let end = end.map_or(Bound::Unbounded, |key| Bound::Excluded(key.to_vec()));
+        let iter = self
+            .history
+            .versions
+            .range((start, end))
+            .filter_map(|(key, versions)| Some((key.clone(), self.value_at(versions)?)));
+        match order {
+            Order::Ascending => Box::new(iter),
+            Order::Descending => Box::new(iter.rev()),
+        }
+    }
+
+    fn set(&mut self, _key: &[u8], _value: &[u8]) {
+        panic!("historical state is read-only");
+    }
+
+    fn remove(&mut self, _key: &[u8]) {
+        panic!("historical state is read-only");
+    }
+}
+
+/// Querier reading the state committed at the end of a past block,
+/// see [App::wrap_at_height](crate::App::wrap_at_height).
+pub struct HistoricalQuerier<'a, ExecC, QueryC> {
+    router: &'a dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+    api: &'a dyn Api,
+    storage: HistoricalStorage<'a>,
+    block: BlockInfo,
+}
+
+impl<'a, ExecC, QueryC> HistoricalQuerier<'a, ExecC, QueryC>
+where
+    ExecC: CustomMsg + DeserializeOwned + 'static,
+    QueryC: CustomQuery + DeserializeOwned + 'static,
+{
+    pub(crate) fn new(
+        router: &'a dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        api: &'a dyn Api,
+        storage: HistoricalStorage<'a>,
+        block: BlockInfo,
+    ) -> Self {
+        Self {
+            router,
+            api,
+            storage,
+            block,
+        }
+    }
+
+    /// Returns the info of the block, at the end of which the state is read.
+    pub fn block_info(&self) -> BlockInfo {
+        self.block.clone()
+    }
+
+    /// Simple helper so we get access to all the QuerierWrapper helpers,
+    /// e.g. wrap().query_wasm_smart, query_all_balances, ...
+    pub fn wrap(&self) -> QuerierWrapper<'_, QueryC> {
+        QuerierWrapper::new(self)
+    }
+}
+
+impl<'a, ExecC, QueryC> Querier for HistoricalQuerier<'a, ExecC, QueryC>
+where
+    ExecC: CustomMsg + DeserializeOwned + 'static,
+    QueryC: CustomQuery + DeserializeOwned + 'static,
+{
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        RouterQuerier::new(self.router, self.api, &self.storage, &self.block).raw_query(bin_request)
+    }
+}
@@ -124,6 +124,7 @@
 #![deny(rustdoc::broken_intra_doc_links)]
 #![deny(rustdoc::missing_crate_level_docs)]
 
+mod accounts;
 pub mod addons;
 mod addresses;
 mod app;
@@ -132,35 +133,68 @@ mod bank;
 mod checksums;
 mod contracts;
 pub mod custom_handler;
+mod diff;
+mod dyn_app;
 pub mod error;
 mod executor;
+#[cfg(feature = "proptest")]
+pub mod fuzz;
+mod genesis;
 mod gov;
+mod history;
 mod ibc;
+#[cfg(feature = "cosmwasm_1_1")]
+pub mod mint;
 mod module;
 mod prefixed_storage;
+mod registry;
+pub mod search;
 mod staking;
 mod stargate;
 mod test_helpers;
 mod tests;
+mod tracer;
 mod transactions;
+mod tx;
 mod wasm;
 
 pub use crate::addresses::{AddressGenerator, SimpleAddressGenerator};
 pub use crate::app::{
-    custom_app, next_block, no_init, App, BasicApp, CosmosRouter, Router, SudoMsg,
+    custom_app, next_block, no_init, App, AppSnapshot, AppState, BasicApp, BlockProduction,
+    BlockSudoMsg, CodeState, CosmosRouter, Router, StorageEntry, SudoMsg,
 };
 pub use crate::app_builder::{AppBuilder, BasicAppBuilder};
 pub use crate::bank::{Bank, BankKeeper, BankSudo};
 pub use crate::checksums::ChecksumGenerator;
 pub use crate::contracts::{Contract, ContractWrapper};
+pub use crate::diff::{DecodedKey, KeyChange, StateDiff};
+pub use crate::dyn_app::{
+    BoxedApi, BoxedModule, BoxedStaking, BoxedStorage, BoxedWasm, DynApi, DynApp, DynModule,
+    DynStaking, DynStorage, DynWasm,
+};
 pub use crate::executor::{AppResponse, Executor};
+pub use crate::genesis::{
+    AbsoluteTxPosition, ContractStateExport, GenesisCode, GenesisCodeInfo, GenesisContract,
+    GenesisContractInfo, Model, WasmGenesis,
+};
 pub use crate::gov::{Gov, GovAcceptingModule, GovFailingModule};
+pub use crate::history::HistoricalQuerier;
 pub use crate::ibc::{Ibc, IbcAcceptingModule, IbcFailingModule};
+#[cfg(feature = "cosmwasm_1_1")]
+pub use crate::mint::{MintParams, Minter};
 pub use crate::module::{AcceptingModule, FailingModule, Module};
+pub use crate::registry::{
+    ModuleRegistration, RegisteredModule, RegisteredMsg, RegisteredQuery, RegisteredSudo,
+};
+pub use crate::search::{BlockResults, EventQuery};
 pub use crate::staking::{
-    Distribution, DistributionKeeper, StakeKeeper, Staking, StakingInfo, StakingSudo,
+    Distribution, DistributionKeeper, Redelegation, StakeKeeper, Staking, StakingHookMsg,
+    StakingInfo, StakingSudo, Unbonding, ValidatorStatus,
 };
 pub use crate::stargate::{
     Stargate, StargateAcceptingModule, StargateFailingModule, StargateMsg, StargateQuery,
 };
+pub use crate::tracer::{TraceKind, TraceNode, TraceResult, Tracer};
+pub use crate::transactions::StorageChange;
+pub use crate::tx::{SimulationResponse, Tx, TxResponse, DEFAULT_GAS_LIMIT};
 pub use crate::wasm::{ContractData, Wasm, WasmKeeper, WasmSudo};
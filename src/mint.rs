@@ -0,0 +1,98 @@
+//! Inflation, modelled after the `x/mint` module of cosmos-sdk.
+//!
+//! Minting is optional and is enabled by calling [StakeKeeper::setup_mint](crate::StakeKeeper::setup_mint).
+//! When enabled, at the beginning of every block the block provision is minted into the fee collector
+//! account (`fee_collector`), moved to the distribution module account (`distribution_module`)
+//! and allocated as rewards to the bonded validators and their delegators, proportionally
+//! to their power. In this mode the fixed [StakingInfo::apr](crate::StakingInfo::apr) is not used,
+//! and withdrawn rewards are paid out from the distribution module account.
+//! The commission of every validator is accumulated separately and paid out with
+//! [StakingSudo::WithdrawValidatorCommission](crate::StakingSudo::WithdrawValidatorCommission).
+
+use cosmwasm_std::{Decimal, Uint128};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Inflation parameters.
+///
+/// See <https://github.com/cosmos/cosmos-sdk/blob/v0.47.5/x/mint/types/params.go>
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct MintParams {
+    /// Maximum annual change of the inflation rate
+    pub inflation_rate_change: Decimal,
+    /// Maximum inflation rate
+    pub inflation_max: Decimal,
+    /// Minimum inflation rate
+    pub inflation_min: Decimal,
+    /// Goal of the bonded tokens ratio
+    pub goal_bonded: Decimal,
+    /// Expected number of blocks per year
+    pub blocks_per_year: u64,
+}
+
+impl Default for MintParams {
+    /// Creates inflation parameters with the same defaults as in cosmos-sdk.
+    fn default() -> Self {
+        Self {
+            inflation_rate_change: Decimal::percent(13),
+            inflation_max: Decimal::percent(20),
+            inflation_min: Decimal::percent(7),
+            goal_bonded: Decimal::percent(67),
+            blocks_per_year: 60 * 60 * 8766 / 5,
+        }
+    }
+}
+
+/// Current state of the inflation.
+///
+/// See <https://github.com/cosmos/cosmos-sdk/blob/v0.47.5/x/mint/types/minter.go>
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Minter {
+    /// Current annual inflation rate
+    pub inflation: Decimal,
+    /// Current annual expected provisions
+    pub annual_provisions: Decimal,
+}
+
+impl Default for Minter {
+    /// Creates a minter with the same initial inflation as in cosmos-sdk.
+    fn default() -> Self {
+        Self {
+            inflation: Decimal::percent(13),
+            annual_provisions: Decimal::zero(),
+        }
+    }
+}
+
+impl Minter {
+    /// Returns the inflation rate for the next block.
+    ///
+    /// The inflation rate grows while the bonded ratio is below the goal and falls when it is above,
+    /// but always stays between the minimum and maximum inflation rate.
+    pub fn next_inflation_rate(&self, params: &MintParams, bonded_ratio: Decimal) -> Decimal {
+        let blocks_per_year = Decimal::from_ratio(params.blocks_per_year.max(1), 1u128);
+        let ratio = if params.goal_bonded.is_zero() {
+            Decimal::one()
+        } else {
+            bonded_ratio / params.goal_bonded
+        };
+        let inflation = if ratio < Decimal::one() {
+            let change = (Decimal::one() - ratio) * params.inflation_rate_change / blocks_per_year;
+            self.inflation.saturating_add(change)
+        } else {
+            let change = (ratio - Decimal::one()) * params.inflation_rate_change / blocks_per_year;
+            self.inflation.saturating_sub(change)
+        };
+        inflation.clamp(params.inflation_min, params.inflation_max)
+    }
+
+    /// Returns the annual provisions for the current inflation rate and total supply.
+    pub fn next_annual_provisions(&self, total_supply: Uint128) -> Decimal {
+        self.inflation * Decimal::from_ratio(total_supply, 1u128)
+    }
+
+    /// Returns the amount of tokens minted in a single block.
+    pub fn block_provision(&self, params: &MintParams) -> Uint128 {
+        self.annual_provisions.to_uint_floor() / Uint128::from(params.blocks_per_year.max(1))
+    }
+}
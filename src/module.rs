@@ -1,5 +1,5 @@
 use crate::app::CosmosRouter;
-use crate::error::{bail, AnyResult};
+use crate::error::{bail, AnyResult, Error};
 use crate::AppResponse;
 use cosmwasm_std::{Addr, Api, Binary, BlockInfo, CustomMsg, CustomQuery, Querier, Storage};
 use serde::de::DeserializeOwned;
@@ -62,6 +62,40 @@ pub trait Module {
     where
         ExecC: CustomMsg + DeserializeOwned + 'static,
         QueryC: CustomQuery + DeserializeOwned + 'static;
+
+    /// Called at the beginning of every block, when the block is advanced
+    /// with [update_block](crate::App::update_block) or [set_block](crate::App::set_block).
+    /// Receives the info of the new block. Does nothing by default.
+    fn begin_block<ExecC, QueryC>(
+        &self,
+        _api: &dyn Api,
+        _storage: &mut dyn Storage,
+        _router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        _block: &BlockInfo,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: CustomMsg + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        Ok(AppResponse::default())
+    }
+
+    /// Called at the end of every block, when the block is advanced
+    /// with [update_block](crate::App::update_block) or [set_block](crate::App::set_block).
+    /// Receives the info of the ending block. Does nothing by default.
+    fn end_block<ExecC, QueryC>(
+        &self,
+        _api: &dyn Api,
+        _storage: &mut dyn Storage,
+        _router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        _block: &BlockInfo,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: CustomMsg + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        Ok(AppResponse::default())
+    }
 }
 /// # Always failing module
 ///
@@ -103,7 +137,7 @@ where
         sender: Addr,
         msg: Self::ExecT,
     ) -> AnyResult<AppResponse> {
-        bail!("Unexpected exec msg {:?} from {:?}", msg, sender)
+        bail!(Error::unexpected_exec_msg(msg, sender))
     }
 
     /// Runs any [QueryT](Self::QueryT) message, always returns an error.
@@ -115,7 +149,7 @@ where
         _block: &BlockInfo,
         request: Self::QueryT,
     ) -> AnyResult<Binary> {
-        bail!("Unexpected custom query {:?}", request)
+        bail!(Error::unexpected_query(request))
     }
 
     /// Runs any [SudoT](Self::SudoT) privileged action, always returns an error.
@@ -127,7 +161,7 @@ where
         _block: &BlockInfo,
         msg: Self::SudoT,
     ) -> AnyResult<AppResponse> {
-        bail!("Unexpected sudo msg {:?}", msg)
+        bail!(Error::unexpected_sudo_msg(msg))
     }
 }
 /// # Always accepting module
@@ -0,0 +1,312 @@
+//! Registry of additional modules, claiming messages by their type
+//! instead of occupying one of the fixed module slots of the [Router](crate::Router).
+
+use crate::app::CosmosRouter;
+use crate::error::{anyhow, bail, AnyResult};
+use crate::stargate::{StargateMsg, StargateQuery};
+use crate::AppResponse;
+use cosmwasm_std::{to_json_binary, Addr, Api, Binary, BlockInfo, Querier, StdResult, Storage};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::any::Any;
+use std::rc::Rc;
+
+/// Message claimed by a [RegisteredModule].
+#[derive(Clone, Debug, PartialEq)]
+pub enum RegisteredMsg<ExecC> {
+    /// Stargate message with a claimed type URL.
+    Stargate(StargateMsg),
+    /// Claimed custom message.
+    Custom(ExecC),
+}
+
+/// Query claimed by a [RegisteredModule].
+#[derive(Clone, Debug, PartialEq)]
+pub enum RegisteredQuery<QueryC> {
+    /// Stargate query with a claimed path.
+    Stargate(StargateQuery),
+    /// Claimed custom query.
+    Custom(QueryC),
+}
+
+/// Privileged message for a [RegisteredModule], addressed by the claimed namespace.
+#[derive(Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct RegisteredSudo {
+    /// Namespace claimed by the module.
+    pub namespace: String,
+    /// Serialized privileged message.
+    pub msg: Binary,
+}
+
+impl RegisteredSudo {
+    /// Creates a new privileged message for the module claiming the namespace.
+    pub fn new<T: Serialize>(namespace: impl Into<String>, msg: &T) -> StdResult<RegisteredSudo> {
+        Ok(RegisteredSudo {
+            namespace: namespace.into(),
+            msg: to_json_binary(msg)?,
+        })
+    }
+}
+
+/// Object-safe module registered in the [Router](crate::Router) with a [ModuleRegistration],
+/// processing only the messages, queries and privileged actions claimed by the registration.
+pub trait RegisteredModule<ExecC, QueryC> {
+    /// Runs a claimed message.
+    fn execute(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        sender: Addr,
+        msg: RegisteredMsg<ExecC>,
+    ) -> AnyResult<AppResponse>;
+
+    /// Runs a claimed query.
+    fn query(
+        &self,
+        api: &dyn Api,
+        storage: &dyn Storage,
+        querier: &dyn Querier,
+        block: &BlockInfo,
+        request: RegisteredQuery<QueryC>,
+    ) -> AnyResult<Binary>;
+
+    /// Runs a privileged action in a claimed namespace.
+    fn sudo(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        msg: RegisteredSudo,
+    ) -> AnyResult<AppResponse>;
+}
+
+/// Predicate selecting claimed custom messages or queries.
+type Claim<T> = Rc<dyn Fn(&T) -> bool>;
+
+/// Registration of a [RegisteredModule] with the messages it claims.
+///
+/// # Example
+///
+/// ```
+/// # use cosmwasm_std::{Addr, Api, Binary, BlockInfo, Empty, Querier, Storage};
+/// # use cw_multi_test::error::{bail, AnyResult};
+/// # use cw_multi_test::{AppResponse, CosmosRouter, RegisteredModule, RegisteredMsg, RegisteredQuery, RegisteredSudo};
+/// use cw_multi_test::{App, ModuleRegistration};
+///
+/// struct TokenFactory;
+///
+/// impl RegisteredModule<Empty, Empty> for TokenFactory {
+///     // here implement processing of the claimed messages
+/// #   fn execute(&self, _: &dyn Api, _: &mut dyn Storage, _: &dyn CosmosRouter<ExecC = Empty, QueryC = Empty>, _: &BlockInfo, _: Addr, _: RegisteredMsg<Empty>) -> AnyResult<AppResponse> { Ok(AppResponse::default()) }
+/// #   fn query(&self, _: &dyn Api, _: &dyn Storage, _: &dyn Querier, _: &BlockInfo, _: RegisteredQuery<Empty>) -> AnyResult<Binary> { bail!("no queries") }
+/// #   fn sudo(&self, _: &dyn Api, _: &mut dyn Storage, _: &dyn CosmosRouter<ExecC = Empty, QueryC = Empty>, _: &BlockInfo, _: RegisteredSudo) -> AnyResult<AppResponse> { bail!("no sudo") }
+/// }
+///
+/// let mut app = App::default();
+/// app.register_module(
+///     ModuleRegistration::new("tokenfactory", TokenFactory)
+///         .claim_type_url("/osmosis.tokenfactory.v1beta1.MsgCreateDenom")
+///         .claim_sudo_namespace("tokenfactory"),
+/// )
+/// .unwrap();
+/// ```
+pub struct ModuleRegistration<ExecC, QueryC> {
+    name: String,
+    module: Rc<dyn RegisteredModule<ExecC, QueryC>>,
+    type_urls: Vec<String>,
+    query_paths: Vec<String>,
+    custom_msgs: Option<Claim<ExecC>>,
+    custom_queries: Option<Claim<QueryC>>,
+    sudo_namespaces: Vec<String>,
+}
+
+impl<ExecC, QueryC> ModuleRegistration<ExecC, QueryC> {
+    /// Creates a registration of the module, claiming nothing yet.
+    pub fn new(
+        name: impl Into<String>,
+        module: impl RegisteredModule<ExecC, QueryC> + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            module: Rc::new(module),
+            type_urls: vec![],
+            query_paths: vec![],
+            custom_msgs: None,
+            custom_queries: None,
+            sudo_namespaces: vec![],
+        }
+    }
+
+    /// Claims stargate messages with the type URL.
+    pub fn claim_type_url(mut self, type_url: impl Into<String>) -> Self {
+        self.type_urls.push(type_url.into());
+        self
+    }
+
+    /// Claims stargate queries with the path.
+    pub fn claim_query_path(mut self, path: impl Into<String>) -> Self {
+        self.query_paths.push(path.into());
+        self
+    }
+
+    /// Claims custom messages, for which the predicate returns `true`.
+    /// Messages claimed also by another module are rejected when executed.
+    pub fn claim_custom_msgs(mut self, predicate: impl Fn(&ExecC) -> bool + 'static) -> Self {
+        self.custom_msgs = Some(Rc::new(predicate));
+        self
+    }
+
+    /// Claims custom queries, for which the predicate returns `true`.
+    /// Queries claimed also by another module are rejected.
+    pub fn claim_custom_queries(mut self, predicate: impl Fn(&QueryC) -> bool + 'static) -> Self {
+        self.custom_queries = Some(Rc::new(predicate));
+        self
+    }
+
+    /// Claims privileged actions in the namespace, see [RegisteredSudo].
+    pub fn claim_sudo_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.sudo_namespaces.push(namespace.into());
+        self
+    }
+
+    /// Returns the name of the registered module.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Registered modules, stored with erased message types,
+/// so that the [Router](crate::Router) does not need type parameters for them.
+///
+/// Modules are registered only through the router, so all registrations
+/// should have the message types of the router. Registrations with other message types
+/// are rejected, instead of being skipped when looking up the claiming module.
+#[derive(Clone, Default)]
+pub(crate) struct ModuleRegistry {
+    /// Names of registered modules, with registrations of erased types.
+    registrations: Vec<(String, Rc<dyn Any>)>,
+}
+
+impl ModuleRegistry {
+    /// Registers the module, failing when any of its type URLs, query paths
+    /// or sudo namespaces is already claimed by another module,
+    /// or when other modules are registered with different message types.
+    pub(crate) fn register<ExecC: 'static, QueryC: 'static>(
+        &mut self,
+        registration: ModuleRegistration<ExecC, QueryC>,
+    ) -> AnyResult<()> {
+        for other in self.registrations::<ExecC, QueryC>() {
+            let other = other?;
+            if other.name == registration.name {
+                bail!("module '{}' is already registered", registration.name);
+            }
+            let claimed = |claims: &[String], other_claims: &[String]| {
+                claims
+                    .iter()
+                    .find(|claim| other_claims.contains(claim))
+                    .cloned()
+            };
+            if let Some(claim) = claimed(&registration.type_urls, &other.type_urls)
+                .or_else(|| claimed(&registration.query_paths, &other.query_paths))
+                .or_else(|| claimed(&registration.sudo_namespaces, &other.sudo_namespaces))
+            {
+                bail!("'{}' is already claimed by module '{}'", claim, other.name);
+            }
+        }
+        self.registrations
+            .push((registration.name.clone(), Rc::new(registration)));
+        Ok(())
+    }
+
+    /// Returns the module claiming stargate messages with the type URL.
+    pub(crate) fn by_type_url<ExecC: 'static, QueryC: 'static>(
+        &self,
+        type_url: &str,
+    ) -> AnyResult<Option<&dyn RegisteredModule<ExecC, QueryC>>> {
+        self.find(|registration| registration.type_urls.iter().any(|url| url == type_url))
+    }
+
+    /// Returns the module claiming stargate queries with the path.
+    pub(crate) fn by_query_path<ExecC: 'static, QueryC: 'static>(
+        &self,
+        path: &str,
+    ) -> AnyResult<Option<&dyn RegisteredModule<ExecC, QueryC>>> {
+        self.find(|registration| registration.query_paths.iter().any(|p| p == path))
+    }
+
+    /// Returns the module claiming the custom message,
+    /// failing when the message is claimed by more than one module.
+    pub(crate) fn by_custom_msg<ExecC: 'static, QueryC: 'static>(
+        &self,
+        msg: &ExecC,
+    ) -> AnyResult<Option<&dyn RegisteredModule<ExecC, QueryC>>> {
+        self.find(|registration| {
+            registration
+                .custom_msgs
+                .as_ref()
+                .is_some_and(|claim| claim(msg))
+        })
+    }
+
+    /// Returns the module claiming the custom query,
+    /// failing when the query is claimed by more than one module.
+    pub(crate) fn by_custom_query<ExecC: 'static, QueryC: 'static>(
+        &self,
+        request: &QueryC,
+    ) -> AnyResult<Option<&dyn RegisteredModule<ExecC, QueryC>>> {
+        self.find(|registration| {
+            registration
+                .custom_queries
+                .as_ref()
+                .is_some_and(|claim| claim(request))
+        })
+    }
+
+    /// Returns the module claiming privileged actions in the namespace.
+    pub(crate) fn by_sudo_namespace<ExecC: 'static, QueryC: 'static>(
+        &self,
+        namespace: &str,
+    ) -> AnyResult<Option<&dyn RegisteredModule<ExecC, QueryC>>> {
+        self.find(|registration| registration.sudo_namespaces.iter().any(|n| n == namespace))
+    }
+
+    /// Returns the only module with matching claims. Claims of custom messages and queries
+    /// are predicates, so their overlaps are detected only here, when both modules claim the message.
+    fn find<ExecC: 'static, QueryC: 'static>(
+        &self,
+        claims: impl Fn(&ModuleRegistration<ExecC, QueryC>) -> bool,
+    ) -> AnyResult<Option<&dyn RegisteredModule<ExecC, QueryC>>> {
+        let mut found: Option<&ModuleRegistration<ExecC, QueryC>> = None;
+        for registration in self.registrations::<ExecC, QueryC>() {
+            let registration = registration?;
+            if !claims(registration) {
+                continue;
+            }
+            if let Some(other) = found {
+                bail!(
+                    "message is claimed by both module '{}' and module '{}'",
+                    other.name,
+                    registration.name
+                );
+            }
+            found = Some(registration);
+        }
+        Ok(found.map(|registration| registration.module.as_ref()))
+    }
+
+    fn registrations<ExecC: 'static, QueryC: 'static>(
+        &self,
+    ) -> impl Iterator<Item = AnyResult<&ModuleRegistration<ExecC, QueryC>>> {
+        self.registrations.iter().map(|(name, registration)| {
+            registration.downcast_ref().ok_or_else(|| {
+                anyhow!(
+                    "module '{}' is registered with different message types",
+                    name
+                )
+            })
+        })
+    }
+}
@@ -0,0 +1,263 @@
+//! Search over the history of transactions and blocks, modelled after `tx_search`
+//! and `block_search` of Tendermint (CometBFT).
+//!
+//! A query is a list of conditions joined with `AND`, like
+//! `wasm._contract_address = 'contract0' AND wasm.action = 'swap'`.
+//! Every condition compares a tag with a value, where the tag is the type of an event
+//! and the key of its attribute separated with a dot. Transactions can also be searched by
+//! `tx.height` and `tx.hash`, blocks by `block.height`. Supported operators are
+//! `=`, `<`, `<=`, `>`, `>=`, `CONTAINS` and `EXISTS`. Values are quoted strings or numbers,
+//! numbers are compared numerically. Different conditions may be satisfied by different events.
+
+use crate::error::{bail, AnyResult};
+use cosmwasm_std::{BlockInfo, Event, Timestamp};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// Events emitted while processing a block, outside of any transaction.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BlockResults {
+    /// Height of the block.
+    pub height: u64,
+    /// Time of the block.
+    pub time: Timestamp,
+    /// Events emitted at the beginning of the block.
+    pub begin_block_events: Vec<Event>,
+    /// Events emitted at the end of the block.
+    pub end_block_events: Vec<Event>,
+}
+
+/// Results of processed blocks, by height,
+/// see [App::enable_block_history](crate::App::enable_block_history).
+#[derive(Clone, Debug, Default)]
+pub(crate) struct BlockHistory {
+    blocks: BTreeMap<u64, BlockResults>,
+}
+
+impl BlockHistory {
+    /// Records events emitted at the end of the current block and at the beginning of the next one.
+    pub(crate) fn record(
+        &mut self,
+        current: &BlockInfo,
+        next: &BlockInfo,
+        end_block_events: Vec<Event>,
+        begin_block_events: Vec<Event>,
+    ) {
+        self.blocks
+            .entry(current.height)
+            .or_insert_with(|| BlockResults::new(current))
+            .end_block_events
+            .extend(end_block_events);
+        if next.height != current.height {
+            let mut results = BlockResults::new(next);
+            results.begin_block_events = begin_block_events;
+            self.blocks.insert(next.height, results);
+        }
+    }
+
+    /// Returns the results of the block with specified height.
+    pub(crate) fn get(&self, height: u64) -> Option<&BlockResults> {
+        self.blocks.get(&height)
+    }
+
+    /// Returns the results of all recorded blocks, in order of height.
+    pub(crate) fn blocks(&self) -> impl Iterator<Item = &BlockResults> {
+        self.blocks.values()
+    }
+}
+
+impl BlockResults {
+    /// Creates results of the block without any events.
+    fn new(block: &BlockInfo) -> Self {
+        Self {
+            height: block.height,
+            time: block.time,
+            begin_block_events: vec![],
+            end_block_events: vec![],
+        }
+    }
+}
+
+/// Parsed search query, see the [module documentation](self) for the syntax.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventQuery {
+    conditions: Vec<Condition>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Condition {
+    tag: String,
+    operator: Operator,
+    operand: Operand,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operator {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    Exists,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Operand {
+    None,
+    String(String),
+    Number(f64),
+}
+
+impl FromStr for EventQuery {
+    type Err = crate::error::AnyError;
+
+    fn from_str(query: &str) -> AnyResult<Self> {
+        let tokens = tokenize(query)?;
+        let mut tokens = tokens.into_iter();
+        let mut conditions = vec![];
+        loop {
+            let Some(Token::Word(tag)) = tokens.next() else {
+                bail!("invalid query: expected tag in '{}'", query);
+            };
+            let operator = match tokens.next() {
+                Some(Token::Operator(operator)) => operator,
+                Some(Token::Word(word)) if word == "CONTAINS" => Operator::Contains,
+                Some(Token::Word(word)) if word == "EXISTS" => Operator::Exists,
+                _ => bail!("invalid query: expected operator after '{}'", tag),
+            };
+            let operand = if operator == Operator::Exists {
+                Operand::None
+            } else {
+                match tokens.next() {
+                    Some(Token::Quoted(value)) => Operand::String(value),
+                    Some(Token::Word(value)) => match value.parse() {
+                        Ok(number) => Operand::Number(number),
+                        Err(_) if operator == Operator::Eq => Operand::String(value),
+                        Err(_) => bail!("invalid query: expected number, got '{}'", value),
+                    },
+                    _ => bail!("invalid query: expected value after '{}'", tag),
+                }
+            };
+            if operator == Operator::Contains && !matches!(operand, Operand::String(_)) {
+                bail!("invalid query: CONTAINS requires a quoted string");
+            }
+            conditions.push(Condition {
+                tag,
+                operator,
+                operand,
+            });
+            match tokens.next() {
+                None => break,
+                Some(Token::Word(word)) if word == "AND" => continue,
+                _ => bail!("invalid query: expected AND in '{}'", query),
+            }
+        }
+        Ok(Self { conditions })
+    }
+}
+
+impl EventQuery {
+    /// Returns `true` when every condition is satisfied by at least one of the tags,
+    /// given as pairs of the tag name and value.
+    pub(crate) fn matches(&self, tags: &[(String, String)]) -> bool {
+        self.conditions.iter().all(|condition| {
+            tags.iter()
+                .any(|(tag, value)| *tag == condition.tag && condition.matches(value))
+        })
+    }
+}
+
+impl Condition {
+    fn matches(&self, value: &str) -> bool {
+        match (&self.operand, self.operator) {
+            (_, Operator::Exists) => true,
+            (Operand::String(operand), Operator::Eq) => value == operand,
+            (Operand::String(operand), Operator::Contains) => value.contains(operand.as_str()),
+            (Operand::String(operand), operator) => compare(operator, value, operand.as_str()),
+            (Operand::Number(operand), operator) => match value.parse::<f64>() {
+                Ok(value) => compare(operator, value, *operand),
+                Err(_) => false,
+            },
+            (Operand::None, _) => false,
+        }
+    }
+}
+
+fn compare<T: PartialOrd>(operator: Operator, value: T, operand: T) -> bool {
+    match operator {
+        Operator::Eq => value == operand,
+        Operator::Lt => value < operand,
+        Operator::Le => value <= operand,
+        Operator::Gt => value > operand,
+        Operator::Ge => value >= operand,
+        Operator::Contains | Operator::Exists => false,
+    }
+}
+
+/// Returns tags of the events, as pairs of `type.key` and the attribute value.
+pub(crate) fn event_tags(events: &[Event]) -> Vec<(String, String)> {
+    events
+        .iter()
+        .flat_map(|event| {
+            event
+                .attributes
+                .iter()
+                .map(move |attr| (format!("{}.{}", event.ty, attr.key), attr.value.clone()))
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Operator(Operator),
+}
+
+fn tokenize(query: &str) -> AnyResult<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = query.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '\'' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => value.push(c),
+                        None => bail!("invalid query: unterminated string in '{}'", query),
+                    }
+                }
+                tokens.push(Token::Quoted(value));
+            }
+            '=' | '<' | '>' => {
+                chars.next();
+                let or_equal = chars.next_if_eq(&'=').is_some();
+                tokens.push(Token::Operator(match (c, or_equal) {
+                    ('=', _) => Operator::Eq,
+                    ('<', false) => Operator::Lt,
+                    ('<', true) => Operator::Le,
+                    ('>', false) => Operator::Gt,
+                    _ => Operator::Ge,
+                }));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(c) =
+                    chars.next_if(|c| !c.is_whitespace() && !matches!(c, '=' | '<' | '>' | '\''))
+                {
+                    word.push(c);
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
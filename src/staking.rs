@@ -1,18 +1,28 @@
+use crate::accounts::DISTRIBUTION_MODULE;
+#[cfg(feature = "cosmwasm_1_1")]
+use crate::accounts::FEE_COLLECTOR;
 use crate::app::CosmosRouter;
-use crate::error::{anyhow, bail, AnyResult};
+use crate::error::{anyhow, bail, AnyContext, AnyResult, Error};
 use crate::executor::AppResponse;
+#[cfg(feature = "cosmwasm_1_1")]
+use crate::mint::{MintParams, Minter};
 use crate::prefixed_storage::{prefixed, prefixed_read};
-use crate::{BankSudo, Module};
+use crate::{BankSudo, Module, WasmSudo};
 use cosmwasm_std::{
     coin, ensure, ensure_eq, to_json_binary, Addr, AllDelegationsResponse, AllValidatorsResponse,
     Api, BankMsg, Binary, BlockInfo, BondedDenomResponse, Coin, CustomQuery, Decimal, Delegation,
-    DelegationResponse, DistributionMsg, Empty, Event, FullDelegation, Querier, StakingMsg,
-    StakingQuery, Storage, Timestamp, Uint128, Validator, ValidatorResponse,
+    DelegationResponse, DistributionMsg, Empty, Event, FullDelegation, Order, Querier,
+    QuerierWrapper, StakingMsg, StakingQuery, StdResult, Storage, Timestamp, Uint128, Validator,
+    ValidatorResponse,
 };
-use cw_storage_plus::{Deque, Item, Map};
+#[cfg(feature = "cosmwasm_1_1")]
+use cosmwasm_std::{from_json, BankQuery, CustomMsg, SupplyResponse};
+use cw_storage_plus::{Bound, Deque, Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 use schemars::JsonSchema;
+#[cfg(feature = "cosmwasm_1_1")]
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeSet, VecDeque};
+use std::collections::BTreeSet;
 
 /// A structure containing some general staking parameters.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -23,6 +33,13 @@ pub struct StakingInfo {
     pub unbonding_time: u64,
     /// Interest rate per year (60 * 60 * 24 * 365 seconds)
     pub apr: Decimal,
+    /// Maximum number of bonded validators, validators with the highest power are bonded first
+    #[serde(default = "default_max_validators")]
+    pub max_validators: u32,
+    /// Maximum number of pending unbonding entries per (delegator, validator) tuple
+    /// and redelegation entries per (delegator, source, destination) tuple
+    #[serde(default = "default_max_entries")]
+    pub max_entries: u32,
 }
 
 impl Default for StakingInfo {
@@ -32,10 +49,36 @@ impl Default for StakingInfo {
             bonded_denom: "TOKEN".to_string(),
             unbonding_time: 60,
             apr: Decimal::percent(10),
+            max_validators: default_max_validators(),
+            max_entries: default_max_entries(),
         }
     }
 }
 
+/// Default maximum number of bonded validators, the same as in cosmos-sdk.
+fn default_max_validators() -> u32 {
+    100
+}
+
+/// Default maximum number of pending entries, the same as in cosmos-sdk.
+fn default_max_entries() -> u32 {
+    7
+}
+
+/// Bonding status of a validator.
+///
+/// See <https://github.com/cosmos/cosmos-sdk/blob/v0.47.5/x/staking/types/staking.pb.go#L37-L49>
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub enum ValidatorStatus {
+    /// Validator is in the active set and earns rewards.
+    #[default]
+    Bonded,
+    /// Validator left the active set and waits for the unbonding time to pass.
+    Unbonding,
+    /// Validator is not in the active set.
+    Unbonded,
+}
+
 /// The number of stake and rewards of this validator the staker has. These can be fractional in case of slashing.
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
 struct Shares {
@@ -63,6 +106,18 @@ struct ValidatorInfo {
     stake: Uint128,
     /// The block time when this validator's rewards were last update. This is needed for rewards calculation.
     last_rewards_calculation: Timestamp,
+    /// Bonding status of this validator.
+    #[serde(default)]
+    status: ValidatorStatus,
+    /// The block time when the unbonding of this validator completes.
+    #[serde(default)]
+    unbonding_completion: Option<Timestamp>,
+    /// Jailed validators are removed from the active set.
+    #[serde(default)]
+    jailed: bool,
+    /// Tombstoned validators are jailed forever.
+    #[serde(default)]
+    tombstoned: bool,
 }
 
 impl ValidatorInfo {
@@ -71,16 +126,86 @@ impl ValidatorInfo {
             stakers: BTreeSet::new(),
             stake: Uint128::zero(),
             last_rewards_calculation: block_time,
+            status: ValidatorStatus::Unbonded,
+            unbonding_completion: None,
+            jailed: false,
+            tombstoned: false,
         }
     }
+
+    /// Returns `true` when this validator is in the active set.
+    pub fn is_bonded(&self) -> bool {
+        self.status == ValidatorStatus::Bonded
+    }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-struct Unbonding {
+/// Unbonding entry that has not completed yet.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Unbonding {
+    /// Delegator's address.
     pub delegator: Addr,
+    /// Address of the validator the tokens were unbonded from.
     pub validator: Addr,
+    /// Amount of tokens to be paid out, reduced when the validator is slashed.
     pub amount: Uint128,
+    /// Amount of unbonded tokens, before any slashing.
+    #[serde(default)]
+    pub initial_balance: Uint128,
+    /// Time at which the unbonding completes and the tokens are paid out.
     pub payout_at: Timestamp,
+    /// Block height at which the unbonding started.
+    #[serde(default)]
+    pub creation_height: u64,
+}
+
+/// Secondary indexes of the unbonding queue.
+struct UnbondingIndexes<'a> {
+    /// (delegator, validator) -> unbonding entries
+    delegation: MultiIndex<'a, (Addr, Addr), Unbonding, (u64, u64)>,
+    /// validator -> unbonding entries
+    validator: MultiIndex<'a, Addr, Unbonding, (u64, u64)>,
+}
+
+impl IndexList<Unbonding> for UnbondingIndexes<'_> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Unbonding>> + '_> {
+        let v: Vec<&dyn Index<Unbonding>> = vec![&self.delegation, &self.validator];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Redelegation entry that has not completed yet.
+///
+/// Until completion, the redelegated tokens can be slashed for infractions of the source validator,
+/// and the destination validator can not be used as a source of another redelegation.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Redelegation {
+    /// Delegator's address.
+    pub delegator: Addr,
+    /// Address of the validator the tokens were moved from.
+    pub src_validator: Addr,
+    /// Address of the validator the tokens were moved to.
+    pub dst_validator: Addr,
+    /// Amount of tokens moved to the destination validator.
+    pub amount: Uint128,
+    /// Block height at which the redelegation started.
+    pub creation_height: u64,
+    /// Time at which the redelegation completes.
+    pub completion_time: Timestamp,
+}
+
+/// Secondary indexes of the redelegation queue.
+struct RedelegationIndexes<'a> {
+    /// delegator -> redelegation entries
+    delegator: MultiIndex<'a, Addr, Redelegation, (u64, u64)>,
+    /// (source validator, destination validator) -> redelegation entries
+    validators: MultiIndex<'a, (Addr, Addr), Redelegation, (u64, u64)>,
+}
+
+impl IndexList<Redelegation> for RedelegationIndexes<'_> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Redelegation>> + '_> {
+        let v: Vec<&dyn Index<Redelegation>> = vec![&self.delegator, &self.validators];
+        Box::new(v.into_iter())
+    }
 }
 
 const STAKING_INFO: Item<StakingInfo> = Item::new("staking_info");
@@ -92,7 +217,69 @@ const VALIDATORS: Deque<Validator> = Deque::new("validators");
 /// Contains additional info for each validator
 const VALIDATOR_INFO: Map<&Addr, ValidatorInfo> = Map::new("validator_info");
 /// The queue of unbonding operations. This is needed because unbonding has a waiting time. See [`StakeKeeper`]
-const UNBONDING_QUEUE: Item<VecDeque<Unbonding>> = Item::new("unbonding_queue");
+///
+/// (payout time in nanoseconds, sequence number) -> unbonding,
+/// so the entries are ordered by payout time.
+fn unbonding_queue<'a>() -> IndexedMap<'a, (u64, u64), Unbonding, UnbondingIndexes<'a>> {
+    IndexedMap::new(
+        "unbonding_queue",
+        UnbondingIndexes {
+            delegation: MultiIndex::new(
+                |_, ub| (ub.delegator.clone(), ub.validator.clone()),
+                "unbonding_queue",
+                "unbonding_queue__delegation",
+            ),
+            validator: MultiIndex::new(
+                |_, ub| ub.validator.clone(),
+                "unbonding_queue",
+                "unbonding_queue__validator",
+            ),
+        },
+    )
+}
+/// Sequence number distinguishing unbondings with the same payout time
+const UNBONDING_SEQUENCE: Item<u64> = Item::new("unbonding_sequence");
+/// The queue of redelegations. Needed to slash redelegated tokens for infractions of the source validator.
+///
+/// (completion time in nanoseconds, sequence number) -> redelegation,
+/// so the entries are ordered by completion time.
+fn redelegation_queue<'a>() -> IndexedMap<'a, (u64, u64), Redelegation, RedelegationIndexes<'a>> {
+    IndexedMap::new(
+        "redelegation_queue",
+        RedelegationIndexes {
+            delegator: MultiIndex::new(
+                |_, r| r.delegator.clone(),
+                "redelegation_queue",
+                "redelegation_queue__delegator",
+            ),
+            validators: MultiIndex::new(
+                |_, r| (r.src_validator.clone(), r.dst_validator.clone()),
+                "redelegation_queue",
+                "redelegation_queue__validators",
+            ),
+        },
+    )
+}
+/// Sequence number distinguishing redelegations with the same completion time
+const REDELEGATION_SEQUENCE: Item<u64> = Item::new("redelegation_sequence");
+/// Tokens slashed with [`StakingSudo::Slash`], which are not burned and stay in the staking module
+const UNBURNED_SLASHES: Item<Uint128> = Item::new("unburned_slashes");
+/// Inflation parameters, minting is disabled when not set
+#[cfg(feature = "cosmwasm_1_1")]
+const MINT_PARAMS: Item<MintParams> = Item::new("mint_params");
+/// Current state of the inflation
+#[cfg(feature = "cosmwasm_1_1")]
+const MINTER: Item<Minter> = Item::new("minter");
+/// Height of the last block for which tokens were minted
+#[cfg(feature = "cosmwasm_1_1")]
+const LAST_MINT_HEIGHT: Item<u64> = Item::new("last_mint_height");
+/// (validator) -> commission from minted rewards, not withdrawn yet
+#[cfg(feature = "cosmwasm_1_1")]
+const VALIDATOR_COMMISSION: Map<&Addr, Decimal> = Map::new("validator_commission");
+/// Contracts notified about staking changes, see [`StakingHookMsg`]
+const STAKING_HOOKS: Item<Vec<Addr>> = Item::new("staking_hooks");
+/// Hook messages waiting to be sent to the registered contracts
+const PENDING_HOOKS: Item<Vec<StakingHookMsg>> = Item::new("pending_staking_hooks");
 /// (addr) -> addr. Maps addresses to the address they have delegated
 /// to receive their staking rewards. A missing key => no delegation
 /// has been set.
@@ -107,14 +294,71 @@ pub const NAMESPACE_DISTRIBUTION: &[u8] = b"distribution";
 /// We need to expand on this, but we will need this to properly test out staking
 #[derive(Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub enum StakingSudo {
-    /// Slashes the given percentage of the validator's stake.
-    /// For now, you cannot slash retrospectively in tests.
+    /// Slashes the given percentage of the validator's stake and of all its pending unbondings.
+    /// Slashed tokens are not burned and stay in the staking module.
+    /// Use [`StakingSudo::SlashAtHeight`] to slash retroactively.
     Slash {
         /// Validator's address.
         validator: String,
         /// Percentage of the validator's stake.
         percentage: Decimal,
     },
+    /// Slashes the validator for an infraction committed at the given block height.
+    ///
+    /// Like in cosmos-sdk, the slashed amount is the given percentage of the validator's power
+    /// at the infraction height. Unbondings and redelegations started from this validator
+    /// at or after the infraction height and not yet completed are slashed by the percentage
+    /// of their initial amount, and the rest of the slashed amount is taken from the current stake
+    /// of the validator.
+    ///
+    /// Slashed tokens are burned from the balance of the staking module,
+    /// so slashing fails when the staking module does not hold them,
+    /// e.g. when stakes were set up without transferring tokens to the staking module.
+    SlashAtHeight {
+        /// Validator's address.
+        validator: String,
+        /// Percentage of the validator's power at the infraction height.
+        percentage: Decimal,
+        /// Block height at which the infraction was committed.
+        infraction_height: u64,
+        /// Amount of tokens bonded to the validator at the infraction height,
+        /// like the power reported in the evidence of the infraction.
+        power: Uint128,
+    },
+    /// Jails the validator, removing it from the active set.
+    /// Jailed validators do not earn any rewards.
+    Jail {
+        /// Validator's address.
+        validator: String,
+    },
+    /// Unjails the validator, it becomes a candidate for the active set again.
+    Unjail {
+        /// Validator's address.
+        validator: String,
+    },
+    /// Jails the validator forever, tombstoned validators can not be unjailed.
+    Tombstone {
+        /// Validator's address.
+        validator: String,
+    },
+    /// Registers the contract to be notified about staking changes, see [`StakingHookMsg`].
+    AddHook {
+        /// Contract's address.
+        contract: String,
+    },
+    /// Removes the contract from the contracts notified about staking changes.
+    RemoveHook {
+        /// Contract's address.
+        contract: String,
+    },
+    /// Pays the commission accumulated by the validator from minted rewards
+    /// out of the distribution module to the validator's address, see [mint](crate::mint).
+    /// Fractions of tokens stay accumulated until the next withdrawal.
+    #[cfg(feature = "cosmwasm_1_1")]
+    WithdrawValidatorCommission {
+        /// Validator's address.
+        validator: String,
+    },
     /// Causes the unbonding queue to be processed.
     /// This needs to be triggered manually, since there is no good place to do this right now.
     /// In cosmos-sdk, this is done in `EndBlock`, but we don't have that here.
@@ -122,6 +366,49 @@ pub enum StakingSudo {
     ProcessQueue {},
 }
 
+/// Notification about a staking change, sent to the `sudo` entry-point of all contracts
+/// registered with [`StakingSudo::AddHook`].
+///
+/// Hooks are called after the staking operation is processed, in the order the changes happened.
+/// Changes made directly through [`StakeKeeper`] methods (like adding a validator) are sent
+/// after the next staking message or block update.
+/// An error returned by the contract fails the whole staking operation.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StakingHookMsg {
+    /// Delegation was created or its amount has changed.
+    AfterDelegationModified {
+        /// Delegator's address.
+        delegator: String,
+        /// Validator's address.
+        validator: String,
+    },
+    /// Delegation was removed, because the whole stake was unbonded or redelegated.
+    AfterDelegationRemoved {
+        /// Delegator's address.
+        delegator: String,
+        /// Validator's address.
+        validator: String,
+    },
+    /// Validator was slashed.
+    AfterValidatorSlashed {
+        /// Validator's address.
+        validator: String,
+        /// Slashed percentage of the validator's stake.
+        fraction: Decimal,
+    },
+    /// Validator has joined the active set.
+    AfterValidatorBonded {
+        /// Validator's address.
+        validator: String,
+    },
+    /// Validator has left the active set and started unbonding.
+    AfterValidatorBeginUnbonding {
+        /// Validator's address.
+        validator: String,
+    },
+}
+
 /// A trait defining a behavior of the stake keeper.
 ///
 /// Manages staking operations, vital for testing contracts in proof-of-stake (PoS) blockchain environments.
@@ -164,6 +451,50 @@ impl StakeKeeper {
         }
     }
 
+    /// Returns the address of the staking module and the amount of tokens it should hold:
+    /// the whole stake of all validators, all tokens waiting for the completion of unbonding
+    /// and all tokens slashed without burning.
+    pub(crate) fn expected_module_balance(&self, storage: &dyn Storage) -> AnyResult<(Addr, Coin)> {
+        let staking_storage = prefixed_read(storage, NAMESPACE_STAKING);
+        let staking_info = Self::get_staking_info(&staking_storage)?;
+        let mut total = Uint128::zero();
+        for item in VALIDATOR_INFO.range(&staking_storage, None, None, Order::Ascending) {
+            total = total.checked_add(item?.1.stake)?;
+        }
+        for item in unbonding_queue().range(&staking_storage, None, None, Order::Ascending) {
+            total = total.checked_add(item?.1.amount)?;
+        }
+        total = total.checked_add(
+            UNBURNED_SLASHES
+                .may_load(&staking_storage)?
+                .unwrap_or_default(),
+        )?;
+        Ok((
+            self.module_addr.clone(),
+            coin(total.u128(), staking_info.bonded_denom),
+        ))
+    }
+
+    /// Checks that the staking module holds exactly the tokens it should hold,
+    /// see [expected_module_balance](Self::expected_module_balance).
+    pub(crate) fn check_module_balance(
+        &self,
+        storage: &dyn Storage,
+        querier: &dyn Querier,
+    ) -> AnyResult<()> {
+        let (module_addr, expected) = self.expected_module_balance(storage)?;
+        let balance =
+            QuerierWrapper::<Empty>::new(querier).query_balance(&module_addr, &expected.denom)?;
+        if balance != expected {
+            bail!(
+                "staking module holds {}, but {} is bonded, unbonding or slashed",
+                balance,
+                expected
+            );
+        }
+        Ok(())
+    }
+
     /// Provides some general parameters to the stake keeper
     pub fn setup(&self, storage: &mut dyn Storage, staking_info: StakingInfo) -> AnyResult<()> {
         let mut storage = prefixed(storage, NAMESPACE_STAKING);
@@ -171,6 +502,48 @@ impl StakeKeeper {
         Ok(())
     }
 
+    /// Enables minting with the given inflation parameters and initial inflation rate,
+    /// see [mint](crate::mint) for details.
+    #[cfg(feature = "cosmwasm_1_1")]
+    pub fn setup_mint(
+        &self,
+        storage: &mut dyn Storage,
+        params: MintParams,
+        inflation: Decimal,
+    ) -> AnyResult<()> {
+        let mut storage = prefixed(storage, NAMESPACE_STAKING);
+        MINT_PARAMS.save(&mut storage, &params)?;
+        MINTER.save(
+            &mut storage,
+            &Minter {
+                inflation,
+                annual_provisions: Decimal::zero(),
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Returns the current state of the inflation (or `None` if minting is not enabled).
+    #[cfg(feature = "cosmwasm_1_1")]
+    pub fn minter(&self, storage: &dyn Storage) -> AnyResult<Option<Minter>> {
+        let storage = prefixed_read(storage, NAMESPACE_STAKING);
+        Ok(MINTER.may_load(&storage)?)
+    }
+
+    /// Returns the commission accumulated by the validator from minted rewards
+    /// and not withdrawn yet, see [`StakingSudo::WithdrawValidatorCommission`].
+    #[cfg(feature = "cosmwasm_1_1")]
+    pub fn validator_commission(
+        &self,
+        storage: &dyn Storage,
+        validator: &Addr,
+    ) -> AnyResult<Decimal> {
+        let storage = prefixed_read(storage, NAMESPACE_STAKING);
+        Ok(VALIDATOR_COMMISSION
+            .may_load(&storage, validator)?
+            .unwrap_or_default())
+    }
+
     /// Add a new validator available for staking
     pub fn add_validator(
         &self,
@@ -192,13 +565,184 @@ impl StakeKeeper {
         VALIDATOR_MAP.save(&mut storage, &val_addr, &validator)?;
         VALIDATORS.push_back(&mut storage, &validator)?;
         VALIDATOR_INFO.save(&mut storage, &val_addr, &ValidatorInfo::new(block.time))?;
+        self.update_validator_set(api, &mut storage, block)?;
+        Ok(())
+    }
+
+    /// Returns the bonding status of the validator with the given address
+    /// (or `None` if there is no such validator).
+    pub fn validator_status(
+        &self,
+        storage: &dyn Storage,
+        validator: &Addr,
+    ) -> AnyResult<Option<ValidatorStatus>> {
+        let staking_storage = prefixed_read(storage, NAMESPACE_STAKING);
+        Ok(VALIDATOR_INFO
+            .may_load(&staking_storage, validator)?
+            .map(|info| info.status))
+    }
+
+    /// Returns `true` when the validator with the given address is jailed.
+    pub fn is_jailed(&self, storage: &dyn Storage, validator: &Addr) -> AnyResult<bool> {
+        let staking_storage = prefixed_read(storage, NAMESPACE_STAKING);
+        Ok(VALIDATOR_INFO
+            .may_load(&staking_storage, validator)?
+            .map(|info| info.jailed)
+            .unwrap_or_default())
+    }
+
+    /// Returns the contracts notified about staking changes.
+    pub fn hooks(&self, storage: &dyn Storage) -> AnyResult<Vec<Addr>> {
+        let staking_storage = prefixed_read(storage, NAMESPACE_STAKING);
+        Ok(STAKING_HOOKS
+            .may_load(&staking_storage)?
+            .unwrap_or_default())
+    }
+
+    /// Queues the hook message, when there are contracts registered to receive it.
+    fn queue_hook(staking_storage: &mut dyn Storage, msg: StakingHookMsg) -> AnyResult<()> {
+        if STAKING_HOOKS
+            .may_load(staking_storage)?
+            .unwrap_or_default()
+            .is_empty()
+        {
+            return Ok(());
+        }
+        let mut pending = PENDING_HOOKS.may_load(staking_storage)?.unwrap_or_default();
+        pending.push(msg);
+        PENDING_HOOKS.save(staking_storage, &pending)?;
         Ok(())
     }
 
+    /// Sends all queued hook messages to the registered contracts.
+    fn dispatch_hooks<ExecC, QueryC: CustomQuery>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+    ) -> AnyResult<Vec<Event>> {
+        let mut staking_storage = prefixed(storage, NAMESPACE_STAKING);
+        let Some(pending) = PENDING_HOOKS.may_load(&staking_storage)? else {
+            return Ok(vec![]);
+        };
+        PENDING_HOOKS.remove(&mut staking_storage);
+        let hooks = STAKING_HOOKS
+            .may_load(&staking_storage)?
+            .unwrap_or_default();
+        let mut events = vec![];
+        for msg in pending {
+            for contract in hooks.iter() {
+                let response =
+                    router.sudo(api, storage, block, WasmSudo::new(contract, &msg)?.into())?;
+                events.extend(response.events);
+            }
+        }
+        Ok(events)
+    }
+
+    /// Returns all pending unbondings of the given delegator, sorted by completion time.
+    pub fn unbonding_delegations(
+        &self,
+        storage: &dyn Storage,
+        delegator: &Addr,
+    ) -> AnyResult<Vec<Unbonding>> {
+        let staking_storage = prefixed_read(storage, NAMESPACE_STAKING);
+        let mut unbondings = unbonding_queue()
+            .idx
+            .delegation
+            .sub_prefix(delegator.clone())
+            .range(&staking_storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        unbondings.sort_by_key(|((_, key), _)| *key);
+        Ok(unbondings
+            .into_iter()
+            .map(|(_, unbonding)| unbonding)
+            .collect())
+    }
+
+    /// Returns pending unbondings of the given delegator from the given validator.
+    fn get_unbondings(
+        staking_storage: &dyn Storage,
+        delegator: &Addr,
+        validator: &Addr,
+    ) -> AnyResult<Vec<Unbonding>> {
+        Ok(unbonding_queue()
+            .idx
+            .delegation
+            .prefix((delegator.clone(), validator.clone()))
+            .range(staking_storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, unbonding)| unbonding))
+            .collect::<StdResult<_>>()?)
+    }
+
+    /// Returns all pending redelegations of the given delegator, sorted by completion time.
+    pub fn redelegations(
+        &self,
+        storage: &dyn Storage,
+        delegator: &Addr,
+    ) -> AnyResult<Vec<Redelegation>> {
+        let staking_storage = prefixed_read(storage, NAMESPACE_STAKING);
+        Ok(Self::get_redelegations(&staking_storage, delegator)?)
+    }
+
+    /// Returns pending redelegations of the given delegator, sorted by completion time.
+    fn get_redelegations(
+        staking_storage: &dyn Storage,
+        delegator: &Addr,
+    ) -> StdResult<Vec<Redelegation>> {
+        redelegation_queue()
+            .idx
+            .delegator
+            .prefix(delegator.clone())
+            .range(staking_storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, redelegation)| redelegation))
+            .collect()
+    }
+
+    /// Returns all pending redelegations from the given source validator to the given
+    /// destination validator, sorted by completion time.
+    pub fn redelegations_between(
+        &self,
+        storage: &dyn Storage,
+        src_validator: &Addr,
+        dst_validator: &Addr,
+    ) -> AnyResult<Vec<Redelegation>> {
+        let staking_storage = prefixed_read(storage, NAMESPACE_STAKING);
+        Ok(redelegation_queue()
+            .idx
+            .validators
+            .prefix((src_validator.clone(), dst_validator.clone()))
+            .range(&staking_storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, redelegation)| redelegation))
+            .collect::<StdResult<_>>()?)
+    }
+
     fn get_staking_info(staking_storage: &dyn Storage) -> AnyResult<StakingInfo> {
         Ok(STAKING_INFO.may_load(staking_storage)?.unwrap_or_default())
     }
 
+    /// Returns the interest rate used for calculating rewards.
+    /// When minting is enabled, rewards are funded by the minted tokens instead.
+    fn get_apr(staking_storage: &dyn Storage) -> AnyResult<Decimal> {
+        if Self::is_minting(staking_storage)? {
+            return Ok(Decimal::zero());
+        }
+        Ok(Self::get_staking_info(staking_storage)?.apr)
+    }
+
+    /// Returns `true` when rewards are funded by minting, see [mint](crate::mint).
+    fn is_minting(staking_storage: &dyn Storage) -> AnyResult<bool> {
+        #[cfg(feature = "cosmwasm_1_1")]
+        let minting = MINT_PARAMS.may_load(staking_storage)?.is_some();
+        #[cfg(not(feature = "cosmwasm_1_1"))]
+        let minting = {
+            let _ = staking_storage;
+            false
+        };
+        Ok(minting)
+    }
+
     /// Returns the rewards of the given delegator at the given validator
     pub fn get_rewards(
         &self,
@@ -211,7 +755,7 @@ impl StakeKeeper {
 
         let validator_obj = match self.get_validator(&staking_storage, validator)? {
             Some(validator) => validator,
-            None => bail!("validator {} not found", validator),
+            None => bail!(Error::unknown_validator(validator)),
         };
         // calculate rewards using fixed ratio
         let shares = match STAKES.load(&staking_storage, (delegator, validator)) {
@@ -241,14 +785,19 @@ impl StakeKeeper {
     ) -> AnyResult<Coin> {
         let staking_info = Self::get_staking_info(staking_storage)?;
 
-        // calculate missing rewards without updating the validator to reduce rounding errors
-        let new_validator_rewards = Self::calculate_rewards(
-            block.time,
-            validator_info.last_rewards_calculation,
-            staking_info.apr,
-            validator.commission,
-            validator_info.stake,
-        );
+        // calculate missing rewards without updating the validator to reduce rounding errors,
+        // validators outside the active set do not earn any rewards
+        let new_validator_rewards = if validator_info.is_bonded() {
+            Self::calculate_rewards(
+                block.time,
+                validator_info.last_rewards_calculation,
+                Self::get_apr(staking_storage)?,
+                validator.commission,
+                validator_info.stake,
+            )
+        } else {
+            Decimal::zero()
+        };
 
         // calculate the delegator's share of those
         let delegator_rewards =
@@ -290,12 +839,10 @@ impl StakeKeeper {
         block: &BlockInfo,
         validator: &Addr,
     ) -> AnyResult<()> {
-        let staking_info = Self::get_staking_info(staking_storage)?;
-
         let mut validator_info = VALIDATOR_INFO
             .may_load(staking_storage, validator)?
             // https://github.com/cosmos/cosmos-sdk/blob/3c5387048f75d7e78b40c5b8d2421fdb8f5d973a/x/staking/types/errors.go#L15
-            .ok_or_else(|| anyhow!("validator does not exist"))?;
+            .ok_or_else(|| Error::unknown_validator(validator))?;
 
         let validator_obj = VALIDATOR_MAP.load(staking_storage, validator)?;
 
@@ -303,13 +850,18 @@ impl StakeKeeper {
             return Ok(());
         }
 
-        let new_rewards = Self::calculate_rewards(
-            block.time,
-            validator_info.last_rewards_calculation,
-            staking_info.apr,
-            validator_obj.commission,
-            validator_info.stake,
-        );
+        // validators outside the active set do not earn any rewards
+        let new_rewards = if validator_info.is_bonded() {
+            Self::calculate_rewards(
+                block.time,
+                validator_info.last_rewards_calculation,
+                Self::get_apr(staking_storage)?,
+                validator_obj.commission,
+                validator_info.stake,
+            )
+        } else {
+            Decimal::zero()
+        };
 
         // update validator info
         validator_info.last_rewards_calculation = block.time;
@@ -335,6 +887,155 @@ impl StakeKeeper {
         Ok(())
     }
 
+    /// Mints the block provisions for all blocks since the last minting into the fee collector,
+    /// moves them to the distribution module and allocates them as rewards.
+    ///
+    /// See <https://github.com/cosmos/cosmos-sdk/blob/v0.47.5/x/mint/abci.go>
+    #[cfg(feature = "cosmwasm_1_1")]
+    fn mint<ExecC, QueryC: CustomQuery>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+    ) -> AnyResult<Vec<Event>> {
+        let staking_storage = prefixed_read(storage, NAMESPACE_STAKING);
+        let Some(params) = MINT_PARAMS.may_load(&staking_storage)? else {
+            return Ok(vec![]);
+        };
+        let blocks = match LAST_MINT_HEIGHT.may_load(&staking_storage)? {
+            Some(height) => block.height.saturating_sub(height),
+            None => 1,
+        };
+        if blocks == 0 {
+            return Ok(vec![]);
+        }
+        let staking_info = Self::get_staking_info(&staking_storage)?;
+        let mut minter = MINTER.may_load(&staking_storage)?.unwrap_or_default();
+
+        // recalculate inflation based on the bonded ratio
+        let bonded_tokens = self
+            .get_bonded_validators(&staking_storage)?
+            .into_iter()
+            .map(|validator| {
+                VALIDATOR_INFO
+                    .load(&staking_storage, &Addr::unchecked(validator.address))
+                    .map(|info| info.stake)
+            })
+            .sum::<StdResult<Uint128>>()?;
+        let supply: SupplyResponse = from_json(
+            router.query(
+                api,
+                storage,
+                block,
+                BankQuery::Supply {
+                    denom: staking_info.bonded_denom.clone(),
+                }
+                .into(),
+            )?,
+        )?;
+        let total_supply = supply.amount.amount;
+        let bonded_ratio = if total_supply.is_zero() {
+            Decimal::zero()
+        } else {
+            Decimal::from_ratio(bonded_tokens, total_supply)
+        };
+        minter.inflation = minter.next_inflation_rate(&params, bonded_ratio);
+        minter.annual_provisions = minter.next_annual_provisions(total_supply);
+        let minted = minter
+            .block_provision(&params)
+            .checked_mul(Uint128::from(blocks))?;
+
+        let mut staking_storage = prefixed(storage, NAMESPACE_STAKING);
+        MINTER.save(&mut staking_storage, &minter)?;
+        LAST_MINT_HEIGHT.save(&mut staking_storage, &block.height)?;
+        self.allocate_rewards(&mut staking_storage, minted)?;
+
+        if !minted.is_zero() {
+            let amount = vec![coin(minted.u128(), &staking_info.bonded_denom)];
+            router.sudo(
+                api,
+                storage,
+                block,
+                BankSudo::Mint {
+                    to_address: FEE_COLLECTOR.to_string(),
+                    amount: amount.clone(),
+                }
+                .into(),
+            )?;
+            router.execute(
+                api,
+                storage,
+                block,
+                Addr::unchecked(FEE_COLLECTOR),
+                BankMsg::Send {
+                    to_address: DISTRIBUTION_MODULE.to_string(),
+                    amount,
+                }
+                .into(),
+            )?;
+        }
+
+        Ok(vec![Event::new("mint")
+            .add_attribute("bonded_ratio", bonded_ratio.to_string())
+            .add_attribute("inflation", minter.inflation.to_string())
+            .add_attribute("annual_provisions", minter.annual_provisions.to_string())
+            .add_attribute("amount", minted.to_string())])
+    }
+
+    /// Allocates the given amount of tokens as rewards to the bonded validators,
+    /// proportionally to their power. Validator's commission is not paid to the delegators,
+    /// but accumulated for the validator until it is withdrawn.
+    #[cfg(feature = "cosmwasm_1_1")]
+    fn allocate_rewards(
+        &self,
+        staking_storage: &mut dyn Storage,
+        amount: Uint128,
+    ) -> AnyResult<()> {
+        let mut validators = vec![];
+        for validator in self.get_bonded_validators(staking_storage)? {
+            let validator_addr = Addr::unchecked(&validator.address);
+            let validator_info = VALIDATOR_INFO.load(staking_storage, &validator_addr)?;
+            validators.push((validator_addr, validator.commission, validator_info));
+        }
+        let total_power = validators
+            .iter()
+            .map(|(_, _, info)| info.stake)
+            .sum::<Uint128>();
+        if total_power.is_zero() || amount.is_zero() {
+            return Ok(());
+        }
+        for (validator_addr, commission, validator_info) in validators {
+            let rewards = Decimal::from_ratio(amount, 1u128)
+                * Decimal::from_ratio(validator_info.stake, total_power);
+            let commission = rewards * commission;
+            if !commission.is_zero() {
+                let accumulated = VALIDATOR_COMMISSION
+                    .may_load(staking_storage, &validator_addr)?
+                    .unwrap_or_default();
+                VALIDATOR_COMMISSION.save(
+                    staking_storage,
+                    &validator_addr,
+                    &(accumulated + commission),
+                )?;
+            }
+            let rewards = rewards - commission;
+            for staker in validator_info.stakers.iter() {
+                STAKES.update(
+                    staking_storage,
+                    (staker, &validator_addr),
+                    |shares| -> AnyResult<_> {
+                        let mut shares =
+                            shares.expect("all stakers in validator_info should exist");
+                        shares.rewards += shares.share_of_rewards(&validator_info, rewards);
+                        Ok(shares)
+                    },
+                )?;
+            }
+        }
+        Ok(())
+    }
+
     /// Returns the single validator with the given address (or `None` if there is no such validator)
     fn get_validator(
         &self,
@@ -350,6 +1051,21 @@ impl StakeKeeper {
         Ok(res?)
     }
 
+    /// Returns all validators in the active set
+    fn get_bonded_validators(&self, staking_storage: &dyn Storage) -> AnyResult<Vec<Validator>> {
+        let mut validators = vec![];
+        for validator in self.get_validators(staking_storage)? {
+            let validator_addr = Addr::unchecked(&validator.address);
+            if VALIDATOR_INFO
+                .load(staking_storage, &validator_addr)?
+                .is_bonded()
+            {
+                validators.push(validator);
+            }
+        }
+        Ok(validators)
+    }
+
     fn get_stake(
         &self,
         staking_storage: &dyn Storage,
@@ -432,7 +1148,7 @@ impl StakeKeeper {
         let mut shares = if sub {
             // see https://github.com/cosmos/cosmos-sdk/blob/3c5387048f75d7e78b40c5b8d2421fdb8f5d973a/x/staking/keeper/delegation.go#L1005-L1007
             // and https://github.com/cosmos/cosmos-sdk/blob/3c5387048f75d7e78b40c5b8d2421fdb8f5d973a/x/staking/types/errors.go#L31
-            shares.ok_or_else(|| anyhow!("no delegation for (address, validator) tuple"))?
+            shares.ok_or_else(Error::no_delegation)?
         } else {
             shares.unwrap_or_default()
         };
@@ -441,7 +1157,7 @@ impl StakeKeeper {
         if sub {
             // see https://github.com/cosmos/cosmos-sdk/blob/3c5387048f75d7e78b40c5b8d2421fdb8f5d973a/x/staking/keeper/delegation.go#L1019-L1022
             if amount_dec > shares.stake {
-                bail!("invalid shares amount");
+                bail!(Error::invalid_shares_amount());
             }
             shares.stake -= amount_dec;
             validator_info.stake = validator_info.stake.checked_sub(amount)?;
@@ -455,48 +1171,303 @@ impl StakeKeeper {
             // no more stake, so remove
             STAKES.remove(staking_storage, (delegator, validator));
             validator_info.stakers.remove(delegator);
+            Self::queue_hook(
+                staking_storage,
+                StakingHookMsg::AfterDelegationRemoved {
+                    delegator: delegator.to_string(),
+                    validator: validator.to_string(),
+                },
+            )?;
         } else {
             STAKES.save(staking_storage, (delegator, validator), &shares)?;
             validator_info.stakers.insert(delegator.clone());
+            Self::queue_hook(
+                staking_storage,
+                StakingHookMsg::AfterDelegationModified {
+                    delegator: delegator.to_string(),
+                    validator: validator.to_string(),
+                },
+            )?;
         }
         // save updated validator info
         VALIDATOR_INFO.save(staking_storage, validator, &validator_info)?;
 
-        Ok(())
+        // the change of validator's power may change the active set
+        self.update_validator_set(api, staking_storage, block)
     }
 
-    fn slash(
+    /// Recalculates the active set of validators.
+    ///
+    /// Not jailed validators with the highest non-zero power are bonded, up to `max_validators`.
+    /// Validators leaving the active set start unbonding, validators joining it become bonded.
+    /// Rewards are updated before changing the status, so no rewards are paid outside the active set.
+    fn update_validator_set(
         &self,
         api: &dyn Api,
         staking_storage: &mut dyn Storage,
         block: &BlockInfo,
-        validator: &Addr,
-        percentage: Decimal,
     ) -> AnyResult<()> {
-        // calculate rewards before slashing
-        Self::update_rewards(api, staking_storage, block, validator)?;
-
-        // update stake of validator and stakers
-        let mut validator_info = VALIDATOR_INFO
-            .may_load(staking_storage, validator)?
-            .unwrap();
-
-        let remaining_percentage = Decimal::one() - percentage;
-        validator_info.stake = validator_info.stake * remaining_percentage;
+        let staking_info = Self::get_staking_info(staking_storage)?;
+        let mut validators = vec![];
+        for validator in self.get_validators(staking_storage)? {
+            let validator_addr = Addr::unchecked(validator.address);
+            let validator_info = VALIDATOR_INFO.load(staking_storage, &validator_addr)?;
+            validators.push((validator_addr, validator_info));
+        }
 
-        // if the stake is completely gone, we clear all stakers and reinitialize the validator
-        if validator_info.stake.is_zero() {
-            // need to remove all stakes
-            for delegator in validator_info.stakers.iter() {
-                STAKES.remove(staking_storage, (delegator, validator));
+        // like in cosmos-sdk, validators without any power are never bonded;
+        // stable sort keeps the order of adding validators when their power is equal
+        let mut candidates: Vec<&(Addr, ValidatorInfo)> = validators
+            .iter()
+            .filter(|(_, info)| !info.jailed && !info.stake.is_zero())
+            .collect();
+        candidates.sort_by_key(|(_, info)| std::cmp::Reverse(info.stake));
+        let active_set: BTreeSet<Addr> = candidates
+            .into_iter()
+            .take(staking_info.max_validators as usize)
+            .map(|(addr, _)| addr.clone())
+            .collect();
+
+        for (validator_addr, validator_info) in validators {
+            let bonded = active_set.contains(&validator_addr);
+            if validator_info.is_bonded() == bonded {
+                continue;
             }
-            validator_info.stakers.clear();
-        } else {
-            // otherwise we update all stakers
-            for delegator in validator_info.stakers.iter() {
-                STAKES.update(
-                    staking_storage,
-                    (delegator, validator),
+            // settle rewards with the current status before changing it
+            Self::update_rewards(api, staking_storage, block, &validator_addr)?;
+            let mut validator_info = VALIDATOR_INFO.load(staking_storage, &validator_addr)?;
+            let hook = if bonded {
+                validator_info.status = ValidatorStatus::Bonded;
+                validator_info.unbonding_completion = None;
+                StakingHookMsg::AfterValidatorBonded {
+                    validator: validator_addr.to_string(),
+                }
+            } else {
+                validator_info.status = ValidatorStatus::Unbonding;
+                validator_info.unbonding_completion =
+                    Some(block.time.plus_seconds(staking_info.unbonding_time));
+                StakingHookMsg::AfterValidatorBeginUnbonding {
+                    validator: validator_addr.to_string(),
+                }
+            };
+            VALIDATOR_INFO.save(staking_storage, &validator_addr, &validator_info)?;
+            Self::queue_hook(staking_storage, hook)?;
+        }
+        Ok(())
+    }
+
+    /// Completes unbonding of validators whose unbonding time has passed.
+    fn complete_validator_unbonding(
+        &self,
+        staking_storage: &mut dyn Storage,
+        block: &BlockInfo,
+    ) -> AnyResult<()> {
+        for validator in self.get_validators(staking_storage)? {
+            let validator_addr = Addr::unchecked(validator.address);
+            let mut validator_info = VALIDATOR_INFO.load(staking_storage, &validator_addr)?;
+            if matches!(validator_info.unbonding_completion, Some(completion) if completion <= block.time)
+            {
+                validator_info.status = ValidatorStatus::Unbonded;
+                validator_info.unbonding_completion = None;
+                VALIDATOR_INFO.save(staking_storage, &validator_addr, &validator_info)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the time when tokens undelegated now from the given validator are paid out.
+    ///
+    /// See <https://github.com/cosmos/cosmos-sdk/blob/v0.47.5/x/staking/keeper/delegation.go#L865-L887>
+    fn unbonding_completion(
+        staking_storage: &dyn Storage,
+        block: &BlockInfo,
+        validator: &Addr,
+    ) -> AnyResult<Timestamp> {
+        let staking_info = Self::get_staking_info(staking_storage)?;
+        let validator_info = VALIDATOR_INFO.load(staking_storage, validator)?;
+        Ok(match validator_info.status {
+            ValidatorStatus::Bonded => block.time.plus_seconds(staking_info.unbonding_time),
+            ValidatorStatus::Unbonding => validator_info
+                .unbonding_completion
+                .unwrap_or_else(|| block.time.plus_seconds(staking_info.unbonding_time)),
+            ValidatorStatus::Unbonded => block.time,
+        })
+    }
+
+    /// Jails or unjails the validator.
+    fn set_jailed(
+        &self,
+        api: &dyn Api,
+        staking_storage: &mut dyn Storage,
+        block: &BlockInfo,
+        validator: &Addr,
+        jailed: bool,
+        tombstoned: bool,
+    ) -> AnyResult<()> {
+        let mut validator_info = VALIDATOR_INFO
+            .may_load(staking_storage, validator)?
+            .ok_or_else(|| Error::unknown_validator(validator))?;
+        if jailed {
+            // see https://github.com/cosmos/cosmos-sdk/blob/v0.47.5/x/staking/keeper/val_state_change.go#L258-L260
+            if validator_info.jailed && !tombstoned {
+                bail!(Error::validator_already_jailed());
+            }
+        } else {
+            // see https://github.com/cosmos/cosmos-sdk/blob/v0.47.5/x/slashing/keeper/unjail.go#L49-L64
+            if validator_info.tombstoned {
+                bail!(Error::validator_still_jailed());
+            }
+            if !validator_info.jailed {
+                bail!(Error::validator_not_jailed());
+            }
+        }
+        validator_info.jailed = jailed;
+        validator_info.tombstoned |= tombstoned;
+        VALIDATOR_INFO.save(staking_storage, validator, &validator_info)?;
+        self.update_validator_set(api, staking_storage, block)
+    }
+
+    /// Slashes the validator and returns the amount of slashed tokens, which should be burned.
+    ///
+    /// Without an infraction, the current stake and all pending unbondings from the validator
+    /// are slashed by the percentage.
+    ///
+    /// With an infraction (its height and the power of the validator at that height),
+    /// the validator is slashed like in cosmos-sdk: the slashed amount is the percentage of the power,
+    /// unbondings and redelegations started at or after the infraction height, which are not
+    /// completed yet, are slashed by the percentage of their initial amount first, and the rest
+    /// of the slashed amount is taken from the current stake of the validator,
+    /// see <https://github.com/cosmos/cosmos-sdk/blob/v0.47.5/x/staking/keeper/slash.go>.
+    fn slash(
+        &self,
+        api: &dyn Api,
+        staking_storage: &mut dyn Storage,
+        block: &BlockInfo,
+        validator: &Addr,
+        percentage: Decimal,
+        infraction: Option<(u64, Uint128)>,
+    ) -> AnyResult<Uint128> {
+        if let Some((infraction_height, _)) = infraction {
+            // see https://github.com/cosmos/cosmos-sdk/blob/v0.47.5/x/staking/keeper/slash.go#L68-L75
+            ensure!(
+                infraction_height <= block.height,
+                anyhow!(
+                    "impossible attempt to slash future infraction at height {} but we are at height {}",
+                    infraction_height,
+                    block.height
+                )
+            );
+        }
+        // when slashing at the current height, only the stake of the validator is affected,
+        // see https://github.com/cosmos/cosmos-sdk/blob/v0.47.5/x/staking/keeper/slash.go#L76-L80
+        let slash_entries = |creation_height: u64, completion: Timestamp| match infraction {
+            None => true,
+            Some((infraction_height, _)) => {
+                infraction_height < block.height
+                    && creation_height >= infraction_height
+                    && completion > block.time
+            }
+        };
+        // amount to be slashed from the stake of the validator, reduced by slashed entries
+        let mut remaining_slash_amount = infraction.map(|(_, power)| power * percentage);
+        let mut slashed = Uint128::zero();
+
+        // calculate rewards before slashing
+        Self::update_rewards(api, staking_storage, block, validator)?;
+
+        // slash pending unbondings from this validator
+        let unbondings = unbonding_queue()
+            .idx
+            .validator
+            .prefix(validator.clone())
+            .range(staking_storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (key, mut ub) in unbondings
+            .into_iter()
+            .filter(|(_, ub)| slash_entries(ub.creation_height, ub.payout_at))
+        {
+            let burned = match remaining_slash_amount.as_mut() {
+                None => ub.amount - ub.amount * (Decimal::one() - percentage),
+                Some(remaining_slash_amount) => {
+                    let slash_amount = ub.initial_balance * percentage;
+                    *remaining_slash_amount = remaining_slash_amount.saturating_sub(slash_amount);
+                    slash_amount.min(ub.amount)
+                }
+            };
+            slashed += burned;
+            ub.amount -= burned;
+            unbonding_queue().save(staking_storage, key, &ub)?;
+        }
+
+        // redelegated tokens are slashed by unbonding them from the destination validator
+        if let Some(remaining_slash_amount) = remaining_slash_amount.as_mut() {
+            let redelegations = redelegation_queue()
+                .idx
+                .validators
+                .sub_prefix(validator.clone())
+                .range(staking_storage, None, None, Order::Ascending)
+                .map(|item| item.map(|(_, redelegation)| redelegation))
+                .collect::<StdResult<Vec<_>>>()?;
+            for redelegation in redelegations
+                .iter()
+                .filter(|r| slash_entries(r.creation_height, r.completion_time))
+            {
+                let slash_amount = redelegation.amount * percentage;
+                *remaining_slash_amount = remaining_slash_amount.saturating_sub(slash_amount);
+                let delegated = self
+                    .get_stake(
+                        staking_storage,
+                        &redelegation.delegator,
+                        &redelegation.dst_validator,
+                    )?
+                    .map(|stake| stake.amount)
+                    .unwrap_or_default();
+                let unbond_amount = slash_amount.min(delegated);
+                if !unbond_amount.is_zero() {
+                    self.update_stake(
+                        api,
+                        staking_storage,
+                        block,
+                        &redelegation.delegator,
+                        &redelegation.dst_validator,
+                        unbond_amount,
+                        true,
+                    )?;
+                    slashed += unbond_amount;
+                }
+            }
+        }
+
+        // update stake of validator and stakers
+        let mut validator_info = VALIDATOR_INFO
+            .may_load(staking_storage, validator)?
+            .unwrap();
+
+        let burned = match remaining_slash_amount {
+            None => validator_info.stake - validator_info.stake * (Decimal::one() - percentage),
+            Some(remaining_slash_amount) => remaining_slash_amount.min(validator_info.stake),
+        };
+        let remaining_percentage = if validator_info.stake.is_zero() {
+            Decimal::one()
+        } else {
+            Decimal::one() - Decimal::from_ratio(burned, validator_info.stake)
+        };
+        slashed += burned;
+        validator_info.stake -= burned;
+
+        // if the stake is completely gone, we clear all stakers and reinitialize the validator
+        if validator_info.stake.is_zero() {
+            // need to remove all stakes
+            for delegator in validator_info.stakers.iter() {
+                STAKES.remove(staking_storage, (delegator, validator));
+            }
+            validator_info.stakers.clear();
+        } else {
+            // otherwise we update all stakers
+            for delegator in validator_info.stakers.iter() {
+                STAKES.update(
+                    staking_storage,
+                    (delegator, validator),
                     |stake| -> AnyResult<_> {
                         let mut stake = stake.expect("all stakers in validator_info should exist");
                         stake.stake *= remaining_percentage;
@@ -506,20 +1477,50 @@ impl StakeKeeper {
                 )?;
             }
         }
-        // go through the queue to slash all pending unbondings
-        let mut unbonding_queue = UNBONDING_QUEUE
-            .may_load(staking_storage)?
-            .unwrap_or_default();
-        #[allow(clippy::op_ref)]
-        unbonding_queue
-            .iter_mut()
-            .filter(|ub| &ub.validator == validator)
-            .for_each(|ub| {
-                ub.amount = ub.amount * remaining_percentage;
-            });
-        UNBONDING_QUEUE.save(staking_storage, &unbonding_queue)?;
 
         VALIDATOR_INFO.save(staking_storage, validator, &validator_info)?;
+        Self::queue_hook(
+            staking_storage,
+            StakingHookMsg::AfterValidatorSlashed {
+                validator: validator.to_string(),
+                fraction: percentage,
+            },
+        )?;
+        self.update_validator_set(api, staking_storage, block)?;
+        Ok(slashed)
+    }
+
+    /// Burns slashed tokens held by the staking module.
+    fn burn_slashed<ExecC, QueryC: CustomQuery>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        amount: Uint128,
+    ) -> AnyResult<()> {
+        if amount.is_zero() {
+            return Ok(());
+        }
+        let staking_info = Self::get_staking_info(&prefixed_read(storage, NAMESPACE_STAKING))?;
+        let amount = coin(amount.u128(), staking_info.bonded_denom);
+        router
+            .execute(
+                api,
+                storage,
+                block,
+                self.module_addr.clone(),
+                BankMsg::Burn {
+                    amount: vec![amount.clone()],
+                }
+                .into(),
+            )
+            .with_context(|| {
+                format!(
+                    "staking module {} cannot burn slashed {}",
+                    self.module_addr, amount
+                )
+            })?;
         Ok(())
     }
 
@@ -529,11 +1530,7 @@ impl StakeKeeper {
         ensure_eq!(
             amount.denom,
             staking_info.bonded_denom,
-            anyhow!(
-                "cannot delegate coins of denominator {}, only of {}",
-                amount.denom,
-                staking_info.bonded_denom
-            )
+            Error::wrong_denom(&amount.denom, staking_info.bonded_denom)
         );
         Ok(())
     }
@@ -551,64 +1548,86 @@ impl StakeKeeper {
         router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
         block: &BlockInfo,
     ) -> AnyResult<AppResponse> {
-        let staking_storage = prefixed_read(storage, NAMESPACE_STAKING);
-        let mut unbonding_queue = UNBONDING_QUEUE
-            .may_load(&staking_storage)?
-            .unwrap_or_default();
-        loop {
+        let mut staking_storage = prefixed(storage, NAMESPACE_STAKING);
+        self.complete_validator_unbonding(&mut staking_storage, block)?;
+        self.update_validator_set(api, &mut staking_storage, block)?;
+        // remove completed redelegations
+        let completed_redelegations = redelegation_queue()
+            .range(
+                &staking_storage,
+                None,
+                Some(Bound::inclusive((block.time.nanos(), u64::MAX))),
+                Order::Ascending,
+            )
+            .collect::<StdResult<Vec<_>>>()?;
+        // see https://github.com/cosmos/cosmos-sdk/blob/v0.47.5/x/staking/keeper/val_state_change.go#L68-L89
+        let mut events = vec![];
+        for (key, redelegation) in completed_redelegations {
+            redelegation_queue().remove(&mut staking_storage, key)?;
+            events.push(
+                Event::new("complete_redelegation")
+                    .add_attribute("amount", redelegation.amount.to_string())
+                    .add_attribute("delegator", redelegation.delegator)
+                    .add_attribute("source_validator", redelegation.src_validator)
+                    .add_attribute("destination_validator", redelegation.dst_validator),
+            );
+        }
+        let completed = unbonding_queue()
+            .range(
+                &staking_storage,
+                None,
+                Some(Bound::inclusive((block.time.nanos(), u64::MAX))),
+                Order::Ascending,
+            )
+            .collect::<StdResult<Vec<_>>>()?;
+        for (key, unbonding) in completed {
             let mut staking_storage = prefixed(storage, NAMESPACE_STAKING);
-            match unbonding_queue.front() {
-                // assuming the queue is sorted by payout_at
-                Some(Unbonding { payout_at, .. }) if payout_at <= &block.time => {
-                    // remove from queue
-                    let Unbonding {
-                        delegator,
-                        validator,
-                        amount,
-                        ..
-                    } = unbonding_queue.pop_front().unwrap();
-
-                    // remove staking entry if it is empty
-                    let delegation = self
-                        .get_stake(&staking_storage, &delegator, &validator)?
-                        .map(|mut stake| {
-                            // add unbonding amounts
-                            stake.amount += unbonding_queue
-                                .iter()
-                                .filter(|u| u.delegator == delegator && u.validator == validator)
-                                .map(|u| u.amount)
-                                .sum::<Uint128>();
-                            stake
-                        });
-                    match delegation {
-                        Some(delegation) if delegation.amount.is_zero() => {
-                            STAKES.remove(&mut staking_storage, (&delegator, &validator));
-                        }
-                        None => STAKES.remove(&mut staking_storage, (&delegator, &validator)),
-                        _ => {}
-                    }
+            // remove from queue
+            unbonding_queue().remove(&mut staking_storage, key)?;
+            let Unbonding {
+                delegator,
+                validator,
+                amount,
+                ..
+            } = unbonding;
+
+            // remove staking entry if it is empty
+            let pending = Self::get_unbondings(&staking_storage, &delegator, &validator)?
+                .iter()
+                .map(|u| u.amount)
+                .sum::<Uint128>();
+            let delegation = self
+                .get_stake(&staking_storage, &delegator, &validator)?
+                .map(|mut stake| {
+                    // add unbonding amounts
+                    stake.amount += pending;
+                    stake
+                });
+            match delegation {
+                Some(delegation) if delegation.amount.is_zero() => {
+                    STAKES.remove(&mut staking_storage, (&delegator, &validator));
+                }
+                None => STAKES.remove(&mut staking_storage, (&delegator, &validator)),
+                _ => {}
+            }
 
-                    let staking_info = Self::get_staking_info(&staking_storage)?;
-                    if !amount.is_zero() {
-                        router.execute(
-                            api,
-                            storage,
-                            block,
-                            self.module_addr.clone(),
-                            BankMsg::Send {
-                                to_address: delegator.into_string(),
-                                amount: vec![coin(amount.u128(), &staking_info.bonded_denom)],
-                            }
-                            .into(),
-                        )?;
+            let staking_info = Self::get_staking_info(&staking_storage)?;
+            if !amount.is_zero() {
+                router.execute(
+                    api,
+                    storage,
+                    block,
+                    self.module_addr.clone(),
+                    BankMsg::Send {
+                        to_address: delegator.into_string(),
+                        amount: vec![coin(amount.u128(), &staking_info.bonded_denom)],
                     }
-                }
-                _ => break,
+                    .into(),
+                )?;
             }
         }
-        let mut staking_storage = prefixed(storage, NAMESPACE_STAKING);
-        UNBONDING_QUEUE.save(&mut staking_storage, &unbonding_queue)?;
-        Ok(AppResponse::default())
+        events.extend(self.dispatch_hooks(api, storage, router, block)?);
+        Ok(AppResponse { events, data: None })
     }
 }
 
@@ -624,12 +1643,8 @@ impl Staking for StakeKeeper {
     }
 }
 
-impl Module for StakeKeeper {
-    type ExecT = StakingMsg;
-    type QueryT = StakingQuery;
-    type SudoT = StakingSudo;
-
-    fn execute<ExecC, QueryC: CustomQuery>(
+impl StakeKeeper {
+    fn execute_msg<ExecC, QueryC: CustomQuery>(
         &self,
         api: &dyn Api,
         storage: &mut dyn Storage,
@@ -645,7 +1660,7 @@ impl Module for StakeKeeper {
 
                 // see https://github.com/cosmos/cosmos-sdk/blob/3c5387048f75d7e78b40c5b8d2421fdb8f5d973a/x/staking/types/msg.go#L202-L207
                 if amount.amount.is_zero() {
-                    bail!("invalid delegation amount");
+                    bail!(Error::invalid_delegation_amount());
                 }
 
                 // see https://github.com/cosmos/cosmos-sdk/blob/v0.46.1/x/staking/keeper/msg_server.go#L251-L256
@@ -681,14 +1696,27 @@ impl Module for StakeKeeper {
 
                 // see https://github.com/cosmos/cosmos-sdk/blob/3c5387048f75d7e78b40c5b8d2421fdb8f5d973a/x/staking/types/msg.go#L292-L297
                 if amount.amount.is_zero() {
-                    bail!("invalid shares amount");
+                    bail!(Error::invalid_shares_amount());
                 }
 
+                // see https://github.com/cosmos/cosmos-sdk/blob/v0.47.5/x/staking/keeper/delegation.go#L821-L823
+                let staking_info = Self::get_staking_info(&staking_storage)?;
+                let entries = Self::get_unbondings(&staking_storage, &sender, &validator)?.len();
+                if entries >= staking_info.max_entries as usize {
+                    bail!(Error::max_unbonding_entries());
+                }
+
+                // the payout time depends on the status of the validator before unbonding
+                let payout_at = if VALIDATOR_INFO.has(&staking_storage, &validator) {
+                    Self::unbonding_completion(&staking_storage, block, &validator)?
+                } else {
+                    block.time
+                };
                 // see https://github.com/cosmos/cosmos-sdk/blob/v0.46.1/x/staking/keeper/msg_server.go#L378-L383
                 let events = vec![Event::new("unbond")
                     .add_attribute("validator", &validator)
                     .add_attribute("amount", format!("{}{}", amount.amount, amount.denom))
-                    .add_attribute("completion_time", "2022-09-27T14:00:00+00:00")]; // TODO: actual date?
+                    .add_attribute("completion_time", payout_at.to_string())];
                 self.remove_stake(
                     api,
                     &mut staking_storage,
@@ -697,18 +1725,38 @@ impl Module for StakeKeeper {
                     &validator,
                     amount.clone(),
                 )?;
+                if payout_at <= block.time {
+                    // unbonding from a validator that is not bonded completes immediately
+                    router.execute(
+                        api,
+                        storage,
+                        block,
+                        self.module_addr.clone(),
+                        BankMsg::Send {
+                            to_address: sender.into_string(),
+                            amount: vec![amount],
+                        }
+                        .into(),
+                    )?;
+                    return Ok(AppResponse { events, data: None });
+                }
                 // add tokens to unbonding queue
-                let staking_info = Self::get_staking_info(&staking_storage)?;
-                let mut unbonding_queue = UNBONDING_QUEUE
+                let sequence = UNBONDING_SEQUENCE
                     .may_load(&staking_storage)?
                     .unwrap_or_default();
-                unbonding_queue.push_back(Unbonding {
-                    delegator: sender.clone(),
-                    validator,
-                    amount: amount.amount,
-                    payout_at: block.time.plus_seconds(staking_info.unbonding_time),
-                });
-                UNBONDING_QUEUE.save(&mut staking_storage, &unbonding_queue)?;
+                UNBONDING_SEQUENCE.save(&mut staking_storage, &(sequence + 1))?;
+                unbonding_queue().save(
+                    &mut staking_storage,
+                    (payout_at.nanos(), sequence),
+                    &Unbonding {
+                        delegator: sender.clone(),
+                        validator,
+                        amount: amount.amount,
+                        initial_balance: amount.amount,
+                        payout_at,
+                        creation_height: block.height,
+                    },
+                )?;
                 Ok(AppResponse { events, data: None })
             }
             StakingMsg::Redelegate {
@@ -718,12 +1766,49 @@ impl Module for StakeKeeper {
             } => {
                 let src_validator = api.addr_validate(&src_validator)?;
                 let dst_validator = api.addr_validate(&dst_validator)?;
-                // see https://github.com/cosmos/cosmos-sdk/blob/v0.46.1/x/staking/keeper/msg_server.go#L316-L322
+
+                // see https://github.com/cosmos/cosmos-sdk/blob/v0.47.5/x/staking/keeper/delegation.go#L906-L943
+                if src_validator == dst_validator {
+                    bail!(Error::self_redelegation());
+                }
+                if self
+                    .get_validator(&staking_storage, &dst_validator)?
+                    .is_none()
+                {
+                    bail!(Error::unknown_validator(dst_validator));
+                }
+                if Self::get_redelegations(&staking_storage, &sender)?
+                    .iter()
+                    .any(|r| r.dst_validator == src_validator)
+                {
+                    bail!(Error::transitive_redelegation());
+                }
+                let staking_info = Self::get_staking_info(&staking_storage)?;
+                let entries = redelegation_queue()
+                    .idx
+                    .validators
+                    .prefix((src_validator.clone(), dst_validator.clone()))
+                    .range(&staking_storage, None, None, Order::Ascending)
+                    .collect::<StdResult<Vec<_>>>()?
+                    .iter()
+                    .filter(|(_, r)| r.delegator == sender)
+                    .count();
+                if entries >= staking_info.max_entries as usize {
+                    bail!(Error::max_redelegation_entries());
+                }
+
+                // redelegation completes when the source validator could have unbonded the tokens
+                let completion_time = if VALIDATOR_INFO.has(&staking_storage, &src_validator) {
+                    Self::unbonding_completion(&staking_storage, block, &src_validator)?
+                } else {
+                    block.time
+                };
+                // see https://github.com/cosmos/cosmos-sdk/blob/v0.47.5/x/staking/keeper/msg_server.go#L301-L309
                 let events = vec![Event::new("redelegate")
                     .add_attribute("source_validator", &src_validator)
                     .add_attribute("destination_validator", &dst_validator)
-                    .add_attribute("amount", format!("{}{}", amount.amount, amount.denom))];
-
+                    .add_attribute("amount", format!("{}{}", amount.amount, amount.denom))
+                    .add_attribute("completion_time", completion_time.to_string())];
                 self.remove_stake(
                     api,
                     &mut staking_storage,
@@ -738,16 +1823,34 @@ impl Module for StakeKeeper {
                     block,
                     &sender,
                     &dst_validator,
-                    amount,
+                    amount.clone(),
                 )?;
+                if completion_time > block.time {
+                    let sequence = REDELEGATION_SEQUENCE
+                        .may_load(&staking_storage)?
+                        .unwrap_or_default();
+                    REDELEGATION_SEQUENCE.save(&mut staking_storage, &(sequence + 1))?;
+                    redelegation_queue().save(
+                        &mut staking_storage,
+                        (completion_time.nanos(), sequence),
+                        &Redelegation {
+                            delegator: sender,
+                            src_validator,
+                            dst_validator,
+                            amount: amount.amount,
+                            creation_height: block.height,
+                            completion_time,
+                        },
+                    )?;
+                }
 
                 Ok(AppResponse { events, data: None })
             }
-            m => bail!("Unsupported staking message: {:?}", m),
+            other => bail!(Error::unsupported_staking_message(other)),
         }
     }
 
-    fn sudo<ExecC, QueryC: CustomQuery>(
+    fn sudo_msg<ExecC, QueryC: CustomQuery>(
         &self,
         api: &dyn Api,
         storage: &mut dyn Storage,
@@ -764,28 +1867,191 @@ impl Module for StakeKeeper {
                 let validator = api.addr_validate(&validator)?;
                 self.validate_percentage(percentage)?;
 
-                self.slash(api, &mut staking_storage, block, &validator, percentage)?;
+                let slashed = self.slash(
+                    api,
+                    &mut staking_storage,
+                    block,
+                    &validator,
+                    percentage,
+                    None,
+                )?;
+                let unburned = UNBURNED_SLASHES
+                    .may_load(&staking_storage)?
+                    .unwrap_or_default();
+                UNBURNED_SLASHES.save(&mut staking_storage, &unburned.checked_add(slashed)?)?;
+
+                Ok(AppResponse::default())
+            }
+            StakingSudo::SlashAtHeight {
+                validator,
+                percentage,
+                infraction_height,
+                power,
+            } => {
+                let mut staking_storage = prefixed(storage, NAMESPACE_STAKING);
+                let validator = api.addr_validate(&validator)?;
+                self.validate_percentage(percentage)?;
+
+                let slashed = self.slash(
+                    api,
+                    &mut staking_storage,
+                    block,
+                    &validator,
+                    percentage,
+                    Some((infraction_height, power)),
+                )?;
+                self.burn_slashed(api, storage, router, block, slashed)?;
 
                 Ok(AppResponse::default())
             }
+            StakingSudo::Jail { validator } => {
+                let mut staking_storage = prefixed(storage, NAMESPACE_STAKING);
+                let validator = api.addr_validate(&validator)?;
+                self.set_jailed(api, &mut staking_storage, block, &validator, true, false)?;
+                Ok(AppResponse::default())
+            }
+            StakingSudo::Unjail { validator } => {
+                let mut staking_storage = prefixed(storage, NAMESPACE_STAKING);
+                let validator = api.addr_validate(&validator)?;
+                self.set_jailed(api, &mut staking_storage, block, &validator, false, false)?;
+                Ok(AppResponse::default())
+            }
+            StakingSudo::Tombstone { validator } => {
+                let mut staking_storage = prefixed(storage, NAMESPACE_STAKING);
+                let validator = api.addr_validate(&validator)?;
+                self.set_jailed(api, &mut staking_storage, block, &validator, true, true)?;
+                Ok(AppResponse::default())
+            }
+            StakingSudo::AddHook { contract } => {
+                let mut staking_storage = prefixed(storage, NAMESPACE_STAKING);
+                let contract = api.addr_validate(&contract)?;
+                let mut hooks = STAKING_HOOKS
+                    .may_load(&staking_storage)?
+                    .unwrap_or_default();
+                if hooks.contains(&contract) {
+                    bail!(Error::staking_hook_already_registered(contract));
+                }
+                hooks.push(contract);
+                STAKING_HOOKS.save(&mut staking_storage, &hooks)?;
+                Ok(AppResponse::default())
+            }
+            StakingSudo::RemoveHook { contract } => {
+                let mut staking_storage = prefixed(storage, NAMESPACE_STAKING);
+                let contract = api.addr_validate(&contract)?;
+                let mut hooks = STAKING_HOOKS
+                    .may_load(&staking_storage)?
+                    .unwrap_or_default();
+                if !hooks.contains(&contract) {
+                    bail!(Error::staking_hook_not_registered(contract));
+                }
+                hooks.retain(|hook| *hook != contract);
+                STAKING_HOOKS.save(&mut staking_storage, &hooks)?;
+                Ok(AppResponse::default())
+            }
+            #[cfg(feature = "cosmwasm_1_1")]
+            StakingSudo::WithdrawValidatorCommission { validator } => {
+                let mut staking_storage = prefixed(storage, NAMESPACE_STAKING);
+                let validator = api.addr_validate(&validator)?;
+                let staking_info = Self::get_staking_info(&staking_storage)?;
+                let accumulated = VALIDATOR_COMMISSION
+                    .may_load(&staking_storage, &validator)?
+                    .unwrap_or_default();
+                let amount = accumulated.to_uint_floor();
+                VALIDATOR_COMMISSION.save(
+                    &mut staking_storage,
+                    &validator,
+                    &(accumulated - Decimal::from_ratio(amount, 1u128)),
+                )?;
+                if !amount.is_zero() {
+                    router.execute(
+                        api,
+                        storage,
+                        block,
+                        Addr::unchecked(DISTRIBUTION_MODULE),
+                        BankMsg::Send {
+                            to_address: validator.to_string(),
+                            amount: vec![coin(amount.u128(), &staking_info.bonded_denom)],
+                        }
+                        .into(),
+                    )?;
+                }
+                // see https://github.com/cosmos/cosmos-sdk/blob/v0.47.5/x/distribution/keeper/keeper.go#L158-L163
+                let events = vec![Event::new("withdraw_commission")
+                    .add_attribute("amount", format!("{}{}", amount, staking_info.bonded_denom))];
+                Ok(AppResponse { events, data: None })
+            }
             #[allow(deprecated)]
             StakingSudo::ProcessQueue {} => self.process_queue(api, storage, router, block),
         }
     }
+}
 
-    fn query(
+impl Module for StakeKeeper {
+    type ExecT = StakingMsg;
+    type QueryT = StakingQuery;
+    type SudoT = StakingSudo;
+
+    fn execute<ExecC, QueryC: CustomQuery>(
         &self,
         api: &dyn Api,
-        storage: &dyn Storage,
-        _querier: &dyn Querier,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
         block: &BlockInfo,
-        request: StakingQuery,
-    ) -> AnyResult<Binary> {
-        let staking_storage = prefixed_read(storage, NAMESPACE_STAKING);
-        match request {
-            StakingQuery::BondedDenom {} => Ok(to_json_binary(&BondedDenomResponse {
-                denom: Self::get_staking_info(&staking_storage)?.bonded_denom,
-            })?),
+        sender: Addr,
+        msg: StakingMsg,
+    ) -> AnyResult<AppResponse> {
+        let mut response = self.execute_msg(api, storage, router, block, sender, msg)?;
+        response
+            .events
+            .extend(self.dispatch_hooks(api, storage, router, block)?);
+        Ok(response)
+    }
+
+    fn sudo<ExecC, QueryC: CustomQuery>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        msg: StakingSudo,
+    ) -> AnyResult<AppResponse> {
+        let mut response = self.sudo_msg(api, storage, router, block, msg)?;
+        response
+            .events
+            .extend(self.dispatch_hooks(api, storage, router, block)?);
+        Ok(response)
+    }
+
+    /// Mints the block provisions when minting is enabled, see [mint](crate::mint).
+    #[cfg(feature = "cosmwasm_1_1")]
+    fn begin_block<ExecC, QueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: CustomMsg + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        let events = self.mint(api, storage, router, block)?;
+        Ok(AppResponse { events, data: None })
+    }
+
+    fn query(
+        &self,
+        api: &dyn Api,
+        storage: &dyn Storage,
+        _querier: &dyn Querier,
+        block: &BlockInfo,
+        request: StakingQuery,
+    ) -> AnyResult<Binary> {
+        let staking_storage = prefixed_read(storage, NAMESPACE_STAKING);
+        match request {
+            StakingQuery::BondedDenom {} => Ok(to_json_binary(&BondedDenomResponse {
+                denom: Self::get_staking_info(&staking_storage)?.bonded_denom,
+            })?),
             StakingQuery::AllDelegations { delegator } => {
                 let delegator = api.addr_validate(&delegator)?;
                 let validators = self.get_validators(&staking_storage)?;
@@ -821,7 +2087,7 @@ impl Module for StakeKeeper {
                 let validator_addr = Addr::unchecked(&validator);
                 let validator_obj = match self.get_validator(&staking_storage, &validator_addr)? {
                     Some(validator) => validator,
-                    None => bail!("non-existent validator {}", validator),
+                    None => bail!(Error::unknown_validator(validator)),
                 };
                 let delegator = api.addr_validate(&delegator)?;
 
@@ -844,6 +2110,17 @@ impl Module for StakeKeeper {
                     staking_info.bonded_denom,
                 );
 
+                // delegations receiving a pending redelegation can not be redelegated further
+                let has_receiving_redelegation =
+                    Self::get_redelegations(&staking_storage, &delegator)?
+                        .iter()
+                        .any(|r| r.dst_validator == validator_addr);
+                let can_redelegate = if has_receiving_redelegation {
+                    coin(0, &amount.denom)
+                } else {
+                    amount.clone()
+                };
+
                 let full_delegation_response = if amount.amount.is_zero() {
                     // no delegation
                     DelegationResponse { delegation: None }
@@ -853,7 +2130,7 @@ impl Module for StakeKeeper {
                             delegator,
                             validator,
                             amount: amount.clone(),
-                            can_redelegate: amount, // TODO: not implemented right now
+                            can_redelegate,
                             accumulated_rewards: if reward.amount.is_zero() {
                                 vec![]
                             } else {
@@ -867,12 +2144,12 @@ impl Module for StakeKeeper {
                 Ok(res)
             }
             StakingQuery::AllValidators {} => Ok(to_json_binary(&AllValidatorsResponse {
-                validators: self.get_validators(&staking_storage)?,
+                validators: self.get_bonded_validators(&staking_storage)?,
             })?),
             StakingQuery::Validator { address } => Ok(to_json_binary(&ValidatorResponse {
                 validator: self.get_validator(&staking_storage, &Addr::unchecked(address))?,
             })?),
-            q => bail!("Unsupported staking sudo message: {:?}", q),
+            other => bail!(Error::unsupported_staking_query(other)),
         }
     }
 }
@@ -970,20 +2247,39 @@ impl Module for DistributionKeeper {
                 let distribution_storage = prefixed_read(storage, NAMESPACE_DISTRIBUTION);
                 let staking_info = StakeKeeper::get_staking_info(&staking_storage)?;
                 let receiver = Self::get_withdraw_address(&distribution_storage, &sender)?;
-                // directly mint rewards to delegator
-                router.sudo(
-                    api,
-                    storage,
-                    block,
-                    BankSudo::Mint {
-                        to_address: receiver.into_string(),
-                        amount: vec![Coin {
-                            amount: rewards,
-                            denom: staking_info.bonded_denom.clone(),
-                        }],
+                let minting = StakeKeeper::is_minting(&staking_storage)?;
+                let amount = vec![Coin {
+                    amount: rewards,
+                    denom: staking_info.bonded_denom.clone(),
+                }];
+                if minting {
+                    // rewards were minted already, pay them out from the distribution module
+                    if !rewards.is_zero() {
+                        router.execute(
+                            api,
+                            storage,
+                            block,
+                            Addr::unchecked(DISTRIBUTION_MODULE),
+                            BankMsg::Send {
+                                to_address: receiver.into_string(),
+                                amount,
+                            }
+                            .into(),
+                        )?;
                     }
-                    .into(),
-                )?;
+                } else {
+                    // directly mint rewards to delegator
+                    router.sudo(
+                        api,
+                        storage,
+                        block,
+                        BankSudo::Mint {
+                            to_address: receiver.into_string(),
+                            amount,
+                        }
+                        .into(),
+                    )?;
+                }
 
                 let events = vec![Event::new("withdraw_delegator_reward")
                     .add_attribute("validator", &validator)
@@ -1006,7 +2302,7 @@ impl Module for DistributionKeeper {
                         .add_attribute("withdraw_address", address)],
                 })
             }
-            m => bail!("Unsupported distribution message: {:?}", m),
+            other => bail!(Error::unsupported_distribution_message(other)),
         }
     }
 
@@ -1069,6 +2365,9 @@ mod test {
             ibc: IbcFailingModule::new(),
             gov: GovFailingModule::new(),
             stargate: StargateFailingModule::new(),
+            tracer: None,
+            registry: Default::default(),
+            transaction_index: 0,
         }
     }
 
@@ -1091,6 +2390,8 @@ mod test {
                     bonded_denom: "TOKEN".to_string(),
                     unbonding_time: 60,
                     apr,
+                    max_validators: 100,
+                    max_entries: 7,
                 },
             )
             .unwrap();
@@ -1506,6 +2807,12 @@ mod test {
             )
         }
 
+        fn sudo_stake(env: &mut TestEnv, msg: StakingSudo) -> AnyResult<AppResponse> {
+            env.router
+                .staking
+                .sudo(&env.api, &mut env.store, &env.router, &env.block, msg)
+        }
+
         fn query_stake<T: DeserializeOwned>(env: &TestEnv, msg: StakingQuery) -> AnyResult<T> {
             Ok(from_json(env.router.staking.query(
                 &env.api,
@@ -1810,7 +3117,7 @@ mod test {
             )
             .unwrap_err();
 
-            assert_eq!(e.to_string(), "invalid shares amount");
+            assert_eq!(Error::invalid_shares_amount(), e.downcast().unwrap());
 
             // add second validator
             let validator2 = Addr::unchecked("validator2");
@@ -1841,7 +3148,7 @@ mod test {
                 },
             )
             .unwrap_err();
-            assert_eq!(e.to_string(), "invalid shares amount");
+            assert_eq!(Error::invalid_shares_amount(), e.downcast().unwrap());
 
             // undelegate from non-existing delegation
             let e = execute_stake(
@@ -1853,10 +3160,7 @@ mod test {
                 },
             )
             .unwrap_err();
-            assert_eq!(
-                e.to_string(),
-                "no delegation for (address, validator) tuple"
-            );
+            assert_eq!(Error::no_delegation(), e.downcast().unwrap());
         }
 
         #[test]
@@ -1979,7 +3283,7 @@ mod test {
                 },
             )
             .unwrap_err();
-            assert_eq!(err.to_string(), "invalid delegation amount");
+            assert_eq!(Error::invalid_delegation_amount(), err.downcast().unwrap());
 
             // undelegate 0
             let err = execute_stake(
@@ -1991,7 +3295,7 @@ mod test {
                 },
             )
             .unwrap_err();
-            assert_eq!(err.to_string(), "invalid shares amount");
+            assert_eq!(Error::invalid_shares_amount(), err.downcast().unwrap());
         }
 
         #[test]
@@ -2041,12 +3345,12 @@ mod test {
                 },
             )
             .unwrap();
+            let valoper1 = valoper1.validator.unwrap();
+            assert_eq!(valoper1.address, validator1.to_string());
+            // validators without any power are not bonded
             let validators: AllValidatorsResponse =
                 query_stake(&test_env, StakingQuery::AllValidators {}).unwrap();
-            assert_eq!(
-                validators.validators,
-                [valoper1.validator.unwrap(), valoper2]
-            );
+            assert_eq!(validators.validators, []);
             // query non-existent validator
             let response = query_stake::<ValidatorResponse>(
                 &test_env,
@@ -2110,6 +3414,11 @@ mod test {
             )
             .unwrap();
 
+            // query bonded validators
+            let validators: AllValidatorsResponse =
+                query_stake(&test_env, StakingQuery::AllValidators {}).unwrap();
+            assert_eq!(validators.validators, [valoper1, valoper2]);
+
             // query all delegations
             let response1: AllDelegationsResponse = query_stake(
                 &test_env,
@@ -2541,5 +3850,1050 @@ mod test {
                 vec![coin(10, "TOKEN")] // 10% of 100
             );
         }
+
+        #[test]
+        fn jailed_validator_earns_no_rewards() {
+            let (mut test_env, validator) =
+                TestEnv::wrap(setup_test_env(Decimal::percent(10), Decimal::percent(10)));
+            let delegator = Addr::unchecked("delegator");
+
+            test_env
+                .router
+                .bank
+                .init_balance(&mut test_env.store, &delegator, vec![coin(100, "TOKEN")])
+                .unwrap();
+            execute_stake(
+                &mut test_env,
+                delegator.clone(),
+                StakingMsg::Delegate {
+                    validator: validator.to_string(),
+                    amount: coin(100, "TOKEN"),
+                },
+            )
+            .unwrap();
+
+            // jail the validator, it leaves the active set
+            sudo_stake(
+                &mut test_env,
+                StakingSudo::Jail {
+                    validator: validator.to_string(),
+                },
+            )
+            .unwrap();
+            let validators: AllValidatorsResponse =
+                query_stake(&test_env, StakingQuery::AllValidators {}).unwrap();
+            assert!(validators.validators.is_empty());
+            let status = test_env
+                .router
+                .staking
+                .validator_status(&test_env.store, &validator)
+                .unwrap();
+            assert_eq!(status, Some(ValidatorStatus::Unbonding));
+
+            // jailing again is not allowed
+            let err = sudo_stake(
+                &mut test_env,
+                StakingSudo::Jail {
+                    validator: validator.to_string(),
+                },
+            )
+            .unwrap_err();
+            assert_eq!(
+                &Error::validator_already_jailed(),
+                err.downcast_ref::<Error>().unwrap()
+            );
+
+            // a year passes without any rewards
+            test_env.block.time = test_env.block.time.plus_seconds(60 * 60 * 24 * 365);
+            let response: DelegationResponse = query_stake(
+                &test_env,
+                StakingQuery::Delegation {
+                    delegator: delegator.to_string(),
+                    validator: validator.to_string(),
+                },
+            )
+            .unwrap();
+            assert_eq!(response.delegation.unwrap().accumulated_rewards, vec![]);
+
+            // unjail the validator, it earns rewards again
+            sudo_stake(
+                &mut test_env,
+                StakingSudo::Unjail {
+                    validator: validator.to_string(),
+                },
+            )
+            .unwrap();
+            let validators: AllValidatorsResponse =
+                query_stake(&test_env, StakingQuery::AllValidators {}).unwrap();
+            assert_eq!(validators.validators.len(), 1);
+
+            test_env.block.time = test_env.block.time.plus_seconds(60 * 60 * 24 * 365);
+            let response: DelegationResponse = query_stake(
+                &test_env,
+                StakingQuery::Delegation {
+                    delegator: delegator.to_string(),
+                    validator: validator.to_string(),
+                },
+            )
+            .unwrap();
+            // one year, 10%apr, 10% commission, 100 tokens staked
+            assert_eq!(
+                response.delegation.unwrap().accumulated_rewards,
+                vec![coin(9, "TOKEN")]
+            );
+
+            // unjailing a validator that is not jailed fails
+            let err = sudo_stake(
+                &mut test_env,
+                StakingSudo::Unjail {
+                    validator: validator.to_string(),
+                },
+            )
+            .unwrap_err();
+            assert_eq!(
+                &Error::validator_not_jailed(),
+                err.downcast_ref::<Error>().unwrap()
+            );
+        }
+
+        #[test]
+        fn tombstoned_validator_cannot_be_unjailed() {
+            let (mut test_env, validator) =
+                TestEnv::wrap(setup_test_env(Decimal::percent(10), Decimal::percent(10)));
+
+            sudo_stake(
+                &mut test_env,
+                StakingSudo::Tombstone {
+                    validator: validator.to_string(),
+                },
+            )
+            .unwrap();
+            assert!(test_env
+                .router
+                .staking
+                .is_jailed(&test_env.store, &validator)
+                .unwrap());
+
+            let err = sudo_stake(
+                &mut test_env,
+                StakingSudo::Unjail {
+                    validator: validator.to_string(),
+                },
+            )
+            .unwrap_err();
+            assert_eq!(
+                &Error::validator_still_jailed(),
+                err.downcast_ref::<Error>().unwrap()
+            );
+
+            // tombstoned validator is still returned by the validator query
+            let response: ValidatorResponse = query_stake(
+                &test_env,
+                StakingQuery::Validator {
+                    address: validator.to_string(),
+                },
+            )
+            .unwrap();
+            assert!(response.validator.is_some());
+        }
+
+        #[test]
+        fn max_validators_ranks_by_power() {
+            let (mut test_env, validator1) =
+                TestEnv::wrap(setup_test_env(Decimal::percent(10), Decimal::percent(10)));
+            let delegator = Addr::unchecked("delegator");
+
+            test_env
+                .router
+                .staking
+                .setup(
+                    &mut test_env.store,
+                    StakingInfo {
+                        max_validators: 1,
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            test_env
+                .router
+                .bank
+                .init_balance(&mut test_env.store, &delegator, vec![coin(300, "TOKEN")])
+                .unwrap();
+            execute_stake(
+                &mut test_env,
+                delegator.clone(),
+                StakingMsg::Delegate {
+                    validator: validator1.to_string(),
+                    amount: coin(100, "TOKEN"),
+                },
+            )
+            .unwrap();
+
+            // add second validator, without any power it is not bonded
+            let validator2 = Addr::unchecked("validator2");
+            test_env
+                .router
+                .staking
+                .add_validator(
+                    &test_env.api,
+                    &mut test_env.store,
+                    &test_env.block,
+                    Validator {
+                        address: validator2.to_string(),
+                        commission: Decimal::zero(),
+                        max_commission: Decimal::percent(20),
+                        max_change_rate: Decimal::percent(1),
+                    },
+                )
+                .unwrap();
+            let validators: AllValidatorsResponse =
+                query_stake(&test_env, StakingQuery::AllValidators {}).unwrap();
+            assert_eq!(validators.validators.len(), 1);
+            assert_eq!(validators.validators[0].address, validator1.to_string());
+            let status = test_env
+                .router
+                .staking
+                .validator_status(&test_env.store, &validator2)
+                .unwrap();
+            assert_eq!(status, Some(ValidatorStatus::Unbonded));
+
+            // delegate to the second validator, it gets more power
+            execute_stake(
+                &mut test_env,
+                delegator.clone(),
+                StakingMsg::Delegate {
+                    validator: validator2.to_string(),
+                    amount: coin(200, "TOKEN"),
+                },
+            )
+            .unwrap();
+            let validators: AllValidatorsResponse =
+                query_stake(&test_env, StakingQuery::AllValidators {}).unwrap();
+            assert_eq!(validators.validators.len(), 1);
+            assert_eq!(validators.validators[0].address, validator2.to_string());
+
+            // the first validator finishes unbonding after the unbonding time
+            test_env.block.time = test_env.block.time.plus_seconds(60);
+            test_env
+                .router
+                .staking
+                .process_queue(
+                    &test_env.api,
+                    &mut test_env.store,
+                    &test_env.router,
+                    &test_env.block,
+                )
+                .unwrap();
+            let status = test_env
+                .router
+                .staking
+                .validator_status(&test_env.store, &validator1)
+                .unwrap();
+            assert_eq!(status, Some(ValidatorStatus::Unbonded));
+
+            // all delegations are still returned
+            let response: AllDelegationsResponse = query_stake(
+                &test_env,
+                StakingQuery::AllDelegations {
+                    delegator: delegator.to_string(),
+                },
+            )
+            .unwrap();
+            assert_eq!(response.delegations.len(), 2);
+        }
+
+        #[test]
+        fn unbonding_from_unbonded_validator_completes_immediately() {
+            let (mut test_env, validator) =
+                TestEnv::wrap(setup_test_env(Decimal::percent(10), Decimal::percent(10)));
+            let delegator = Addr::unchecked("delegator");
+
+            test_env
+                .router
+                .bank
+                .init_balance(&mut test_env.store, &delegator, vec![coin(100, "TOKEN")])
+                .unwrap();
+            execute_stake(
+                &mut test_env,
+                delegator.clone(),
+                StakingMsg::Delegate {
+                    validator: validator.to_string(),
+                    amount: coin(100, "TOKEN"),
+                },
+            )
+            .unwrap();
+            sudo_stake(
+                &mut test_env,
+                StakingSudo::Jail {
+                    validator: validator.to_string(),
+                },
+            )
+            .unwrap();
+
+            // undelegating from unbonding validator completes with the validator's unbonding
+            test_env.block.time = test_env.block.time.plus_seconds(30);
+            execute_stake(
+                &mut test_env,
+                delegator.clone(),
+                StakingMsg::Undelegate {
+                    validator: validator.to_string(),
+                    amount: coin(50, "TOKEN"),
+                },
+            )
+            .unwrap();
+            test_env.block.time = test_env.block.time.plus_seconds(30);
+            test_env
+                .router
+                .staking
+                .process_queue(
+                    &test_env.api,
+                    &mut test_env.store,
+                    &test_env.router,
+                    &test_env.block,
+                )
+                .unwrap();
+            assert_balances(&test_env, vec![(delegator.clone(), 50)]);
+
+            // validator is unbonded now, undelegating pays out immediately
+            execute_stake(
+                &mut test_env,
+                delegator.clone(),
+                StakingMsg::Undelegate {
+                    validator: validator.to_string(),
+                    amount: coin(50, "TOKEN"),
+                },
+            )
+            .unwrap();
+            assert_balances(&test_env, vec![(delegator, 100)]);
+        }
+        #[test]
+        fn retroactive_slashing_covers_unbondings() {
+            let (mut test_env, validator) =
+                TestEnv::wrap(setup_test_env(Decimal::percent(10), Decimal::percent(10)));
+            let delegator = Addr::unchecked("delegator");
+
+            test_env
+                .router
+                .bank
+                .init_balance(&mut test_env.store, &delegator, vec![coin(200, "TOKEN")])
+                .unwrap();
+            execute_stake(
+                &mut test_env,
+                delegator.clone(),
+                StakingMsg::Delegate {
+                    validator: validator.to_string(),
+                    amount: coin(200, "TOKEN"),
+                },
+            )
+            .unwrap();
+
+            // unbond at two different heights
+            let infraction_height = test_env.block.height + 1;
+            test_env.block.height += 1;
+            execute_stake(
+                &mut test_env,
+                delegator.clone(),
+                StakingMsg::Undelegate {
+                    validator: validator.to_string(),
+                    amount: coin(100, "TOKEN"),
+                },
+            )
+            .unwrap();
+            test_env.block.height += 1;
+            execute_stake(
+                &mut test_env,
+                delegator.clone(),
+                StakingMsg::Undelegate {
+                    validator: validator.to_string(),
+                    amount: coin(50, "TOKEN"),
+                },
+            )
+            .unwrap();
+
+            // cannot slash infractions from the future
+            let future_height = test_env.block.height + 1;
+            sudo_stake(
+                &mut test_env,
+                StakingSudo::SlashAtHeight {
+                    validator: validator.to_string(),
+                    percentage: Decimal::percent(50),
+                    infraction_height: future_height,
+                    power: Uint128::new(50),
+                },
+            )
+            .unwrap_err();
+
+            // only the second unbonding was started after the infraction,
+            // it is slashed first and the rest is slashed from the stake of the validator
+            test_env.block.height += 1;
+            sudo_stake(
+                &mut test_env,
+                StakingSudo::SlashAtHeight {
+                    validator: validator.to_string(),
+                    percentage: Decimal::percent(50),
+                    infraction_height: infraction_height + 1,
+                    power: Uint128::new(100),
+                },
+            )
+            .unwrap();
+
+            let response: DelegationResponse = query_stake(
+                &test_env,
+                StakingQuery::Delegation {
+                    delegator: delegator.to_string(),
+                    validator: validator.to_string(),
+                },
+            )
+            .unwrap();
+            assert_eq!(response.delegation.unwrap().amount, coin(25, "TOKEN"));
+
+            // slashed tokens are burned
+            assert_balances(
+                &test_env,
+                vec![(test_env.router.staking.module_addr.clone(), 150)],
+            );
+
+            test_env.block.time = test_env.block.time.plus_seconds(60);
+            test_env
+                .router
+                .staking
+                .process_queue(
+                    &test_env.api,
+                    &mut test_env.store,
+                    &test_env.router,
+                    &test_env.block,
+                )
+                .unwrap();
+            assert_balances(&test_env, vec![(delegator, 125)]);
+        }
+
+        #[test]
+        fn retroactive_slashing_covers_redelegations() {
+            let (mut test_env, validator1) =
+                TestEnv::wrap(setup_test_env(Decimal::percent(10), Decimal::percent(10)));
+            let delegator = Addr::unchecked("delegator");
+            let validator2 = Addr::unchecked("validator2");
+
+            test_env
+                .router
+                .staking
+                .add_validator(
+                    &test_env.api,
+                    &mut test_env.store,
+                    &test_env.block,
+                    Validator {
+                        address: validator2.to_string(),
+                        commission: Decimal::zero(),
+                        max_commission: Decimal::percent(20),
+                        max_change_rate: Decimal::percent(1),
+                    },
+                )
+                .unwrap();
+            test_env
+                .router
+                .bank
+                .init_balance(&mut test_env.store, &delegator, vec![coin(100, "TOKEN")])
+                .unwrap();
+            execute_stake(
+                &mut test_env,
+                delegator.clone(),
+                StakingMsg::Delegate {
+                    validator: validator1.to_string(),
+                    amount: coin(100, "TOKEN"),
+                },
+            )
+            .unwrap();
+
+            // redelegate after the infraction
+            let infraction_height = test_env.block.height;
+            test_env.block.height += 1;
+            execute_stake(
+                &mut test_env,
+                delegator.clone(),
+                StakingMsg::Redelegate {
+                    src_validator: validator1.to_string(),
+                    dst_validator: validator2.to_string(),
+                    amount: coin(60, "TOKEN"),
+                },
+            )
+            .unwrap();
+
+            test_env.block.height += 1;
+            sudo_stake(
+                &mut test_env,
+                StakingSudo::SlashAtHeight {
+                    validator: validator1.to_string(),
+                    percentage: Decimal::percent(50),
+                    infraction_height,
+                    power: Uint128::new(100),
+                },
+            )
+            .unwrap();
+
+            // the redelegated stake is slashed first, the rest from the remaining stake
+            let response: DelegationResponse = query_stake(
+                &test_env,
+                StakingQuery::Delegation {
+                    delegator: delegator.to_string(),
+                    validator: validator1.to_string(),
+                },
+            )
+            .unwrap();
+            assert_eq!(response.delegation.unwrap().amount, coin(20, "TOKEN"));
+            let response: DelegationResponse = query_stake(
+                &test_env,
+                StakingQuery::Delegation {
+                    delegator: delegator.to_string(),
+                    validator: validator2.to_string(),
+                },
+            )
+            .unwrap();
+            assert_eq!(response.delegation.unwrap().amount, coin(30, "TOKEN"));
+            assert_balances(
+                &test_env,
+                vec![(test_env.router.staking.module_addr.clone(), 50)],
+            );
+
+            // completed redelegations are not slashed anymore
+            test_env.block.time = test_env.block.time.plus_seconds(60);
+            test_env
+                .router
+                .staking
+                .process_queue(
+                    &test_env.api,
+                    &mut test_env.store,
+                    &test_env.router,
+                    &test_env.block,
+                )
+                .unwrap();
+            sudo_stake(
+                &mut test_env,
+                StakingSudo::SlashAtHeight {
+                    validator: validator1.to_string(),
+                    percentage: Decimal::percent(50),
+                    infraction_height,
+                    power: Uint128::new(100),
+                },
+            )
+            .unwrap();
+            let response: DelegationResponse = query_stake(
+                &test_env,
+                StakingQuery::Delegation {
+                    delegator: delegator.to_string(),
+                    validator: validator2.to_string(),
+                },
+            )
+            .unwrap();
+            assert_eq!(response.delegation.unwrap().amount, coin(30, "TOKEN"));
+        }
+        #[test]
+        fn retroactive_slashing_is_limited_by_power_at_infraction_height() {
+            let (mut test_env, validator) =
+                TestEnv::wrap(setup_test_env(Decimal::percent(10), Decimal::percent(10)));
+            let delegator1 = Addr::unchecked("delegator1");
+            let delegator2 = Addr::unchecked("delegator2");
+
+            for delegator in [&delegator1, &delegator2] {
+                test_env
+                    .router
+                    .bank
+                    .init_balance(&mut test_env.store, delegator, vec![coin(100, "TOKEN")])
+                    .unwrap();
+            }
+            execute_stake(
+                &mut test_env,
+                delegator1.clone(),
+                StakingMsg::Delegate {
+                    validator: validator.to_string(),
+                    amount: coin(100, "TOKEN"),
+                },
+            )
+            .unwrap();
+
+            // the power of the validator doubles after the infraction
+            let infraction_height = test_env.block.height;
+            test_env.block.height += 1;
+            execute_stake(
+                &mut test_env,
+                delegator2.clone(),
+                StakingMsg::Delegate {
+                    validator: validator.to_string(),
+                    amount: coin(100, "TOKEN"),
+                },
+            )
+            .unwrap();
+
+            // only the percentage of the power at the infraction height is slashed
+            test_env.block.height += 1;
+            sudo_stake(
+                &mut test_env,
+                StakingSudo::SlashAtHeight {
+                    validator: validator.to_string(),
+                    percentage: Decimal::percent(10),
+                    infraction_height,
+                    power: Uint128::new(100),
+                },
+            )
+            .unwrap();
+            for delegator in [&delegator1, &delegator2] {
+                let response: DelegationResponse = query_stake(
+                    &test_env,
+                    StakingQuery::Delegation {
+                        delegator: delegator.to_string(),
+                        validator: validator.to_string(),
+                    },
+                )
+                .unwrap();
+                assert_eq!(response.delegation.unwrap().amount, coin(95, "TOKEN"));
+            }
+            assert_balances(
+                &test_env,
+                vec![(test_env.router.staking.module_addr.clone(), 190)],
+            );
+        }
+
+        #[test]
+        fn redelegation_restrictions() {
+            let (mut test_env, validator1) =
+                TestEnv::wrap(setup_test_env(Decimal::percent(10), Decimal::percent(10)));
+            let delegator = Addr::unchecked("delegator");
+            let validator2 = Addr::unchecked("validator2");
+            let validator3 = Addr::unchecked("validator3");
+
+            test_env
+                .router
+                .staking
+                .setup(
+                    &mut test_env.store,
+                    StakingInfo {
+                        max_entries: 2,
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            for validator in [&validator2, &validator3] {
+                test_env
+                    .router
+                    .staking
+                    .add_validator(
+                        &test_env.api,
+                        &mut test_env.store,
+                        &test_env.block,
+                        Validator {
+                            address: validator.to_string(),
+                            commission: Decimal::zero(),
+                            max_commission: Decimal::percent(20),
+                            max_change_rate: Decimal::percent(1),
+                        },
+                    )
+                    .unwrap();
+            }
+            test_env
+                .router
+                .bank
+                .init_balance(&mut test_env.store, &delegator, vec![coin(100, "TOKEN")])
+                .unwrap();
+            execute_stake(
+                &mut test_env,
+                delegator.clone(),
+                StakingMsg::Delegate {
+                    validator: validator1.to_string(),
+                    amount: coin(100, "TOKEN"),
+                },
+            )
+            .unwrap();
+
+            let redelegate = |src: &Addr, dst: &Addr| StakingMsg::Redelegate {
+                src_validator: src.to_string(),
+                dst_validator: dst.to_string(),
+                amount: coin(10, "TOKEN"),
+            };
+
+            // cannot redelegate to the same validator
+            let err = execute_stake(
+                &mut test_env,
+                delegator.clone(),
+                redelegate(&validator1, &validator1),
+            )
+            .unwrap_err();
+            assert_eq!(
+                &Error::self_redelegation(),
+                err.downcast_ref::<Error>().unwrap()
+            );
+
+            let response = execute_stake(
+                &mut test_env,
+                delegator.clone(),
+                redelegate(&validator1, &validator2),
+            )
+            .unwrap();
+            let completion_time = test_env.block.time.plus_seconds(60);
+            assert_eq!(
+                response.events[0].attributes[3].value,
+                completion_time.to_string()
+            );
+            let redelegations = test_env
+                .router
+                .staking
+                .redelegations(&test_env.store, &delegator)
+                .unwrap();
+            assert_eq!(
+                redelegations,
+                vec![Redelegation {
+                    delegator: delegator.clone(),
+                    src_validator: validator1.clone(),
+                    dst_validator: validator2.clone(),
+                    amount: Uint128::new(10),
+                    creation_height: test_env.block.height,
+                    completion_time,
+                }]
+            );
+
+            // transitive redelegation is not allowed while the first one is pending
+            let err = execute_stake(
+                &mut test_env,
+                delegator.clone(),
+                redelegate(&validator2, &validator3),
+            )
+            .unwrap_err();
+            assert_eq!(
+                &Error::transitive_redelegation(),
+                err.downcast_ref::<Error>().unwrap()
+            );
+            let response: DelegationResponse = query_stake(
+                &test_env,
+                StakingQuery::Delegation {
+                    delegator: delegator.to_string(),
+                    validator: validator2.to_string(),
+                },
+            )
+            .unwrap();
+            assert_eq!(
+                response.delegation.unwrap().can_redelegate,
+                coin(0, "TOKEN")
+            );
+
+            // number of entries per tuple is limited
+            execute_stake(
+                &mut test_env,
+                delegator.clone(),
+                redelegate(&validator1, &validator2),
+            )
+            .unwrap();
+            let err = execute_stake(
+                &mut test_env,
+                delegator.clone(),
+                redelegate(&validator1, &validator2),
+            )
+            .unwrap_err();
+            assert_eq!(
+                &Error::max_redelegation_entries(),
+                err.downcast_ref::<Error>().unwrap()
+            );
+            execute_stake(
+                &mut test_env,
+                delegator.clone(),
+                redelegate(&validator1, &validator3),
+            )
+            .unwrap();
+            let redelegations = test_env
+                .router
+                .staking
+                .redelegations_between(&test_env.store, &validator1, &validator2)
+                .unwrap();
+            assert_eq!(redelegations.len(), 2);
+
+            // after completion, redelegations are removed
+            test_env.block.time = completion_time;
+            let response = test_env
+                .router
+                .staking
+                .process_queue(
+                    &test_env.api,
+                    &mut test_env.store,
+                    &test_env.router,
+                    &test_env.block,
+                )
+                .unwrap();
+            assert_eq!(response.events.len(), 3);
+            assert_eq!(response.events[0].ty, "complete_redelegation");
+            assert!(test_env
+                .router
+                .staking
+                .redelegations(&test_env.store, &delegator)
+                .unwrap()
+                .is_empty());
+            execute_stake(
+                &mut test_env,
+                delegator.clone(),
+                redelegate(&validator2, &validator3),
+            )
+            .unwrap();
+        }
+        #[test]
+        fn unbonding_entries_are_limited_and_queryable() {
+            let (mut test_env, validator) =
+                TestEnv::wrap(setup_test_env(Decimal::percent(10), Decimal::percent(10)));
+            let delegator = Addr::unchecked("delegator");
+
+            test_env
+                .router
+                .staking
+                .setup(
+                    &mut test_env.store,
+                    StakingInfo {
+                        max_entries: 2,
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            test_env
+                .router
+                .bank
+                .init_balance(&mut test_env.store, &delegator, vec![coin(100, "TOKEN")])
+                .unwrap();
+            execute_stake(
+                &mut test_env,
+                delegator.clone(),
+                StakingMsg::Delegate {
+                    validator: validator.to_string(),
+                    amount: coin(100, "TOKEN"),
+                },
+            )
+            .unwrap();
+
+            let undelegate = StakingMsg::Undelegate {
+                validator: validator.to_string(),
+                amount: coin(10, "TOKEN"),
+            };
+            let response =
+                execute_stake(&mut test_env, delegator.clone(), undelegate.clone()).unwrap();
+            let first_completion = test_env.block.time.plus_seconds(60);
+            assert_eq!(
+                response.events[0].attributes[2].value,
+                first_completion.to_string()
+            );
+            test_env.block.time = test_env.block.time.plus_seconds(10);
+            test_env.block.height += 1;
+            execute_stake(&mut test_env, delegator.clone(), undelegate.clone()).unwrap();
+
+            // only two entries are allowed
+            let err =
+                execute_stake(&mut test_env, delegator.clone(), undelegate.clone()).unwrap_err();
+            assert_eq!(
+                &Error::max_unbonding_entries(),
+                err.downcast_ref::<Error>().unwrap()
+            );
+
+            let unbondings = test_env
+                .router
+                .staking
+                .unbonding_delegations(&test_env.store, &delegator)
+                .unwrap();
+            assert_eq!(
+                unbondings,
+                vec![
+                    Unbonding {
+                        delegator: delegator.clone(),
+                        validator: validator.clone(),
+                        amount: Uint128::new(10),
+                        initial_balance: Uint128::new(10),
+                        payout_at: first_completion,
+                        creation_height: test_env.block.height - 1,
+                    },
+                    Unbonding {
+                        delegator: delegator.clone(),
+                        validator: validator.clone(),
+                        amount: Uint128::new(10),
+                        initial_balance: Uint128::new(10),
+                        payout_at: first_completion.plus_seconds(10),
+                        creation_height: test_env.block.height,
+                    },
+                ]
+            );
+
+            // completing the first entry allows a new one
+            test_env.block.time = first_completion;
+            test_env
+                .router
+                .staking
+                .process_queue(
+                    &test_env.api,
+                    &mut test_env.store,
+                    &test_env.router,
+                    &test_env.block,
+                )
+                .unwrap();
+            assert_balances(&test_env, vec![(delegator.clone(), 10)]);
+            assert_eq!(
+                test_env
+                    .router
+                    .staking
+                    .unbonding_delegations(&test_env.store, &delegator)
+                    .unwrap()
+                    .len(),
+                1
+            );
+            execute_stake(&mut test_env, delegator, undelegate).unwrap();
+        }
+        #[test]
+        #[cfg(feature = "cosmwasm_1_1")]
+        fn minting_funds_rewards() {
+            let (mut test_env, validator) =
+                TestEnv::wrap(setup_test_env(Decimal::percent(10), Decimal::percent(10)));
+            let delegator = Addr::unchecked("delegator");
+            let params = MintParams {
+                blocks_per_year: 10,
+                ..Default::default()
+            };
+
+            test_env
+                .router
+                .staking
+                .setup_mint(&mut test_env.store, params.clone(), Decimal::percent(13))
+                .unwrap();
+            test_env
+                .router
+                .bank
+                .init_balance(
+                    &mut test_env.store,
+                    &delegator,
+                    vec![coin(1_000_000, "TOKEN")],
+                )
+                .unwrap();
+            execute_stake(
+                &mut test_env,
+                delegator.clone(),
+                StakingMsg::Delegate {
+                    validator: validator.to_string(),
+                    amount: coin(500_000, "TOKEN"),
+                },
+            )
+            .unwrap();
+
+            // half of the supply is bonded, below the goal, so inflation grows
+            let response = test_env
+                .router
+                .staking
+                .begin_block(
+                    &test_env.api,
+                    &mut test_env.store,
+                    &test_env.router,
+                    &test_env.block,
+                )
+                .unwrap();
+            let minter = test_env
+                .router
+                .staking
+                .minter(&test_env.store)
+                .unwrap()
+                .unwrap();
+            assert!(minter.inflation > Decimal::percent(13));
+            assert_eq!(
+                minter.annual_provisions,
+                minter.inflation * Decimal::from_ratio(1_000_000u128, 1u128)
+            );
+            let minted = minter.block_provision(&params);
+            assert_eq!(minted, Uint128::new(13_329));
+            let mint_event = response.events.last().unwrap();
+            assert_eq!(mint_event.ty, "mint");
+            assert_eq!(mint_event.attributes[0].value, "0.5");
+
+            // minted tokens end up in the distribution module
+            assert_balances(
+                &test_env,
+                vec![
+                    (Addr::unchecked(FEE_COLLECTOR), 0),
+                    (Addr::unchecked(DISTRIBUTION_MODULE), minted.u128()),
+                ],
+            );
+            let supply: SupplyResponse = query_bank(
+                &test_env,
+                BankQuery::Supply {
+                    denom: "TOKEN".to_string(),
+                },
+            )
+            .unwrap();
+            assert_eq!(supply.amount.amount, Uint128::new(1_000_000) + minted);
+
+            // processing the same block again does not mint anything,
+            // and the fixed apr is not used when minting
+            test_env.block.time = test_env.block.time.plus_seconds(60 * 60 * 24 * 365);
+            test_env
+                .router
+                .staking
+                .begin_block(
+                    &test_env.api,
+                    &mut test_env.store,
+                    &test_env.router,
+                    &test_env.block,
+                )
+                .unwrap();
+            assert_balances(
+                &test_env,
+                vec![(Addr::unchecked(DISTRIBUTION_MODULE), minted.u128())],
+            );
+
+            // the delegator gets the minted tokens without the validator's commission
+            let rewards = test_env
+                .router
+                .staking
+                .get_rewards(&test_env.store, &test_env.block, &delegator, &validator)
+                .unwrap()
+                .unwrap();
+            assert_eq!(rewards.amount, Uint128::new(11_996));
+            execute_distr(
+                &mut test_env,
+                delegator.clone(),
+                DistributionMsg::WithdrawDelegatorReward {
+                    validator: validator.to_string(),
+                },
+            )
+            .unwrap();
+            assert_balances(
+                &test_env,
+                vec![
+                    (delegator, 500_000 + 11_996),
+                    (Addr::unchecked(DISTRIBUTION_MODULE), minted.u128() - 11_996),
+                ],
+            );
+
+            // the validator's commission is accumulated until it is withdrawn
+            assert_eq!(
+                test_env
+                    .router
+                    .staking
+                    .validator_commission(&test_env.store, &validator)
+                    .unwrap(),
+                Decimal::from_ratio(13_329u128, 10u128)
+            );
+            let response = sudo_stake(
+                &mut test_env,
+                StakingSudo::WithdrawValidatorCommission {
+                    validator: validator.to_string(),
+                },
+            )
+            .unwrap();
+            assert_eq!(response.events[0].ty, "withdraw_commission");
+            assert_eq!(response.events[0].attributes[0].value, "1332TOKEN");
+            assert_balances(
+                &test_env,
+                vec![
+                    (validator.clone(), 1_332),
+                    (
+                        Addr::unchecked(DISTRIBUTION_MODULE),
+                        minted.u128() - 11_996 - 1_332,
+                    ),
+                ],
+            );
+            assert_eq!(
+                test_env
+                    .router
+                    .staking
+                    .validator_commission(&test_env.store, &validator)
+                    .unwrap(),
+                Decimal::from_ratio(9u128, 10u128)
+            );
+        }
     }
 }
@@ -0,0 +1,53 @@
+use crate::{BlockSudoMsg, Contract, ContractWrapper};
+use cosmwasm_std::{
+    Binary, CustomMsg, Deps, DepsMut, Empty, Env, Event, MessageInfo, Response, StdError,
+};
+
+fn instantiate(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: Empty,
+) -> Result<Response, StdError> {
+    Ok(Response::default())
+}
+
+fn execute(
+    _deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    _msg: Empty,
+) -> Result<Response, StdError> {
+    let tx_index = env.transaction.map(|tx| tx.index).unwrap_or_default();
+    Ok(Response::new().add_event(
+        Event::new("tx")
+            .add_attribute("height", env.block.height.to_string())
+            .add_attribute("time", env.block.time.to_string())
+            .add_attribute("index", tx_index.to_string()),
+    ))
+}
+
+fn sudo(_deps: DepsMut, env: Env, msg: BlockSudoMsg) -> Result<Response, StdError> {
+    let hook = match msg {
+        BlockSudoMsg::BeginBlock {} => "begin_block",
+        BlockSudoMsg::EndBlock {} => "end_block",
+    };
+    Ok(Response::new().add_event(
+        Event::new("block_hook")
+            .add_attribute("hook", hook)
+            .add_attribute("height", env.block.height.to_string()),
+    ))
+}
+
+fn query(_deps: Deps, _env: Env, _msg: Empty) -> Result<Binary, StdError> {
+    Ok(Binary::default())
+}
+
+pub fn contract<C>() -> Box<dyn Contract<C>>
+where
+    C: CustomMsg + 'static,
+{
+    let contract =
+        ContractWrapper::new_with_empty(execute, instantiate, query).with_sudo_empty(sudo);
+    Box::new(contract)
+}
@@ -0,0 +1,48 @@
+use crate::{Contract, ContractWrapper, StakingHookMsg};
+use cosmwasm_std::{
+    to_json_binary, Binary, CustomMsg, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdError,
+};
+use cw_storage_plus::Item;
+
+/// Staking hook messages received by the contract.
+const HOOKS: Item<Vec<StakingHookMsg>> = Item::new("hooks");
+
+fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: Empty,
+) -> Result<Response, StdError> {
+    HOOKS.save(deps.storage, &vec![])?;
+    Ok(Response::default())
+}
+
+fn execute(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: Empty,
+) -> Result<Response, StdError> {
+    Ok(Response::default())
+}
+
+fn sudo(deps: DepsMut, _env: Env, msg: StakingHookMsg) -> Result<Response, StdError> {
+    HOOKS.update(deps.storage, |mut hooks| -> Result<_, StdError> {
+        hooks.push(msg);
+        Ok(hooks)
+    })?;
+    Ok(Response::default())
+}
+
+fn query(deps: Deps, _env: Env, _msg: Empty) -> Result<Binary, StdError> {
+    to_json_binary(&HOOKS.load(deps.storage)?)
+}
+
+pub fn contract<C>() -> Box<dyn Contract<C>>
+where
+    C: CustomMsg + 'static,
+{
+    let contract =
+        ContractWrapper::new_with_empty(execute, instantiate, query).with_sudo_empty(sudo);
+    Box::new(contract)
+}
@@ -5,11 +5,13 @@ use cw_storage_plus::Item;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+pub mod blocker;
 pub mod caller;
 pub mod echo;
 pub mod error;
 pub mod gov;
 pub mod hackatom;
+pub mod hooks;
 pub mod ibc;
 pub mod payout;
 pub mod reflect;
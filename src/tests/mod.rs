@@ -2,7 +2,11 @@
 
 mod test_app;
 mod test_custom_handler;
+mod test_dyn_app;
 mod test_error;
+#[cfg(feature = "proptest")]
+mod test_fuzz;
 mod test_gov;
 mod test_ibc;
+mod test_registry;
 mod test_stargate;
@@ -1,27 +1,34 @@
 use crate::custom_handler::CachingCustomHandler;
-use crate::error::{bail, AnyResult};
+use crate::error::{bail, AnyResult, Error};
+use crate::prefixed_storage::{prefixed, PrefixedStorage};
 use crate::test_helpers::echo::EXECUTE_REPLY_BASE_ID;
-use crate::test_helpers::{caller, echo, error, hackatom, payout, reflect, CustomHelperMsg};
+use crate::test_helpers::{
+    blocker, caller, echo, error, hackatom, hooks, payout, reflect, CustomHelperMsg,
+};
 use crate::transactions::{transactional, StorageTransaction};
-use crate::wasm::ContractData;
-use crate::AppBuilder;
+use crate::wasm::{ContractData, CONTRACT_NAMESPACE_PREFIX, NAMESPACE_WASM};
 use crate::{
-    custom_app, next_block, no_init, App, AppResponse, Bank, CosmosRouter, Distribution, Executor,
-    Module, Router, Staking, Wasm, WasmSudo,
+    custom_app, next_block, no_init, App, AppResponse, AppState, Bank, BankSudo, BlockProduction,
+    CodeState, ContractStateExport, CosmosRouter, DecodedKey, Distribution, Executor, Module,
+    Router, Staking, StakingHookMsg, StakingSudo, SudoMsg, TraceKind, TraceNode, TraceResult, Tx,
+    Wasm, WasmGenesis, WasmKeeper, WasmSudo, DEFAULT_GAS_LIMIT,
 };
+use crate::{AppBuilder, BasicAppBuilder, ContractWrapper};
 use cosmwasm_std::testing::{mock_env, MockQuerier};
 use cosmwasm_std::{
-    coin, coins, from_json, to_json_binary, Addr, AllBalanceResponse, Api, Attribute, BankMsg,
-    BankQuery, Binary, BlockInfo, Coin, CosmosMsg, CustomMsg, CustomQuery, Empty, Event,
-    OverflowError, OverflowOperation, Querier, Reply, StdError, StdResult, Storage, SubMsg,
+    coin, coins, from_json, to_json_binary, to_json_vec, Addr, AllBalanceResponse, Api, Attribute,
+    BankMsg, BankQuery, Binary, BlockInfo, Coin, CosmosMsg, CustomMsg, CustomQuery, Decimal, Deps,
+    DepsMut, Empty, Env, Event, MessageInfo, Order, OverflowError, OverflowOperation, Querier,
+    QuerierWrapper, Reply, Response, StakingMsg, StdError, StdResult, Storage, SubMsg, Uint128,
     WasmMsg,
 };
-use cw_storage_plus::Item;
-use cw_utils::parse_instantiate_response_data;
+use cw_storage_plus::{Item, Map};
+use cw_utils::{parse_instantiate_response_data, NativeBalance};
 use schemars::JsonSchema;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
+use std::time::Duration;
 
 /// Utility function that returns all balances for specified address.
 fn get_balance<BankT, ApiT, StorageT, CustomT, WasmT>(
@@ -575,6 +582,1847 @@ fn sudo_works() {
     assert_eq!(49, count);
 }
 
+#[test]
+fn snapshot_and_restore() {
+    let owner = Addr::unchecked("owner");
+    let receiver = Addr::unchecked("receiver");
+
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, coins(100, "eth"))
+            .unwrap();
+    });
+    let payout_id = app.store_code(payout::contract());
+    let payout_addr = app
+        .instantiate_contract(
+            payout_id,
+            owner.clone(),
+            &payout::InstantiateMessage {
+                payout: coin(5, "eth"),
+            },
+            &coins(20, "eth"),
+            "Payout",
+            None,
+        )
+        .unwrap();
+    let snapshot = app.snapshot();
+    let block = app.block_info();
+
+    for _ in 0..2 {
+        // change the state in many ways
+        app.send_tokens(owner.clone(), receiver.clone(), &coins(30, "eth"))
+            .unwrap();
+        app.wasm_sudo(payout_addr.clone(), &payout::SudoMsg { set_count: 25 })
+            .unwrap();
+        // codes stored after the snapshot are removed on restore, so the identifier repeats
+        let echo_id = app.store_code(echo::contract());
+        assert_eq!(echo_id, payout_id + 1);
+        app.update_block(next_block);
+
+        // and go back to the snapshot
+        app.restore(&snapshot);
+        assert_eq!(get_balance(&app, &owner), coins(80, "eth"));
+        assert_eq!(get_balance(&app, &receiver), vec![]);
+        assert_eq!(get_balance(&app, &payout_addr), coins(20, "eth"));
+        let payout::CountResponse { count } = app
+            .wrap()
+            .query_wasm_smart(&payout_addr, &payout::QueryMsg::Count {})
+            .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(app.block_info(), block);
+    }
+}
+
+#[test]
+fn export_and_import_state() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, coins(100, "eth"))
+            .unwrap();
+    });
+    app.update_block(next_block);
+    let payout_id = app.store_code_with_name("payout", payout::contract());
+    let payout_addr = app
+        .instantiate_contract(
+            payout_id,
+            owner.clone(),
+            &payout::InstantiateMessage {
+                payout: coin(5, "eth"),
+            },
+            &coins(20, "eth"),
+            "Payout",
+            None,
+        )
+        .unwrap();
+    app.wasm_sudo(payout_addr.clone(), &payout::SudoMsg { set_count: 25 })
+        .unwrap();
+
+    // export the state and save it as a JSON fixture
+    let json = app.export_state().to_json().unwrap();
+
+    // load the fixture into a new application, binding codes to contracts by name
+    let state = AppState::from_json(&json).unwrap();
+    assert_eq!(state, app.export_state());
+    let mut imported = App::default();
+    imported
+        .import_state(&state, |name| match name {
+            "payout" => Some(payout::contract()),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(imported.block_info(), app.block_info());
+    assert_eq!(imported.export_state(), state);
+    assert_eq!(get_balance(&imported, &owner), coins(80, "eth"));
+    assert_eq!(get_balance(&imported, &payout_addr), coins(20, "eth"));
+    let payout::CountResponse { count } = imported
+        .wrap()
+        .query_wasm_smart(&payout_addr, &payout::QueryMsg::Count {})
+        .unwrap();
+    assert_eq!(count, 25);
+
+    // the imported contract keeps working, and new addresses do not collide
+    imported
+        .execute_contract(owner.clone(), payout_addr.clone(), &Empty {}, &[])
+        .unwrap();
+    assert_eq!(get_balance(&imported, &owner), coins(85, "eth"));
+    let second_addr = imported
+        .instantiate_contract(
+            payout_id,
+            owner.clone(),
+            &payout::InstantiateMessage {
+                payout: coin(5, "eth"),
+            },
+            &[],
+            "Payout",
+            None,
+        )
+        .unwrap();
+    assert_ne!(second_addr, payout_addr);
+
+    // codes without a matching contract can not be imported
+    let err = App::default().import_state(&state, |_| None).unwrap_err();
+    assert_eq!(err.to_string(), "code id 1: no contract named 'payout'");
+
+    // nothing is imported when any of the codes can not be resolved
+    let mut partial_state = state.clone();
+    partial_state.codes.push(CodeState {
+        code_id: 2,
+        name: Some("unknown".to_string()),
+        ..state.codes[0].clone()
+    });
+    let mut untouched = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, coins(7, "eth"))
+            .unwrap();
+    });
+    let before = untouched.export_state();
+    let err = untouched
+        .import_state(&partial_state, |name| match name {
+            "payout" => Some(payout::contract()),
+            _ => None,
+        })
+        .unwrap_err();
+    assert_eq!(err.to_string(), "code id 2: no contract named 'unknown'");
+    assert_eq!(untouched.export_state(), before);
+    assert!(before.codes.is_empty());
+
+    // state can not be imported when codes are already stored
+    let err = app
+        .import_state(&state, |_| Some(payout::contract()))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "state can be imported only into an application without stored codes"
+    );
+}
+
+#[test]
+fn import_wasm_genesis() {
+    // shortened output of `wasmd export`, with two codes and one contract
+    let json = r#"{
+      "genesis_time": "2024-01-01T00:00:00Z",
+      "chain_id": "testing",
+      "initial_height": "101",
+      "app_state": {
+        "bank": { "balances": [], "supply": [] },
+        "wasm": {
+          "params": {
+            "code_upload_access": { "permission": "Everybody", "addresses": [] },
+            "instantiate_default_permission": "Everybody"
+          },
+          "codes": [
+            {
+              "code_id": "1",
+              "code_info": {
+                "code_hash": "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=",
+                "creator": "wasm1creator",
+                "instantiate_config": { "permission": "Everybody", "addresses": [] }
+              },
+              "code_bytes": "AGFzbQ==",
+              "pinned": false
+            },
+            {
+              "code_id": "3",
+              "code_info": {
+                "code_hash": "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=",
+                "creator": "wasm1creator",
+                "instantiate_config": { "permission": "Everybody", "addresses": [] }
+              },
+              "code_bytes": "AGFzbQ==",
+              "pinned": false
+            }
+          ],
+          "contracts": [
+            {
+              "contract_address": "wasm1payout",
+              "contract_info": {
+                "code_id": "3",
+                "creator": "wasm1creator",
+                "admin": "wasm1admin",
+                "label": "Payout",
+                "created": { "block_height": "42", "tx_index": "0" },
+                "ibc_port_id": "",
+                "extension": null
+              },
+              "contract_state": [
+                { "key": "636F756E74", "value": "MjU=" },
+                {
+                  "key": "7061796F7574",
+                  "value": "eyJwYXlvdXQiOnsiZGVub20iOiJldGgiLCJhbW91bnQiOiI1In19"
+                }
+              ],
+              "contract_code_history": []
+            }
+          ],
+          "sequences": [
+            { "id_key": "BGxhc3RDb2RlSWQ=", "value": "4" },
+            { "id_key": "BGxhc3RDb250cmFjdElk", "value": "2" }
+          ]
+        }
+      }
+    }"#;
+    let genesis = WasmGenesis::from_json(json).unwrap();
+    assert_eq!(genesis.codes.len(), 2);
+    assert_eq!(genesis.contracts.len(), 1);
+
+    let owner = Addr::unchecked("owner");
+    let payout_addr = Addr::unchecked("wasm1payout");
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &payout_addr, coins(20, "eth"))
+            .unwrap();
+    });
+    app.import_wasm_genesis(&genesis, |code_id| match code_id {
+        3 => Some(payout::contract()),
+        _ => None,
+    })
+    .unwrap();
+
+    // contract info is imported
+    let contract_data = app.contract_data(&payout_addr).unwrap();
+    assert_eq!(contract_data.code_id, 3);
+    assert_eq!(contract_data.creator, "wasm1creator");
+    assert_eq!(contract_data.admin, Some(Addr::unchecked("wasm1admin")));
+    assert_eq!(contract_data.label, "Payout");
+    assert_eq!(contract_data.created, 42);
+
+    // contract state is imported and the bound contract works
+    let payout::CountResponse { count } = app
+        .wrap()
+        .query_wasm_smart(&payout_addr, &payout::QueryMsg::Count {})
+        .unwrap();
+    assert_eq!(count, 25);
+    app.execute_contract(owner.clone(), payout_addr.clone(), &Empty {}, &[])
+        .unwrap();
+    assert_eq!(get_balance(&app, &owner), coins(5, "eth"));
+
+    // codes not bound to any contract fail, but keep the on-chain identifiers
+    let err = app
+        .instantiate_contract(1, owner.clone(), &Empty {}, &[], "Unbound", None)
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "code id 1: no contract bound to imported code"
+    );
+    assert_eq!(app.store_code(echo::contract()), 4);
+
+    // the state of a single contract can be replaced with the output of `contract-state all`
+    let state = ContractStateExport::from_json(
+        r#"{
+          "models": [
+            {
+              "key": "7061796F7574",
+              "value": "eyJwYXlvdXQiOnsiZGVub20iOiJldGgiLCJhbW91bnQiOiI1In19"
+            }
+          ],
+          "pagination": { "next_key": null, "total": "0" }
+        }"#,
+    )
+    .unwrap();
+    app.import_contract_state(&payout_addr, &state).unwrap();
+    assert_eq!(app.dump_wasm_raw(&payout_addr).len(), 1);
+}
+
+#[test]
+fn block_hooks_are_called() {
+    let owner = Addr::unchecked("owner");
+    let mut app = App::default();
+    let code_id = app.store_code(blocker::contract());
+    let first = app
+        .instantiate_contract(code_id, owner.clone(), &Empty {}, &[], "First", None)
+        .unwrap();
+    let second = app
+        .instantiate_contract(code_id, owner.clone(), &Empty {}, &[], "Second", None)
+        .unwrap();
+    app.register_block_hooks(&first).unwrap();
+    app.register_block_hooks(&second).unwrap();
+
+    let height = app.block_info().height;
+    let res = app.advance_block(next_block).unwrap();
+    let hooks: Vec<(String, String, String)> = res
+        .events
+        .iter()
+        .filter(|event| event.ty == "wasm-block_hook")
+        .map(|event| {
+            (
+                event.attributes[0].value.clone(),
+                event.attributes[1].value.clone(),
+                event.attributes[2].value.clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        hooks,
+        vec![
+            (
+                first.to_string(),
+                "end_block".to_string(),
+                height.to_string()
+            ),
+            (
+                second.to_string(),
+                "end_block".to_string(),
+                height.to_string()
+            ),
+            (
+                first.to_string(),
+                "begin_block".to_string(),
+                (height + 1).to_string()
+            ),
+            (
+                second.to_string(),
+                "begin_block".to_string(),
+                (height + 1).to_string()
+            ),
+        ]
+    );
+    assert_eq!(app.block_info().height, height + 1);
+
+    // registration is checked
+    let err = app.register_block_hooks(&first).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!("block hooks already registered: {}", first)
+    );
+    let err = app
+        .register_block_hooks(&Addr::unchecked("unknown"))
+        .unwrap_err();
+    assert!(err.to_string().contains("not found"), "{}", err);
+
+    // unregistered contracts are not called anymore
+    app.unregister_block_hooks(&first).unwrap();
+    app.unregister_block_hooks(&second).unwrap();
+    let err = app.unregister_block_hooks(&first).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!("block hooks not registered: {}", first)
+    );
+    let res = app.advance_block(next_block).unwrap();
+    assert!(res.events.is_empty());
+}
+
+#[test]
+fn blocks_are_produced_automatically() {
+    let owner = Addr::unchecked("owner");
+    let mut app = App::default();
+    let code_id = app.store_code(blocker::contract());
+    let contract = app
+        .instantiate_contract(code_id, owner.clone(), &Empty {}, &[], "Blocker", None)
+        .unwrap();
+    let block = app.block_info();
+
+    app.set_block_production(BlockProduction {
+        txs_per_block: 2,
+        block_time: Duration::from_secs(6),
+        jitter: Duration::ZERO,
+    });
+    let mut txs = vec![];
+    for _ in 0..5 {
+        let res = app
+            .execute_contract(owner.clone(), contract.clone(), &Empty {}, &[])
+            .unwrap();
+        let event = res.events.iter().find(|e| e.ty == "wasm-tx").unwrap();
+        txs.push((
+            event.attributes[1].value.parse::<u64>().unwrap() - block.height,
+            event.attributes[2].value.clone(),
+            event.attributes[3].value.clone(),
+        ));
+    }
+    let time = |seconds| block.time.plus_seconds(seconds).to_string();
+    assert_eq!(
+        txs,
+        vec![
+            (0, time(0), "0".to_string()),
+            (0, time(0), "1".to_string()),
+            (1, time(6), "0".to_string()),
+            (1, time(6), "1".to_string()),
+            (2, time(12), "0".to_string()),
+        ]
+    );
+
+    // the transaction index is not persisted in the chain storage
+    assert!(!app
+        .storage()
+        .range(None, None, Order::Ascending)
+        .any(|(key, _)| key.ends_with(b"tx_index")));
+
+    // failed transactions take their place in the block too
+    app.execute_contract(owner.clone(), Addr::unchecked("unknown"), &Empty {}, &[])
+        .unwrap_err();
+    app.execute_contract(owner.clone(), contract.clone(), &Empty {}, &[])
+        .unwrap();
+    assert_eq!(app.block_info().height, block.height + 3);
+
+    // jitter adds up to the specified time to the block time
+    app.set_block_production(BlockProduction {
+        txs_per_block: 1,
+        block_time: Duration::from_secs(5),
+        jitter: Duration::from_secs(2),
+    });
+    // the first transaction is placed in the current block
+    let previous = app.block_info();
+    app.execute_contract(owner.clone(), contract.clone(), &Empty {}, &[])
+        .unwrap();
+    assert_eq!(app.block_info(), previous);
+    for _ in 0..10 {
+        let previous = app.block_info();
+        app.execute_contract(owner.clone(), contract.clone(), &Empty {}, &[])
+            .unwrap();
+        let current = app.block_info();
+        assert_eq!(current.height, previous.height + 1);
+        let elapsed = current.time.nanos() - previous.time.nanos();
+        assert!((5_000_000_000..=7_000_000_000).contains(&elapsed));
+    }
+
+    // without block production, all transactions are in the same block
+    app.set_block_production(None);
+    let block = app.block_info();
+    for _ in 0..3 {
+        let res = app
+            .execute_contract(owner.clone(), contract.clone(), &Empty {}, &[])
+            .unwrap();
+        let event = res.events.iter().find(|e| e.ty == "wasm-tx").unwrap();
+        assert_eq!(event.attributes[3].value, "0");
+    }
+    assert_eq!(app.block_info(), block);
+}
+
+#[test]
+fn deliver_tx_with_multiple_messages() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let bob = Addr::unchecked("bob");
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, coins(100, "eth"))
+            .unwrap();
+    });
+    let send = |to: &Addr, amount| -> CosmosMsg {
+        BankMsg::Send {
+            to_address: to.to_string(),
+            amount: coins(amount, "eth"),
+        }
+        .into()
+    };
+
+    // successful transaction executes all messages and pays the fee
+    let tx = Tx::new(owner.clone(), vec![send(&alice, 10), send(&bob, 20)])
+        .with_memo("first")
+        .with_fee(coins(1, "eth"));
+    let res = app.deliver_tx(tx).unwrap();
+    assert!(res.is_ok());
+    assert_eq!(res.height, app.block_info().height);
+    assert_eq!(res.txhash.len(), 64);
+    assert_eq!(res.gas_wanted, DEFAULT_GAS_LIMIT);
+    assert!(res.gas_used > 0 && res.gas_used < res.gas_wanted);
+    assert_eq!(res.msg_responses.len(), 2);
+    let transfers: Vec<(String, String)> = res
+        .events
+        .iter()
+        .filter(|event| event.ty == "transfer")
+        .map(|event| {
+            let attr = |key: &str| {
+                event
+                    .attributes
+                    .iter()
+                    .find(|attr| attr.key == key)
+                    .map(|attr| attr.value.clone())
+                    .unwrap_or_default()
+            };
+            (attr("recipient"), attr("msg_index"))
+        })
+        .collect();
+    assert_eq!(
+        transfers,
+        vec![
+            ("fee_collector".to_string(), "".to_string()),
+            (alice.to_string(), "0".to_string()),
+            (bob.to_string(), "1".to_string()),
+        ]
+    );
+    assert_eq!(get_balance(&app, &owner), coins(69, "eth"));
+    assert_eq!(app.query_tx(&res.txhash).unwrap(), res);
+
+    // failed transaction reverts all messages, but pays the fee
+    let tx =
+        Tx::new(owner.clone(), vec![send(&alice, 10), send(&bob, 100)]).with_fee(coins(1, "eth"));
+    let res = app.deliver_tx(tx).unwrap();
+    assert_eq!(res.code, 1);
+    assert_eq!(res.codespace, "undefined");
+    assert!(res
+        .raw_log
+        .starts_with("failed to execute message; message index: 1: "));
+    assert!(res.msg_responses.is_empty());
+    assert_eq!(get_balance(&app, &owner), coins(68, "eth"));
+    assert_eq!(get_balance(&app, &alice), coins(10, "eth"));
+    assert_eq!(app.query_tx(&res.txhash).unwrap(), res);
+
+    // transaction exceeding the gas limit fails
+    let tx = Tx::new(owner.clone(), vec![send(&alice, 10)]).with_gas_limit(1000);
+    let res = app.deliver_tx(tx).unwrap();
+    assert_eq!((res.code, res.codespace.as_str()), (11, "sdk"));
+    assert!(res.gas_used > res.gas_wanted);
+    assert_eq!(get_balance(&app, &alice), coins(10, "eth"));
+
+    // transaction failing to pay the fee is rejected
+    let tx = Tx::new(alice.clone(), vec![send(&bob, 1)]).with_fee(coins(100, "eth"));
+    let res = app.deliver_tx(tx).unwrap();
+    assert_eq!((res.code, res.codespace.as_str()), (13, "sdk"));
+    assert_eq!(get_balance(&app, &bob), coins(20, "eth"));
+
+    // identical transactions have different hashes
+    let first = app
+        .deliver_tx(Tx::new(owner.clone(), vec![send(&alice, 1)]))
+        .unwrap();
+    let second = app
+        .deliver_tx(Tx::new(owner.clone(), vec![send(&alice, 1)]))
+        .unwrap();
+    assert_ne!(first.txhash, second.txhash);
+
+    // malformed transactions and unknown hashes are errors
+    let err = app.deliver_tx(Tx::new(owner.clone(), vec![])).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "transaction must contain at least one message"
+    );
+    let err = app.query_tx("ABCD").unwrap_err();
+    assert_eq!(err.to_string(), "tx not found: ABCD");
+}
+
+#[test]
+fn simulate_does_not_commit() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, coins(100, "eth"))
+            .unwrap();
+    });
+    let code_id = app.store_code(payout::contract());
+    let instantiate = WasmMsg::Instantiate {
+        admin: None,
+        code_id,
+        msg: to_json_binary(&payout::InstantiateMessage {
+            payout: coin(5, "eth"),
+        })
+        .unwrap(),
+        funds: coins(20, "eth"),
+        label: "Payout".to_string(),
+    };
+    let send = BankMsg::Send {
+        to_address: alice.to_string(),
+        amount: coins(10, "eth"),
+    };
+    let msgs: Vec<CosmosMsg> = vec![instantiate.into(), send.into()];
+    let state = app.export_state();
+
+    let simulation = app.simulate(owner.clone(), msgs.clone()).unwrap();
+    assert_eq!(simulation.responses.len(), 2);
+    assert!(simulation.gas_used > 0);
+    // nothing was committed
+    assert_eq!(app.export_state(), state);
+    assert_eq!(get_balance(&app, &owner), coins(100, "eth"));
+
+    // changes are the same as made by the executed messages
+    let changed_keys: Vec<Binary> = simulation
+        .changes
+        .iter()
+        .map(|change| change.key.clone())
+        .collect();
+    app.execute_multi(owner.clone(), msgs.clone()).unwrap();
+    let after = app.export_state();
+    for change in simulation.changes.iter() {
+        let value = after
+            .storage
+            .iter()
+            .find(|entry| entry.key == change.key)
+            .map(|entry| entry.value.clone());
+        assert_eq!(value, change.new_value);
+        let old_value = state
+            .storage
+            .iter()
+            .find(|entry| entry.key == change.key)
+            .map(|entry| entry.value.clone());
+        assert_eq!(old_value, change.old_value);
+    }
+    // executed messages are also recorded in the transaction history of the application
+    for entry in after.storage.iter() {
+        if !state.storage.contains(entry) && !entry.key.starts_with(b"\x00\x03app") {
+            assert!(changed_keys.contains(&entry.key));
+        }
+    }
+
+    // gas estimate is the same as gas used by the delivered transaction
+    let simulation = app.simulate(owner.clone(), msgs.clone()).unwrap();
+    let res = app.deliver_tx(Tx::new(owner.clone(), msgs)).unwrap();
+    assert!(res.is_ok());
+    assert_eq!(simulation.gas_used, res.gas_used);
+
+    // failing messages return an error
+    let send = BankMsg::Send {
+        to_address: alice.to_string(),
+        amount: coins(1000, "eth"),
+    };
+    app.simulate(owner, vec![send.into()]).unwrap_err();
+}
+
+#[test]
+fn state_diff_is_recorded() {
+    let owner = Addr::unchecked("owner");
+    let alice = Addr::unchecked("alice");
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, coins(100, "eth"))
+            .unwrap();
+    });
+    let code_id = app.store_code(payout::contract());
+    let send = BankMsg::Send {
+        to_address: alice.to_string(),
+        amount: coins(10, "eth"),
+    };
+
+    let (_, diff) = app.execute_with_diff(owner.clone(), send.into()).unwrap();
+    assert!(diff.contracts.is_empty());
+    assert_eq!(diff.modules.keys().collect::<Vec<_>>(), vec!["bank"]);
+    let decoded: Vec<DecodedKey> = diff.modules["bank"]
+        .iter()
+        .map(|change| change.decode_key())
+        .collect();
+    assert_eq!(
+        decoded,
+        vec![
+            DecodedKey {
+                namespace: "balances".to_string(),
+                components: vec![Binary::from(alice.as_bytes())],
+            },
+            DecodedKey {
+                namespace: "balances".to_string(),
+                components: vec![Binary::from(owner.as_bytes())],
+            },
+        ]
+    );
+    assert_ne!(
+        diff.modules["bank"][0].old_value,
+        diff.modules["bank"][0].new_value
+    );
+
+    let msg = WasmMsg::Instantiate {
+        admin: None,
+        code_id,
+        msg: to_json_binary(&payout::InstantiateMessage {
+            payout: coin(5, "eth"),
+        })
+        .unwrap(),
+        funds: vec![],
+        label: "Payout".to_string(),
+    };
+    let (res, diff) = app.execute_with_diff(owner.clone(), msg.into()).unwrap();
+    let contract_addr = res.events[0].attributes[0].value.clone();
+    assert_eq!(diff.modules.keys().collect::<Vec<_>>(), vec!["wasm"]);
+    assert_eq!(
+        diff.modules["wasm"][0].decode_key(),
+        DecodedKey {
+            namespace: "contracts".to_string(),
+            components: vec![Binary::from(contract_addr.as_bytes())],
+        }
+    );
+    let keys: Vec<String> = diff.contracts[&contract_addr]
+        .iter()
+        .map(|change| change.decode_key().namespace)
+        .collect();
+    assert_eq!(keys, vec!["count", "payout"]);
+
+    // the diff is empty when nothing has changed
+    let (_, diff) = app
+        .execute_with_diff(
+            owner.clone(),
+            BankMsg::Send {
+                to_address: owner.to_string(),
+                amount: coins(1, "eth"),
+            }
+            .into(),
+        )
+        .unwrap();
+    assert!(diff.is_empty());
+
+    // failed messages do not change the state
+    app.execute_with_diff(
+        owner.clone(),
+        BankMsg::Send {
+            to_address: alice.to_string(),
+            amount: coins(1000, "eth"),
+        }
+        .into(),
+    )
+    .unwrap_err();
+    assert_eq!(get_balance(&app, &alice), coins(10, "eth"));
+
+    // the diff of multiple messages contains changes made by all of them
+    let bob = Addr::unchecked("bob");
+    let (responses, diff) = app
+        .execute_multi_with_diff(
+            owner.clone(),
+            vec![
+                BankMsg::Send {
+                    to_address: alice.to_string(),
+                    amount: coins(1, "eth"),
+                }
+                .into(),
+                BankMsg::Send {
+                    to_address: bob.to_string(),
+                    amount: coins(2, "eth"),
+                }
+                .into(),
+            ],
+        )
+        .unwrap();
+    assert_eq!(responses.len(), 2);
+    let components: Vec<Binary> = diff.modules["bank"]
+        .iter()
+        .flat_map(|change| change.decode_key().components)
+        .collect();
+    assert_eq!(
+        components,
+        vec![
+            Binary::from(alice.as_bytes()),
+            Binary::from(bob.as_bytes()),
+            Binary::from(owner.as_bytes()),
+        ]
+    );
+    assert_eq!(get_balance(&app, &alice), coins(11, "eth"));
+    assert_eq!(get_balance(&app, &bob), coins(2, "eth"));
+}
+
+#[test]
+fn tracing_records_call_tree() {
+    let owner = Addr::unchecked("owner");
+    let mut app = App::default();
+    let code_id = app.store_code(echo::contract());
+    let first = app
+        .instantiate_contract(code_id, owner.clone(), &Empty {}, &[], "First", None)
+        .unwrap();
+    let second = app
+        .instantiate_contract(code_id, owner.clone(), &Empty {}, &[], "Second", None)
+        .unwrap();
+
+    // nothing is traced by default
+    assert!(app.tracer().is_none());
+
+    app.enable_tracing();
+    let echo_msg = WasmMsg::Execute {
+        contract_addr: second.to_string(),
+        msg: to_json_binary(&echo::Message::<Empty> {
+            data: Some("second".to_string()),
+            ..echo::Message::default()
+        })
+        .unwrap(),
+        funds: vec![],
+    };
+    let send_msg = BankMsg::Send {
+        to_address: owner.to_string(),
+        amount: coins(10, "eth"),
+    };
+    let msg = echo::Message::<Empty> {
+        sub_msg: vec![
+            SubMsg::reply_always(echo_msg, EXECUTE_REPLY_BASE_ID + 1),
+            SubMsg::reply_on_error(send_msg, 2),
+        ],
+        ..echo::Message::default()
+    };
+    app.execute_contract(owner.clone(), first.clone(), &msg, &[])
+        .unwrap();
+    app.wrap().query_balance(&owner, "eth").unwrap();
+
+    let tracer = app.tracer().unwrap();
+    let traces = tracer.traces();
+    assert_eq!(traces.len(), 2);
+
+    let execute = &traces[0];
+    assert_eq!(execute.kind, TraceKind::Execute);
+    assert_eq!(execute.sender, Some(owner.to_string()));
+    assert_eq!(execute.target, first.to_string());
+    assert!(matches!(execute.result, TraceResult::Ok { .. }));
+    assert!(!execute.events.is_empty());
+    assert_eq!(execute.children.len(), 2);
+
+    let echo_submsg = &execute.children[0];
+    assert_eq!(echo_submsg.kind, TraceKind::SubMsg);
+    assert_eq!(echo_submsg.sender, Some(first.to_string()));
+    assert_eq!(echo_submsg.target, second.to_string());
+    assert_eq!(echo_submsg.reply_id, Some(EXECUTE_REPLY_BASE_ID + 1));
+    assert_eq!(
+        echo_submsg.result,
+        TraceResult::Ok {
+            data: Some(Binary::from(b"second"))
+        }
+    );
+    let kinds: Vec<TraceKind> = echo_submsg.children.iter().map(|n| n.kind).collect();
+    assert_eq!(kinds, vec![TraceKind::Execute, TraceKind::Reply]);
+    assert_eq!(echo_submsg.children[1].target, first.to_string());
+
+    let bank_submsg = &execute.children[1];
+    assert_eq!(bank_submsg.target, "bank");
+    assert_eq!(bank_submsg.reply_id, Some(2));
+    assert!(matches!(
+        bank_submsg.children[0].result,
+        TraceResult::Err(_)
+    ));
+    assert_eq!(bank_submsg.children[1].kind, TraceKind::Reply);
+
+    let query = &traces[1];
+    assert_eq!(query.kind, TraceKind::Query);
+    assert_eq!(query.target, "bank");
+    assert!(query.children.is_empty());
+
+    // traces can be pretty-printed and exported as JSON
+    let printed = tracer.to_string();
+    assert_eq!(printed.lines().count(), 8);
+    assert!(printed.starts_with(&format!("execute owner -> {}", first)));
+    assert!(printed.contains(&format!("\n  submsg #2 {} -> bank", first)));
+    assert!(printed.contains("    execute"));
+    let exported: Vec<TraceNode> = from_json(tracer.to_json().unwrap()).unwrap();
+    assert_eq!(exported, traces);
+
+    tracer.clear();
+    assert!(app.tracer().unwrap().traces().is_empty());
+    app.disable_tracing();
+    assert!(app.tracer().is_none());
+}
+
+#[test]
+fn search_txs_and_blocks() {
+    let owner = Addr::unchecked("owner");
+    let mut app = App::default();
+    app.enable_block_history();
+    let code_id = app.store_code(blocker::contract());
+    let instantiate = WasmMsg::Instantiate {
+        admin: None,
+        code_id,
+        msg: to_json_binary(&Empty {}).unwrap(),
+        funds: vec![],
+        label: "Blocker".to_string(),
+    };
+    let res = app
+        .deliver_tx(Tx::new(owner.clone(), vec![instantiate.into()]))
+        .unwrap();
+    let contract = Addr::unchecked(
+        res.events
+            .iter()
+            .find(|e| e.ty == "instantiate")
+            .unwrap()
+            .attributes[0]
+            .value
+            .clone(),
+    );
+    let execute = || -> CosmosMsg {
+        WasmMsg::Execute {
+            contract_addr: contract.to_string(),
+            msg: to_json_binary(&Empty {}).unwrap(),
+            funds: vec![],
+        }
+        .into()
+    };
+    let height = app.block_info().height;
+    app.deliver_tx(Tx::new(owner.clone(), vec![execute()]))
+        .unwrap();
+    app.update_block(next_block);
+    app.deliver_tx(Tx::new(owner.clone(), vec![execute()]))
+        .unwrap();
+    let send = BankMsg::Send {
+        to_address: contract.to_string(),
+        amount: coins(10, "eth"),
+    };
+    let res = app
+        .deliver_tx(Tx::new(owner.clone(), vec![send.into()]))
+        .unwrap();
+    assert!(!res.is_ok());
+
+    // messages executed outside of transactions are not recorded
+    app.execute(owner.clone(), execute()).unwrap();
+
+    // delivered transactions are searched by events
+    let query = format!("wasm-tx._contract_address = '{}'", contract);
+    let txs = app.search_txs(&query).unwrap();
+    assert_eq!(txs.len(), 2);
+    assert_eq!(txs[0].height, height);
+    assert_eq!(txs[1].height, height + 1);
+    assert_eq!(app.query_tx(&txs[0].txhash).unwrap(), txs[0]);
+
+    let query = format!(
+        "execute._contract_address = '{}' AND wasm-tx.height > {}",
+        contract, height
+    );
+    let txs = app.search_txs(&query).unwrap();
+    assert_eq!(txs.len(), 1);
+    assert_eq!(txs[0].height, height + 1);
+    assert_eq!(txs[0].events[0].attributes.last().unwrap().key, "msg_index");
+
+    let txs = app.search_txs("instantiate.code_id EXISTS").unwrap();
+    assert_eq!(txs.len(), 1);
+    let txs = app
+        .search_txs(&format!(
+            "tx.height <= {} AND wasm-tx.time CONTAINS '.'",
+            height
+        ))
+        .unwrap();
+    assert_eq!(txs.len(), 1);
+
+    // failed transactions are recorded without events
+    let txs = app
+        .search_txs(&format!("tx.height = {}", height + 1))
+        .unwrap();
+    assert_eq!(txs.len(), 2);
+    assert!(!txs[1].is_ok());
+    assert!(txs[1].events.is_empty());
+    assert!(txs[1].raw_log.contains("Cannot Sub"));
+    let txs = app
+        .search_txs(&format!("tx.hash = '{}'", txs[1].txhash))
+        .unwrap();
+    assert_eq!(txs.len(), 1);
+
+    // invalid queries are rejected
+    assert_eq!(
+        app.search_txs("tx.height >").unwrap_err().to_string(),
+        "invalid query: expected value after 'tx.height'"
+    );
+    assert_eq!(
+        app.search_txs("tx.height = 1 OR tx.height = 2")
+            .unwrap_err()
+            .to_string(),
+        "invalid query: expected AND in 'tx.height = 1 OR tx.height = 2'"
+    );
+
+    // events emitted at the beginning and at the end of blocks are searched by block
+    app.register_block_hooks(&contract).unwrap();
+    app.update_block(next_block);
+    let blocks = app
+        .search_blocks("wasm-block_hook.hook = 'end_block'")
+        .unwrap();
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].height, height + 1);
+    assert_eq!(blocks[0].end_block_events.len(), 2);
+    let blocks = app
+        .search_blocks("wasm-block_hook.hook = 'begin_block'")
+        .unwrap();
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].height, height + 2);
+    assert_eq!(blocks[0], app.query_block_results(height + 2).unwrap());
+    assert_eq!(app.search_blocks("block.height >= 0").unwrap().len(), 3);
+    assert_eq!(
+        app.query_block_results(height + 3).unwrap_err().to_string(),
+        format!("block results not found: {}", height + 3)
+    );
+
+    // block results are recorded only when enabled
+    app.disable_block_history();
+    app.update_block(next_block);
+    assert_eq!(
+        app.search_blocks("block.height >= 0")
+            .unwrap_err()
+            .to_string(),
+        "block history is not enabled"
+    );
+    app.enable_block_history();
+    app.update_block(next_block);
+    assert_eq!(app.search_blocks("block.height >= 0").unwrap().len(), 2);
+}
+
+#[test]
+fn search_recorded_executions() {
+    let owner = Addr::unchecked("owner");
+    let mut app = App::default();
+    let code_id = app.store_code(blocker::contract());
+    let contract = app
+        .instantiate_contract(code_id, owner.clone(), &Empty {}, &[], "Blocker", None)
+        .unwrap();
+    let query = format!("wasm-tx._contract_address = '{}'", contract);
+
+    // executions are not recorded by default
+    app.execute_contract(owner.clone(), contract.clone(), &Empty {}, &[])
+        .unwrap();
+    assert!(app.search_txs(&query).unwrap().is_empty());
+
+    // executions are recorded when enabled
+    app.enable_execution_history();
+    let res = app
+        .execute_contract(owner.clone(), contract.clone(), &Empty {}, &[])
+        .unwrap();
+    let txs = app.search_txs(&query).unwrap();
+    assert_eq!(txs.len(), 1);
+    assert!(txs[0].is_ok());
+    assert_eq!(txs[0].height, app.block_info().height);
+    assert_eq!(txs[0].msg_responses, vec![res]);
+    assert_eq!(txs[0].events[0].attributes.last().unwrap().key, "msg_index");
+    assert_eq!(app.query_tx(&txs[0].txhash).unwrap(), txs[0]);
+
+    // executions of the same messages are recorded with different hashes
+    app.execute_contract(owner.clone(), contract.clone(), &Empty {}, &[])
+        .unwrap();
+    let txs = app.search_txs(&query).unwrap();
+    assert_eq!(txs.len(), 2);
+    assert_ne!(txs[0].txhash, txs[1].txhash);
+
+    // failed executions are not recorded
+    app.execute(
+        owner.clone(),
+        BankMsg::Send {
+            to_address: contract.to_string(),
+            amount: coins(10, "eth"),
+        }
+        .into(),
+    )
+    .unwrap_err();
+    assert_eq!(app.search_txs("tx.height >= 0").unwrap().len(), 2);
+
+    app.disable_execution_history();
+    app.execute_contract(owner.clone(), contract.clone(), &Empty {}, &[])
+        .unwrap();
+    assert_eq!(app.search_txs(&query).unwrap().len(), 2);
+}
+
+#[test]
+fn query_state_at_past_heights() {
+    let owner = Addr::unchecked("owner");
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, coins(100, "eth"))
+            .unwrap();
+    });
+    assert_eq!(
+        app.wrap_at_height(1).err().unwrap().to_string(),
+        "state history is not enabled"
+    );
+    app.enable_state_history();
+    let height = app.block_info().height;
+    let code_id = app.store_code(payout::contract());
+    let contract = app
+        .instantiate_contract(
+            code_id,
+            owner.clone(),
+            &payout::InstantiateMessage {
+                payout: coin(5, "eth"),
+            },
+            &coins(20, "eth"),
+            "Payout",
+            None,
+        )
+        .unwrap();
+    app.update_block(next_block);
+
+    app.wasm_sudo(contract.clone(), &payout::SudoMsg { set_count: 5 })
+        .unwrap();
+    app.execute_contract(owner.clone(), contract.clone(), &Empty {}, &[])
+        .unwrap();
+    app.update_block(next_block);
+
+    // changes in the current block are not committed yet
+    app.wasm_sudo(contract.clone(), &payout::SudoMsg { set_count: 9 })
+        .unwrap();
+
+    let count = |querier: QuerierWrapper| -> u32 {
+        let res: payout::CountResponse = querier
+            .query_wasm_smart(&contract, &payout::QueryMsg::Count {})
+            .unwrap();
+        res.count
+    };
+
+    let past = app.wrap_at_height(height).unwrap();
+    assert_eq!(past.block_info().height, height);
+    assert_eq!(count(past.wrap()), 1);
+    assert_eq!(
+        past.wrap().query_balance(&owner, "eth").unwrap(),
+        coin(80, "eth")
+    );
+    let raw = past.wrap().query_wasm_raw(&contract, b"count").unwrap();
+    assert_eq!(raw, Some(to_json_vec(&1u32).unwrap()));
+
+    let past = app.wrap_at_height(height + 1).unwrap();
+    assert_eq!(count(past.wrap()), 5);
+    assert_eq!(
+        past.wrap().query_balance(&owner, "eth").unwrap(),
+        coin(85, "eth")
+    );
+    assert_eq!(count(app.wrap()), 9);
+
+    assert_eq!(
+        app.wrap_at_height(height + 2).err().unwrap().to_string(),
+        format!(
+            "height {} is not committed yet, current height is {}",
+            height + 2,
+            height + 2
+        )
+    );
+    assert_eq!(
+        app.wrap_at_height(height - 1).err().unwrap().to_string(),
+        format!("state at height {} was not recorded", height - 1)
+    );
+    app.disable_state_history();
+    assert!(app.wrap_at_height(height).is_err());
+}
+
+#[test]
+fn state_history_records_changes_of_every_block() {
+    let owner = Addr::unchecked("owner");
+    let mut app = App::default();
+    let code_id = app.store_code(payout::contract());
+    let contract = app
+        .instantiate_contract(
+            code_id,
+            owner.clone(),
+            &payout::InstantiateMessage {
+                payout: coin(5, "eth"),
+            },
+            &[],
+            "Payout",
+            None,
+        )
+        .unwrap();
+    app.enable_state_history();
+    let height = app.block_info().height;
+    let mut contract_namespace = CONTRACT_NAMESPACE_PREFIX.to_vec();
+    contract_namespace.extend_from_slice(contract.as_bytes());
+
+    // changes made directly to the storage are recorded
+    app.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, coins(100, "eth"))
+            .unwrap();
+    });
+    PrefixedStorage::multilevel(app.storage_mut(), &[NAMESPACE_WASM, &contract_namespace])
+        .set(b"direct", b"1");
+    app.update_block(next_block);
+
+    // changes made by messages are recorded
+    app.send_tokens(owner.clone(), contract.clone(), &coins(30, "eth"))
+        .unwrap();
+    app.update_block(next_block);
+
+    // removed keys are recorded
+    PrefixedStorage::multilevel(app.storage_mut(), &[NAMESPACE_WASM, &contract_namespace])
+        .remove(b"direct");
+    app.update_block(next_block);
+
+    let balance = |height: u64, account: &Addr| -> u128 {
+        app.wrap_at_height(height)
+            .unwrap()
+            .wrap()
+            .query_balance(account, "eth")
+            .unwrap()
+            .amount
+            .u128()
+    };
+    let raw = |height: u64| -> Option<Vec<u8>> {
+        app.wrap_at_height(height)
+            .unwrap()
+            .wrap()
+            .query_wasm_raw(&contract, b"direct")
+            .unwrap()
+    };
+    assert_eq!(balance(height, &owner), 100);
+    assert_eq!(balance(height, &contract), 0);
+    assert_eq!(balance(height + 1, &owner), 70);
+    assert_eq!(balance(height + 1, &contract), 30);
+    assert_eq!(balance(height + 2, &owner), 70);
+    assert_eq!(raw(height), Some(b"1".to_vec()));
+    assert_eq!(raw(height + 1), Some(b"1".to_vec()));
+    assert_eq!(raw(height + 2), None);
+}
+
+#[test]
+fn invariants_are_checked() {
+    let delegator = Addr::unchecked("delegator");
+    let validator = Addr::unchecked("validator");
+    let alice = Addr::unchecked("alice");
+    let mut app = App::new(|router, api, storage| {
+        router
+            .bank
+            .init_balance(storage, &delegator, coins(100, "TOKEN"))
+            .unwrap();
+        router
+            .staking
+            .add_validator(
+                api,
+                storage,
+                &mock_env().block,
+                cosmwasm_std::Validator {
+                    address: validator.to_string(),
+                    commission: Decimal::percent(10),
+                    max_commission: Decimal::percent(20),
+                    max_change_rate: Decimal::percent(1),
+                },
+            )
+            .unwrap();
+    });
+    app.add_builtin_invariants();
+
+    // built-in invariants hold while delegating, undelegating and slashing
+    app.execute(
+        delegator.clone(),
+        StakingMsg::Delegate {
+            validator: validator.to_string(),
+            amount: coin(60, "TOKEN"),
+        }
+        .into(),
+    )
+    .unwrap();
+    app.execute(
+        delegator.clone(),
+        StakingMsg::Undelegate {
+            validator: validator.to_string(),
+            amount: coin(20, "TOKEN"),
+        }
+        .into(),
+    )
+    .unwrap();
+    app.sudo(SudoMsg::Staking(StakingSudo::Slash {
+        validator: validator.to_string(),
+        percentage: Decimal::percent(50),
+    }))
+    .unwrap();
+
+    // tokens sent to the staking module are neither bonded, unbonding nor slashed
+    let mint = BankSudo::Mint {
+        to_address: "staking_module".to_string(),
+        amount: coins(10, "TOKEN"),
+    };
+    assert_eq!(
+        app.sudo(mint.clone().into()).unwrap_err().to_string(),
+        format!(
+            "invariant 'staking module balance' violated after sudo {:?}: staking module holds 70TOKEN, but 60TOKEN is bonded, unbonding or slashed",
+            SudoMsg::<Empty>::Bank(mint)
+        )
+    );
+    assert_eq!(
+        app.wrap()
+            .query_balance("staking_module", "TOKEN")
+            .unwrap()
+            .amount
+            .u128(),
+        60
+    );
+
+    // balances written without the bank module do not match the tracked supply
+    let mut app = App::default();
+    app.add_builtin_invariants();
+    app.init_modules(|_, _, storage| {
+        let balances: Map<&Addr, NativeBalance> = Map::new("balances");
+        balances
+            .save(
+                &mut prefixed(storage, b"bank"),
+                &alice,
+                &NativeBalance(coins(5, "eth")),
+            )
+            .unwrap();
+    });
+    let mint = BankSudo::Mint {
+        to_address: delegator.to_string(),
+        amount: coins(1, "eth"),
+    };
+    assert_eq!(
+        app.sudo(mint.clone().into()).unwrap_err().to_string(),
+        format!(
+            "invariant 'bank supply' violated after sudo {:?}: supply of eth is 1, but balances sum up to 6",
+            SudoMsg::<Empty>::Bank(mint)
+        )
+    );
+
+    // custom invariants are checked after every message
+    let mut app = App::default();
+    app.add_invariant("contract code ids", |app| {
+        app.read_module(|router, _, storage| router.wasm.check_code_ids(storage))
+    });
+    app.add_invariant("alice holds at most 50 tokens", move |app| {
+        let balance = app.wrap().query_balance("alice", "TOKEN")?;
+        if balance.amount.u128() > 50 {
+            bail!("alice holds {}", balance);
+        }
+        Ok(())
+    });
+    app.sudo(
+        BankSudo::Mint {
+            to_address: alice.to_string(),
+            amount: coins(50, "TOKEN"),
+        }
+        .into(),
+    )
+    .unwrap();
+    let send = BankMsg::Send {
+        to_address: alice.to_string(),
+        amount: coins(1, "TOKEN"),
+    };
+    app.sudo(
+        BankSudo::Mint {
+            to_address: delegator.to_string(),
+            amount: coins(1, "TOKEN"),
+        }
+        .into(),
+    )
+    .unwrap();
+    let err = app
+        .execute(delegator.clone(), send.clone().into())
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "invariant 'alice holds at most 50 tokens' violated after executing {:?}: alice holds 51TOKEN",
+            vec![CosmosMsg::<Empty>::from(send)]
+        )
+    );
+    // state changes are reverted, and the application keeps working
+    assert_eq!(get_balance(&app, &alice), coins(50, "TOKEN"));
+    assert_eq!(get_balance(&app, &delegator), coins(1, "TOKEN"));
+    app.execute(
+        delegator.clone(),
+        BankMsg::Send {
+            to_address: "bob".to_string(),
+            amount: coins(1, "TOKEN"),
+        }
+        .into(),
+    )
+    .unwrap();
+    assert_eq!(get_balance(&app, &delegator), vec![]);
+
+    // contracts must refer to stored codes
+    let contract = Addr::unchecked("contract");
+    app.init_modules(|router, _, storage| {
+        router.wasm.save_contract(
+            storage,
+            &contract,
+            &ContractData {
+                code_id: 7,
+                creator: alice.clone(),
+                admin: None,
+                label: "Orphan".to_string(),
+                created: 1,
+            },
+        )
+    })
+    .unwrap();
+    let err = app.wasm_sudo(contract, &Empty {}).unwrap_err();
+    assert_eq!(err.to_string(), "code id 7: no such code");
+    let mint = BankSudo::Mint {
+        to_address: delegator.to_string(),
+        amount: coins(1, "TOKEN"),
+    };
+    assert_eq!(
+        app.sudo(mint.clone().into()).unwrap_err().to_string(),
+        format!(
+            "invariant 'contract code ids' violated after sudo {:?}: contract contract: code id 7: no such code",
+            SudoMsg::<Empty>::Bank(mint)
+        )
+    );
+}
+
+#[test]
+fn custom_sudo_message_is_routed_to_custom_module() {
+    let mut app = App::default();
+
+    // the default custom module rejects all privileged actions
+    let err = app.sudo(SudoMsg::Custom(Empty {})).unwrap_err();
+    assert_eq!(
+        &Error::unexpected_sudo_msg(Empty {}),
+        err.downcast_ref::<Error>().unwrap()
+    );
+
+    // custom privileged actions can not be routed without knowing their type
+    let err = app
+        .read_module(|router, api, storage| {
+            let mut storage = StorageTransaction::new(storage);
+            router.sudo(
+                api,
+                &mut storage,
+                &app.block_info(),
+                SudoMsg::Custom(Empty {}),
+            )
+        })
+        .unwrap_err();
+    assert_eq!(
+        &Error::unsupported_sudo_message(SudoMsg::<Empty>::Custom(Empty {})),
+        err.downcast_ref::<Error>().unwrap()
+    );
+}
+
+#[test]
+fn errors_are_downcastable_to_typed_variants() {
+    let owner = Addr::unchecked("owner");
+    let validator = Addr::unchecked("validator");
+    let mut app = custom_app::<CustomHelperMsg, Empty, _>(|router, api, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, coins(100, "eth"))
+            .unwrap();
+        router
+            .staking
+            .add_validator(
+                api,
+                storage,
+                &mock_env().block,
+                cosmwasm_std::Validator {
+                    address: validator.to_string(),
+                    commission: Decimal::percent(10),
+                    max_commission: Decimal::percent(20),
+                    max_change_rate: Decimal::percent(1),
+                },
+            )
+            .unwrap();
+    });
+    let reflect_id = app.store_code(reflect::contract());
+    let reflect_addr = app
+        .instantiate_contract(
+            reflect_id,
+            owner.clone(),
+            &Empty {},
+            &coins(40, "eth"),
+            "Reflect",
+            Some(owner.to_string()),
+        )
+        .unwrap();
+
+    // insufficient funds of a contract, reported through the contract execution
+    let msgs = reflect::Message {
+        messages: vec![SubMsg::new(BankMsg::Send {
+            to_address: owner.to_string(),
+            amount: coins(41, "eth"),
+        })],
+    };
+    let err = app
+        .execute_contract(owner.clone(), reflect_addr.clone(), &msgs, &[])
+        .unwrap_err();
+    assert_eq!(
+        Some(&Error::insufficient_funds(
+            reflect_addr.clone(),
+            coins(41, "eth")
+        )),
+        err.downcast_ref::<Error>()
+    );
+    assert!(matches!(
+        err.downcast_ref::<StdError>(),
+        Some(StdError::Overflow { .. })
+    ));
+
+    // zero amount
+    let err = app
+        .send_tokens(owner.clone(), reflect_addr.clone(), &coins(0, "eth"))
+        .unwrap_err();
+    assert_eq!(Some(&Error::zero_amount()), err.downcast_ref::<Error>());
+
+    // unknown validator and wrong denom
+    let err = app
+        .execute(
+            owner.clone(),
+            StakingMsg::Delegate {
+                validator: "unknown".to_string(),
+                amount: coin(10, "TOKEN"),
+            }
+            .into(),
+        )
+        .unwrap_err();
+    assert_eq!(
+        Some(&Error::unknown_validator("unknown")),
+        err.downcast_ref::<Error>()
+    );
+    let err = app
+        .execute(
+            owner.clone(),
+            StakingMsg::Delegate {
+                validator: validator.to_string(),
+                amount: coin(10, "eth"),
+            }
+            .into(),
+        )
+        .unwrap_err();
+    assert_eq!(
+        Some(&Error::wrong_denom("eth", "TOKEN")),
+        err.downcast_ref::<Error>()
+    );
+
+    // unauthorized admin, unknown contract and missing label
+    let err = app
+        .execute(
+            Addr::unchecked("random"),
+            WasmMsg::UpdateAdmin {
+                contract_addr: reflect_addr.to_string(),
+                admin: "random".to_string(),
+            }
+            .into(),
+        )
+        .unwrap_err();
+    assert_eq!(
+        Some(&Error::unauthorized_admin(
+            "update the contract admin",
+            Some(owner.clone())
+        )),
+        err.downcast_ref::<Error>()
+    );
+    let err = app
+        .execute_contract(owner.clone(), Addr::unchecked("unknown"), &Empty {}, &[])
+        .unwrap_err();
+    assert_eq!(
+        Some(&Error::unknown_contract("unknown")),
+        err.downcast_ref::<Error>()
+    );
+    let err = app
+        .instantiate_contract(reflect_id, owner.clone(), &Empty {}, &[], "", None)
+        .unwrap_err();
+    assert_eq!(Some(&Error::label_missing()), err.downcast_ref::<Error>());
+
+    // message routed to a module not accepting it
+    let err = app
+        .execute(
+            owner.clone(),
+            CosmosMsg::Custom(CustomHelperMsg::SetAge { age: 1 }),
+        )
+        .unwrap_err();
+    assert_eq!(
+        Some(&Error::unexpected_exec_msg(
+            CustomHelperMsg::SetAge { age: 1 },
+            owner
+        )),
+        err.downcast_ref::<Error>()
+    );
+}
+
+#[test]
+fn errors_passed_to_replies_can_be_redacted() {
+    let owner = Addr::unchecked("owner");
+    let mut app = AppBuilder::new_custom()
+        .with_wasm(WasmKeeper::new().with_redacted_errors(true))
+        .build(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &owner, coins(100, "eth"))
+                .unwrap();
+        });
+    app.enable_tracing();
+    let reflect_id = app.store_code(reflect::contract());
+    let error_id = app.store_code(error::contract(true));
+    let reflect_addr = app
+        .instantiate_contract(reflect_id, owner.clone(), &Empty {}, &[], "Reflect", None)
+        .unwrap();
+    let error_addr = app
+        .instantiate_contract(error_id, owner.clone(), &Empty {}, &[], "Error", None)
+        .unwrap();
+
+    let msgs = reflect::Message {
+        messages: vec![
+            SubMsg::reply_on_error(
+                BankMsg::Send {
+                    to_address: owner.to_string(),
+                    amount: coins(1, "eth"),
+                },
+                1,
+            ),
+            SubMsg::reply_on_error(
+                WasmMsg::Execute {
+                    contract_addr: error_addr.to_string(),
+                    msg: to_json_binary(&Empty {}).unwrap(),
+                    funds: vec![],
+                },
+                2,
+            ),
+            SubMsg::reply_on_error(CosmosMsg::Custom(CustomHelperMsg::SetAge { age: 1 }), 3),
+        ],
+    };
+    app.execute_contract(owner, reflect_addr.clone(), &msgs, &[])
+        .unwrap();
+
+    let reply_error = |id: u64| {
+        let reply: Reply = app
+            .wrap()
+            .query_wasm_smart(&reflect_addr, &reflect::QueryMsg::Reply { id })
+            .unwrap();
+        reply.result.unwrap_err()
+    };
+    assert_eq!("codespace: sdk, code: 5", reply_error(1));
+    assert_eq!("codespace: wasm, code: 5", reply_error(2));
+    assert_eq!("codespace: wasm, code: 20", reply_error(3));
+
+    // the full error is still available in the execution trace
+    assert!(app.tracer().unwrap().to_string().contains("Handle failed"));
+}
+
+#[test]
+fn slashing_at_height_fails_when_staking_module_cannot_burn_tokens() {
+    let delegator = Addr::unchecked("delegator");
+    let validator = Addr::unchecked("validator");
+    let mut app = App::new(|router, api, storage| {
+        router
+            .bank
+            .init_balance(storage, &delegator, coins(100, "TOKEN"))
+            .unwrap();
+        router
+            .staking
+            .add_validator(
+                api,
+                storage,
+                &mock_env().block,
+                cosmwasm_std::Validator {
+                    address: validator.to_string(),
+                    commission: Decimal::percent(10),
+                    max_commission: Decimal::percent(20),
+                    max_change_rate: Decimal::percent(1),
+                },
+            )
+            .unwrap();
+    });
+    app.execute(
+        delegator.clone(),
+        StakingMsg::Delegate {
+            validator: validator.to_string(),
+            amount: coin(100, "TOKEN"),
+        }
+        .into(),
+    )
+    .unwrap();
+
+    // the staking module does not hold the bonded tokens anymore
+    app.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &Addr::unchecked("staking_module"), vec![])
+            .unwrap();
+    });
+    let err = app
+        .sudo(SudoMsg::Staking(StakingSudo::SlashAtHeight {
+            validator: validator.to_string(),
+            percentage: Decimal::percent(50),
+            infraction_height: app.block_info().height,
+            power: Uint128::new(100),
+        }))
+        .unwrap_err();
+    assert_eq!(
+        "staking module staking_module cannot burn slashed 50TOKEN",
+        err.to_string()
+    );
+    assert!(matches!(
+        err.downcast_ref::<Error>(),
+        Some(Error::InsufficientFunds { .. })
+    ));
+
+    // the stake is not slashed
+    let delegation = app
+        .wrap()
+        .query_delegation(&delegator, &validator)
+        .unwrap()
+        .unwrap();
+    assert_eq!(coin(100, "TOKEN"), delegation.amount);
+
+    // plain slashing does not burn tokens
+    app.sudo(SudoMsg::Staking(StakingSudo::Slash {
+        validator: validator.to_string(),
+        percentage: Decimal::percent(50),
+    }))
+    .unwrap();
+    let delegation = app
+        .wrap()
+        .query_delegation(&delegator, &validator)
+        .unwrap()
+        .unwrap();
+    assert_eq!(coin(50, "TOKEN"), delegation.amount);
+}
+
+#[test]
+fn custom_message_of_contract_returning_empty_returns_typed_error() {
+    fn instantiate(_: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+    fn execute(_: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+        Ok(Response::new().add_message(CosmosMsg::Custom(Empty {})))
+    }
+    fn query(_: Deps, _: Env, _: Empty) -> StdResult<Binary> {
+        Ok(Binary::default())
+    }
+
+    let owner = Addr::unchecked("owner");
+    let mut app = BasicAppBuilder::<CustomHelperMsg, Empty>::new_custom().build(no_init);
+    let code_id = app.store_code(Box::new(ContractWrapper::new_with_empty(
+        execute,
+        instantiate,
+        query,
+    )));
+    let contract = app
+        .instantiate_contract(code_id, owner.clone(), &Empty {}, &[], "Empty", None)
+        .unwrap();
+
+    let err = app
+        .execute_contract(owner, contract, &Empty {}, &[])
+        .unwrap_err();
+    assert_eq!(
+        &Error::unsupported_message(CosmosMsg::<Empty>::Custom(Empty {})),
+        err.downcast_ref::<Error>().unwrap()
+    );
+}
+
+#[test]
+fn staking_hooks_are_called() {
+    let delegator = Addr::unchecked("delegator");
+    let validator = Addr::unchecked("validator");
+
+    let mut app = App::new(|router, api, storage| {
+        router
+            .bank
+            .init_balance(storage, &delegator, coins(100, "TOKEN"))
+            .unwrap();
+        router
+            .staking
+            .add_validator(
+                api,
+                storage,
+                &mock_env().block,
+                cosmwasm_std::Validator {
+                    address: validator.to_string(),
+                    commission: Decimal::percent(10),
+                    max_commission: Decimal::percent(20),
+                    max_change_rate: Decimal::percent(1),
+                },
+            )
+            .unwrap();
+    });
+
+    let hooks_id = app.store_code(hooks::contract());
+    let hooks_addr = app
+        .instantiate_contract(hooks_id, delegator.clone(), &Empty {}, &[], "Hooks", None)
+        .unwrap();
+    app.sudo(SudoMsg::Staking(StakingSudo::AddHook {
+        contract: hooks_addr.to_string(),
+    }))
+    .unwrap();
+    let err = app
+        .sudo(SudoMsg::Staking(StakingSudo::AddHook {
+            contract: hooks_addr.to_string(),
+        }))
+        .unwrap_err();
+    assert_eq!(
+        &Error::staking_hook_already_registered(hooks_addr.clone()),
+        err.downcast_ref::<Error>().unwrap()
+    );
+
+    app.execute(
+        delegator.clone(),
+        StakingMsg::Delegate {
+            validator: validator.to_string(),
+            amount: coin(100, "TOKEN"),
+        }
+        .into(),
+    )
+    .unwrap();
+    app.sudo(SudoMsg::Staking(StakingSudo::Slash {
+        validator: validator.to_string(),
+        percentage: Decimal::percent(50),
+    }))
+    .unwrap();
+    app.sudo(SudoMsg::Staking(StakingSudo::Jail {
+        validator: validator.to_string(),
+    }))
+    .unwrap();
+    app.execute(
+        delegator.clone(),
+        StakingMsg::Undelegate {
+            validator: validator.to_string(),
+            amount: coin(50, "TOKEN"),
+        }
+        .into(),
+    )
+    .unwrap();
+
+    let hooks: Vec<StakingHookMsg> = app.wrap().query_wasm_smart(&hooks_addr, &Empty {}).unwrap();
+    assert_eq!(
+        hooks,
+        vec![
+            StakingHookMsg::AfterDelegationModified {
+                delegator: delegator.to_string(),
+                validator: validator.to_string(),
+            },
+            StakingHookMsg::AfterValidatorBonded {
+                validator: validator.to_string(),
+            },
+            StakingHookMsg::AfterValidatorSlashed {
+                validator: validator.to_string(),
+                fraction: Decimal::percent(50),
+            },
+            StakingHookMsg::AfterValidatorBeginUnbonding {
+                validator: validator.to_string(),
+            },
+            StakingHookMsg::AfterDelegationRemoved {
+                delegator: delegator.to_string(),
+                validator: validator.to_string(),
+            },
+        ]
+    );
+
+    // removed hooks are not called anymore
+    app.sudo(SudoMsg::Staking(StakingSudo::RemoveHook {
+        contract: hooks_addr.to_string(),
+    }))
+    .unwrap();
+    app.sudo(SudoMsg::Staking(StakingSudo::Unjail {
+        validator: validator.to_string(),
+    }))
+    .unwrap();
+    let hooks: Vec<StakingHookMsg> = app.wrap().query_wasm_smart(&hooks_addr, &Empty {}).unwrap();
+    assert_eq!(hooks.len(), 5);
+    let err = app
+        .sudo(SudoMsg::Staking(StakingSudo::RemoveHook {
+            contract: hooks_addr.to_string(),
+        }))
+        .unwrap_err();
+    assert_eq!(
+        &Error::staking_hook_not_registered(hooks_addr),
+        err.downcast_ref::<Error>().unwrap()
+    );
+}
+
 #[test]
 fn reflect_sub_message_reply_works() {
     // set personal balance
@@ -0,0 +1,251 @@
+use crate::error::{bail, AnyResult};
+use crate::test_helpers::{payout, CustomHelperMsg};
+use crate::{
+    next_block, AppBuilder, AppResponse, BankKeeper, BankSudo, BasicAppBuilder, CosmosRouter,
+    DynApp, Executor, Module, WasmKeeper,
+};
+use cosmwasm_std::testing::{mock_env, MockApi};
+use cosmwasm_std::{
+    coin, coins, Addr, Api, BankMsg, Binary, BlockInfo, CosmosMsg, CustomMsg, CustomQuery, Decimal,
+    Empty, Querier, StakingMsg, Storage, Validator,
+};
+use serde::de::DeserializeOwned;
+
+/// Fixture with a nameable application type, storing and instantiating the payout contract.
+fn instantiate_payout(app: &mut DynApp, owner: &Addr) -> Addr {
+    let code_id = app.store_code(payout::contract());
+    let msg = payout::InstantiateMessage {
+        payout: coin(5, "eth"),
+    };
+    app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &msg,
+        &coins(20, "eth"),
+        "Payout",
+        None,
+    )
+    .unwrap()
+}
+
+fn balance<ExecC>(app: &DynApp<ExecC>, address: &Addr, denom: &str) -> u128
+where
+    ExecC: CustomMsg + DeserializeOwned + 'static,
+{
+    app.wrap()
+        .query_balance(address, denom)
+        .unwrap()
+        .amount
+        .u128()
+}
+
+#[test]
+fn dyn_app_works_like_app() {
+    let owner = Addr::unchecked("owner");
+    let validator = Addr::unchecked("validator");
+    let mut app = AppBuilder::default().build_dyn(|router, api, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, vec![coin(100, "eth"), coin(100, "TOKEN")])
+            .unwrap();
+        router
+            .staking
+            .add_validator(
+                api,
+                storage,
+                &mock_env().block,
+                Validator {
+                    address: validator.to_string(),
+                    commission: Decimal::percent(10),
+                    max_commission: Decimal::percent(20),
+                    max_change_rate: Decimal::percent(1),
+                },
+            )
+            .unwrap();
+    });
+
+    // contracts are stored, instantiated and executed
+    let contract = instantiate_payout(&mut app, &owner);
+    assert_eq!(80, balance(&app, &owner, "eth"));
+    app.execute_contract(owner.clone(), contract.clone(), &Empty {}, &[])
+        .unwrap();
+    assert_eq!(85, balance(&app, &owner, "eth"));
+    assert_eq!(15, balance(&app, &contract, "eth"));
+
+    // snapshots restore the stored codes along with the storage
+    let snapshot = app.snapshot();
+    instantiate_payout(&mut app, &owner);
+    assert_eq!(65, balance(&app, &owner, "eth"));
+    app.restore(&snapshot);
+    assert_eq!(85, balance(&app, &owner, "eth"));
+
+    // staking queue is processed by the boxed staking module
+    app.execute(
+        owner.clone(),
+        StakingMsg::Delegate {
+            validator: validator.to_string(),
+            amount: coin(60, "TOKEN"),
+        }
+        .into(),
+    )
+    .unwrap();
+    app.execute(
+        owner.clone(),
+        StakingMsg::Undelegate {
+            validator: validator.to_string(),
+            amount: coin(60, "TOKEN"),
+        }
+        .into(),
+    )
+    .unwrap();
+    assert_eq!(40, balance(&app, &owner, "TOKEN"));
+    app.update_block(|block| {
+        block.time = block.time.plus_days(30);
+        block.height += 1;
+    });
+    app.update_block(next_block);
+    assert_eq!(100, balance(&app, &owner, "TOKEN"));
+
+    // concrete modules, API and storage are accessible by downcasting
+    assert!(app.router().bank.downcast_ref::<BankKeeper>().is_some());
+    assert!(app.router().bank.downcast_ref::<MockApi>().is_none());
+    let creator = app
+        .api()
+        .downcast_ref::<MockApi>()
+        .unwrap()
+        .addr_make("creator");
+    assert_eq!(creator, app.api().addr_validate(creator.as_str()).unwrap());
+    assert!(app.read_module(|router, _, _| {
+        router
+            .wasm
+            .downcast_ref::<WasmKeeper<Empty, Empty>>()
+            .is_some()
+    }));
+}
+
+/// Custom module paying out the age in tokens, by sending a bank message through the router.
+struct AgePayout;
+
+impl Module for AgePayout {
+    type ExecT = CustomHelperMsg;
+    type QueryT = Empty;
+    type SudoT = Empty;
+
+    fn execute<ExecC, QueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        sender: Addr,
+        msg: Self::ExecT,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: CustomMsg + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        let CustomHelperMsg::SetAge { age } = msg else {
+            bail!("unexpected message: {:?}", msg);
+        };
+        let msg = BankMsg::Send {
+            to_address: "treasury".to_string(),
+            amount: coins(age.into(), "eth"),
+        };
+        router.execute(api, storage, block, sender, msg.into())
+    }
+
+    fn query(
+        &self,
+        _api: &dyn Api,
+        _storage: &dyn Storage,
+        _querier: &dyn Querier,
+        _block: &BlockInfo,
+        _request: Self::QueryT,
+    ) -> AnyResult<Binary> {
+        bail!("no queries")
+    }
+
+    fn sudo<ExecC, QueryC>(
+        &self,
+        _api: &dyn Api,
+        _storage: &mut dyn Storage,
+        _router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        _block: &BlockInfo,
+        _msg: Self::SudoT,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: CustomMsg + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        bail!("no sudo")
+    }
+}
+
+#[test]
+fn boxed_custom_module_uses_router() {
+    let owner = Addr::unchecked("owner");
+    let mut app: DynApp<CustomHelperMsg> = BasicAppBuilder::<CustomHelperMsg, Empty>::new_custom()
+        .with_custom(AgePayout)
+        .build_dyn(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &owner, coins(100, "eth"))
+                .unwrap();
+        });
+
+    app.execute(
+        owner.clone(),
+        CosmosMsg::Custom(CustomHelperMsg::SetAge { age: 30 }),
+    )
+    .unwrap();
+    assert_eq!(70, balance(&app, &owner, "eth"));
+    assert_eq!(30, balance(&app, &Addr::unchecked("treasury"), "eth"));
+
+    let err = app
+        .execute(
+            owner,
+            CosmosMsg::Custom(CustomHelperMsg::SetName {
+                name: "John".to_string(),
+            }),
+        )
+        .unwrap_err();
+    assert!(err.to_string().starts_with("unexpected message"));
+}
+
+#[test]
+fn builtin_invariants_check_boxed_keepers() {
+    let owner = Addr::unchecked("owner");
+    let mut app = AppBuilder::default().build_dyn(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &owner, coins(100, "eth"))
+            .unwrap();
+    });
+    app.add_builtin_invariants();
+    instantiate_payout(&mut app, &owner);
+
+    // tokens sent to the staking module are neither bonded, unbonding nor slashed
+    let mint = BankSudo::Mint {
+        to_address: "staking_module".to_string(),
+        amount: coins(10, "TOKEN"),
+    };
+    assert!(app
+        .sudo(mint.into())
+        .unwrap_err()
+        .to_string()
+        .starts_with("invariant 'staking module balance' violated after sudo"));
+    assert_eq!(
+        balance(&app, &Addr::unchecked("staking_module"), "TOKEN"),
+        0
+    );
+}
+
+#[test]
+fn applications_with_invariants_are_not_boxed() {
+    let mut app = BasicAppBuilder::<Empty, Empty>::new().build(|_, _, _| {});
+    app.add_invariant("always", |_| Ok(()));
+    assert_eq!(
+        app.into_dyn().err().unwrap().to_string(),
+        "invariant 'always' is registered for the concrete application and can not be boxed"
+    );
+}
@@ -1,5 +1,8 @@
 use crate::error::Error;
-use cosmwasm_std::{WasmMsg, WasmQuery};
+use cosmwasm_std::{
+    coin, coins, Addr, BankMsg, BankQuery, CosmosMsg, DistributionMsg, Empty, StakingMsg,
+    StakingQuery, WasmMsg, WasmQuery,
+};
 
 #[test]
 fn instantiating_error_should_work() {
@@ -35,6 +38,142 @@ fn instantiating_error_should_work() {
         })
         .to_string()
     );
+    assert_eq!(
+        r#"Unsupported bank query: AllBalances { address: "owner" }"#,
+        Error::unsupported_bank_query(BankQuery::AllBalances {
+            address: "owner".to_string()
+        })
+        .to_string()
+    );
+    assert_eq!(
+        r#"Unsupported bank message: Burn { amount: [Coin { 7 "eth" }] }"#,
+        Error::unsupported_bank_message(BankMsg::Burn {
+            amount: coins(7, "eth")
+        })
+        .to_string()
+    );
+    assert_eq!(
+        r#"Unsupported staking query: AllDelegations { delegator: "owner" }"#,
+        Error::unsupported_staking_query(StakingQuery::AllDelegations {
+            delegator: "owner".to_string()
+        })
+        .to_string()
+    );
+    assert_eq!(
+        r#"Unsupported staking message: Delegate { validator: "validator", amount: Coin { 7 "eth" } }"#,
+        Error::unsupported_staking_message(StakingMsg::Delegate {
+            validator: "validator".to_string(),
+            amount: coin(7, "eth")
+        })
+        .to_string()
+    );
+    assert_eq!(
+        r#"Unsupported distribution message: SetWithdrawAddress { address: "owner" }"#,
+        Error::unsupported_distribution_message(DistributionMsg::SetWithdrawAddress {
+            address: "owner".to_string()
+        })
+        .to_string()
+    );
+    assert_eq!(
+        r#"Unsupported query: {"grpc":{}}"#,
+        Error::unsupported_query(r#"{"grpc":{}}"#).to_string()
+    );
+    assert_eq!(
+        "Unsupported message: Custom(Empty)",
+        Error::unsupported_message(CosmosMsg::<Empty>::Custom(Empty {})).to_string()
+    );
+    assert_eq!(
+        "Unsupported sudo message: Empty",
+        Error::unsupported_sudo_message(Empty {}).to_string()
+    );
+    assert_eq!(
+        r#"Unexpected exec msg Empty from Addr("sender")"#,
+        Error::unexpected_exec_msg(Empty {}, Addr::unchecked("sender")).to_string()
+    );
+    assert_eq!(
+        "Unexpected custom query Empty",
+        Error::unexpected_query(Empty {}).to_string()
+    );
+    assert_eq!(
+        "Unexpected sudo msg Empty",
+        Error::unexpected_sudo_msg(Empty {}).to_string()
+    );
+    assert_eq!(
+        r#"Insufficient funds: owner cannot send [Coin { 7 "eth" }]"#,
+        Error::insufficient_funds(Addr::unchecked("owner"), coins(7, "eth")).to_string()
+    );
+    assert_eq!(
+        "Cannot transfer empty coins amount",
+        Error::zero_amount().to_string()
+    );
+    assert_eq!(
+        "validator does not exist",
+        Error::unknown_validator("validator").to_string()
+    );
+    assert_eq!(
+        "no delegation for (address, validator) tuple",
+        Error::no_delegation().to_string()
+    );
+    assert_eq!(
+        "invalid shares amount",
+        Error::invalid_shares_amount().to_string()
+    );
+    assert_eq!(
+        "invalid delegation amount",
+        Error::invalid_delegation_amount().to_string()
+    );
+    assert_eq!(
+        "cannot jail already jailed validator",
+        Error::validator_already_jailed().to_string()
+    );
+    assert_eq!(
+        "validator still jailed; cannot be unjailed",
+        Error::validator_still_jailed().to_string()
+    );
+    assert_eq!(
+        "validator not jailed; cannot be unjailed",
+        Error::validator_not_jailed().to_string()
+    );
+    assert_eq!(
+        "cannot redelegate to the same validator",
+        Error::self_redelegation().to_string()
+    );
+    assert_eq!(
+        "redelegation to this validator already in progress; first redelegation to this validator must complete before next redelegation",
+        Error::transitive_redelegation().to_string()
+    );
+    assert_eq!(
+        "too many redelegation entries for (delegator, src-validator, dst-validator) tuple",
+        Error::max_redelegation_entries().to_string()
+    );
+    assert_eq!(
+        "too many unbonding delegation entries for (delegator, validator) tuple",
+        Error::max_unbonding_entries().to_string()
+    );
+    assert_eq!(
+        "staking hook already registered: hooks",
+        Error::staking_hook_already_registered(Addr::unchecked("hooks")).to_string()
+    );
+    assert_eq!(
+        "staking hook not registered: hooks",
+        Error::staking_hook_not_registered(Addr::unchecked("hooks")).to_string()
+    );
+    assert_eq!(
+        "cannot delegate coins of denominator FAKE, only of TOKEN",
+        Error::wrong_denom("FAKE", "TOKEN").to_string()
+    );
+    assert_eq!(
+        r#"Only admin can migrate contract: Some(Addr("admin"))"#,
+        Error::unauthorized_admin("migrate contract", Some(Addr::unchecked("admin"))).to_string()
+    );
+    assert_eq!(
+        "contract contract1984 not found",
+        Error::unknown_contract("contract1984").to_string()
+    );
+    assert_eq!(
+        "Label is required on all contracts",
+        Error::label_missing().to_string()
+    );
     assert_eq!(
         "code id: invalid",
         Error::invalid_contract_code_id().to_string()
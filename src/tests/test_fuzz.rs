@@ -0,0 +1,108 @@
+use crate::error::{bail, AnyResult};
+use crate::fuzz::{FuzzHarness, Step};
+use crate::{App, BasicApp};
+use cosmwasm_std::{coins, Addr, BankMsg, CosmosMsg, Empty, Uint128};
+use proptest::prelude::*;
+
+const ACCOUNTS: [&str; 3] = ["owner", "alice", "bob"];
+
+fn setup() -> BasicApp {
+    App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &Addr::unchecked("owner"), coins(100, "eth"))
+            .unwrap();
+    })
+}
+
+fn balance(app: &BasicApp, account: &str) -> Uint128 {
+    app.wrap().query_balance(account, "eth").unwrap().amount
+}
+
+fn total_balance(app: &BasicApp) -> AnyResult<()> {
+    let total: Uint128 = ACCOUNTS.iter().map(|account| balance(app, account)).sum();
+    if total != Uint128::new(100) {
+        bail!("total balance is {}", total);
+    }
+    Ok(())
+}
+
+fn sends() -> impl Strategy<Value = Step<Empty>> {
+    (0..ACCOUNTS.len(), 0..ACCOUNTS.len(), 1..=100u128).prop_map(|(from, to, amount)| {
+        Step::new(
+            ACCOUNTS[from],
+            BankMsg::Send {
+                to_address: ACCOUNTS[to].to_string(),
+                amount: coins(amount, "eth"),
+            },
+        )
+    })
+}
+
+/// Returns the amount transferred by the owner to other accounts.
+fn transferred_by_owner(steps: &[Step<Empty>]) -> u128 {
+    steps
+        .iter()
+        .filter(|step| step.sender == "owner")
+        .map(|step| match &step.msg {
+            CosmosMsg::Bank(BankMsg::Send { to_address, amount }) if to_address != "owner" => {
+                amount[0].amount.u128()
+            }
+            _ => 0,
+        })
+        .sum()
+}
+
+#[test]
+fn invariants_hold() {
+    FuzzHarness::new(setup)
+        .with_cases(32)
+        .with_invariant("total balance", total_balance)
+        .check(sends());
+}
+
+#[test]
+fn failing_sequence_is_shrunk() {
+    let failure = FuzzHarness::new(setup)
+        .with_cases(256)
+        .with_invariant("total balance", total_balance)
+        .with_invariant("owner keeps half", |app: &BasicApp| {
+            if balance(app, "owner") < Uint128::new(50) {
+                bail!("owner has less than half");
+            }
+            Ok(())
+        })
+        .run(sends())
+        .unwrap_err();
+
+    // the sequence is minimal, removing any transfer by the owner makes the invariant hold,
+    // other steps may be left by shrinking, as they do not affect the failure
+    assert!(failure
+        .reason
+        .ends_with("invariant 'owner keeps half' violated: owner has less than half"));
+    assert_eq!(
+        transferred_by_owner(&failure.steps),
+        51,
+        "{:?}",
+        failure.steps
+    );
+    for index in 0..failure.steps.len() {
+        let mut steps = failure.steps.clone();
+        let removed = steps.remove(index);
+        if transferred_by_owner(&[removed]) > 0 {
+            assert!(transferred_by_owner(&steps) < 51);
+        }
+    }
+
+    let code = failure.to_rust_test();
+    assert!(code.starts_with(
+        "use cosmwasm_std::{from_json, Addr};\nuse cw_multi_test::Executor;\n\n#[test]\nfn fuzz_reproduction() {\n    let mut app = setup();\n"
+    ));
+    assert!(code.contains(r###"from_json(r##"{"bank":{"send":{"to_address":"###));
+    assert!(code.ends_with(&format!("    // {}\n}}\n", failure.reason)));
+    assert!(failure.to_string().starts_with(&format!(
+        "{}; minimal failing sequence of {} steps:\nuse cosmwasm_std::",
+        failure.reason,
+        failure.steps.len()
+    )));
+}
@@ -0,0 +1,315 @@
+use crate::error::{bail, AnyResult, Error};
+use crate::registry::ModuleRegistry;
+use crate::test_helpers::CustomHelperMsg;
+use crate::{
+    custom_app, AppResponse, BankSudo, CosmosRouter, Executor, ModuleRegistration,
+    RegisteredModule, RegisteredMsg, RegisteredQuery, RegisteredSudo,
+};
+use cosmwasm_std::{
+    coins, from_json, to_json_binary, Addr, Api, Binary, BlockInfo, CosmosMsg, Empty, Querier,
+    QueryRequest, Storage,
+};
+use serde::{Deserialize, Serialize};
+
+/// Module minting tokens of its own denomination, like a simplified token factory.
+struct TokenFactory;
+
+#[derive(Serialize, Deserialize)]
+struct MintMsg {
+    amount: u128,
+}
+
+impl RegisteredModule<CustomHelperMsg, Empty> for TokenFactory {
+    fn execute(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = CustomHelperMsg, QueryC = Empty>,
+        block: &BlockInfo,
+        sender: Addr,
+        msg: RegisteredMsg<CustomHelperMsg>,
+    ) -> AnyResult<AppResponse> {
+        let amount = match msg {
+            RegisteredMsg::Stargate(msg) => from_json::<MintMsg>(msg.value)?.amount,
+            RegisteredMsg::Custom(CustomHelperMsg::SetAge { age }) => age as u128,
+            RegisteredMsg::Custom(msg) => bail!("unexpected message: {:?}", msg),
+        };
+        let mint = BankSudo::Mint {
+            to_address: sender.to_string(),
+            amount: coins(amount, "factory"),
+        };
+        router.sudo(api, storage, block, mint.into())
+    }
+
+    fn query(
+        &self,
+        _api: &dyn Api,
+        _storage: &dyn Storage,
+        _querier: &dyn Querier,
+        _block: &BlockInfo,
+        request: RegisteredQuery<Empty>,
+    ) -> AnyResult<Binary> {
+        match request {
+            RegisteredQuery::Stargate(_) => Ok(to_json_binary("factory")?),
+            RegisteredQuery::Custom(_) => bail!("unexpected query"),
+        }
+    }
+
+    fn sudo(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = CustomHelperMsg, QueryC = Empty>,
+        block: &BlockInfo,
+        msg: RegisteredSudo,
+    ) -> AnyResult<AppResponse> {
+        let MintMsg { amount } = from_json(msg.msg)?;
+        let mint = BankSudo::Mint {
+            to_address: "treasury".to_string(),
+            amount: coins(amount, "factory"),
+        };
+        router.sudo(api, storage, block, mint.into())
+    }
+}
+
+/// Module without custom messages and queries, processing nothing.
+struct EmptyModule;
+
+impl RegisteredModule<Empty, Empty> for EmptyModule {
+    fn execute(
+        &self,
+        _api: &dyn Api,
+        _storage: &mut dyn Storage,
+        _router: &dyn CosmosRouter<ExecC = Empty, QueryC = Empty>,
+        _block: &BlockInfo,
+        _sender: Addr,
+        _msg: RegisteredMsg<Empty>,
+    ) -> AnyResult<AppResponse> {
+        bail!("no messages")
+    }
+
+    fn query(
+        &self,
+        _api: &dyn Api,
+        _storage: &dyn Storage,
+        _querier: &dyn Querier,
+        _block: &BlockInfo,
+        _request: RegisteredQuery<Empty>,
+    ) -> AnyResult<Binary> {
+        bail!("no queries")
+    }
+
+    fn sudo(
+        &self,
+        _api: &dyn Api,
+        _storage: &mut dyn Storage,
+        _router: &dyn CosmosRouter<ExecC = Empty, QueryC = Empty>,
+        _block: &BlockInfo,
+        _msg: RegisteredSudo,
+    ) -> AnyResult<AppResponse> {
+        bail!("no sudo")
+    }
+}
+
+fn registration() -> ModuleRegistration<CustomHelperMsg, Empty> {
+    ModuleRegistration::new("tokenfactory", TokenFactory)
+        .claim_type_url("/tokenfactory.MsgMint")
+        .claim_query_path("/tokenfactory.Query/Denom")
+        .claim_custom_msgs(|msg| matches!(msg, CustomHelperMsg::SetAge { .. }))
+        .claim_sudo_namespace("tokenfactory")
+}
+
+#[test]
+fn registered_module_processes_claimed_messages() {
+    let mut app = custom_app::<CustomHelperMsg, Empty, _>(|router, _, _| {
+        router.register_module(registration()).unwrap();
+    });
+    let sender = Addr::unchecked("sender");
+
+    // claimed stargate message
+    app.execute(
+        sender.clone(),
+        CosmosMsg::Stargate {
+            type_url: "/tokenfactory.MsgMint".to_string(),
+            value: to_json_binary(&MintMsg { amount: 10 }).unwrap(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        10,
+        app.wrap()
+            .query_balance(&sender, "factory")
+            .unwrap()
+            .amount
+            .u128()
+    );
+
+    // claimed custom message
+    app.execute(
+        sender.clone(),
+        CosmosMsg::Custom(CustomHelperMsg::SetAge { age: 5 }),
+    )
+    .unwrap();
+    assert_eq!(
+        15,
+        app.wrap()
+            .query_balance(&sender, "factory")
+            .unwrap()
+            .amount
+            .u128()
+    );
+
+    // claimed stargate query
+    let denom: String = app
+        .wrap()
+        .query(&QueryRequest::Stargate {
+            path: "/tokenfactory.Query/Denom".to_string(),
+            data: Binary::default(),
+        })
+        .unwrap();
+    assert_eq!("factory", denom);
+
+    // claimed sudo namespace
+    app.sudo(
+        RegisteredSudo::new("tokenfactory", &MintMsg { amount: 7 })
+            .unwrap()
+            .into(),
+    )
+    .unwrap();
+    assert_eq!(
+        7,
+        app.wrap()
+            .query_balance("treasury", "factory")
+            .unwrap()
+            .amount
+            .u128()
+    );
+
+    // messages not claimed are still routed to the modules in fixed slots
+    let err = app
+        .execute(
+            sender.clone(),
+            CosmosMsg::Custom(CustomHelperMsg::SetName {
+                name: "John".to_string(),
+            }),
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<Error>(),
+        Some(Error::UnexpectedExecMsg { .. })
+    ));
+    let err = app
+        .execute(
+            sender,
+            CosmosMsg::Stargate {
+                type_url: "/other.MsgMint".to_string(),
+                value: Binary::default(),
+            },
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<Error>(),
+        Some(Error::UnexpectedExecMsg { .. })
+    ));
+    let msg = RegisteredSudo::new("other", &Empty {}).unwrap();
+    let err = app.sudo(msg.clone().into()).unwrap_err();
+    assert_eq!(
+        Some(&Error::unsupported_sudo_message(msg)),
+        err.downcast_ref::<Error>()
+    );
+}
+
+#[test]
+fn conflicting_claims_are_rejected() {
+    let mut app = custom_app::<CustomHelperMsg, Empty, _>(|router, _, _| {
+        router.register_module(registration()).unwrap();
+    });
+
+    assert_eq!(
+        "module 'tokenfactory' is already registered",
+        app.register_module(registration()).unwrap_err().to_string()
+    );
+    assert_eq!(
+        "'/tokenfactory.MsgMint' is already claimed by module 'tokenfactory'",
+        app.register_module(
+            ModuleRegistration::new("other", TokenFactory).claim_type_url("/tokenfactory.MsgMint")
+        )
+        .unwrap_err()
+        .to_string()
+    );
+    assert_eq!(
+        "'tokenfactory' is already claimed by module 'tokenfactory'",
+        app.register_module(
+            ModuleRegistration::new("other", TokenFactory).claim_sudo_namespace("tokenfactory")
+        )
+        .unwrap_err()
+        .to_string()
+    );
+    app.register_module(
+        ModuleRegistration::new("other", TokenFactory).claim_type_url("/other.MsgMint"),
+    )
+    .unwrap();
+}
+
+#[test]
+fn overlapping_custom_claims_are_rejected() {
+    let mut app = custom_app::<CustomHelperMsg, Empty, _>(|router, _, _| {
+        router.register_module(registration()).unwrap();
+        router
+            .register_module(
+                ModuleRegistration::new("other", TokenFactory).claim_custom_msgs(|_| true),
+            )
+            .unwrap();
+    });
+    let owner = Addr::unchecked("owner");
+
+    assert_eq!(
+        "message is claimed by both module 'tokenfactory' and module 'other'",
+        app.execute(
+            owner.clone(),
+            CosmosMsg::Custom(CustomHelperMsg::SetAge { age: 5 })
+        )
+        .unwrap_err()
+        .to_string()
+    );
+    // messages claimed by a single module are processed by it
+    assert!(app
+        .execute(
+            owner,
+            CosmosMsg::Custom(CustomHelperMsg::SetName {
+                name: "name".to_string(),
+            }),
+        )
+        .unwrap_err()
+        .to_string()
+        .starts_with("unexpected message: SetName"));
+}
+
+#[test]
+fn registrations_with_other_message_types_are_rejected() {
+    let mut registry = ModuleRegistry::default();
+    registry.register(registration()).unwrap();
+
+    assert_eq!(
+        "module 'tokenfactory' is registered with different message types",
+        registry
+            .register(ModuleRegistration::<Empty, Empty>::new(
+                "other",
+                EmptyModule
+            ))
+            .unwrap_err()
+            .to_string()
+    );
+    assert_eq!(
+        "module 'tokenfactory' is registered with different message types",
+        registry
+            .by_type_url::<Empty, Empty>("/tokenfactory.MsgMint")
+            .err()
+            .unwrap()
+            .to_string()
+    );
+    assert!(registry
+        .by_type_url::<CustomHelperMsg, Empty>("/tokenfactory.MsgMint")
+        .unwrap()
+        .is_some());
+}
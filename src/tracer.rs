@@ -0,0 +1,241 @@
+//! Tracing of the execution call tree: messages, submessages, replies, privileged actions and queries.
+//!
+//! Tracing is enabled with [App::enable_tracing](crate::App::enable_tracing).
+//! Every call made while processing a message is recorded as a [TraceNode],
+//! nested in the node of the call that made it.
+
+use crate::error::{AnyError, AnyResult};
+use crate::executor::AppResponse;
+use cosmwasm_std::{to_json_string, Binary, CosmosMsg, Event, QueryRequest, WasmMsg, WasmQuery};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::fmt;
+
+/// Kind of the traced call.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceKind {
+    /// Execution of a message.
+    Execute,
+    /// Execution of a submessage sent by a contract.
+    SubMsg,
+    /// Call to the `reply` entry-point of a contract.
+    Reply,
+    /// Privileged action.
+    Sudo,
+    /// Query.
+    Query,
+}
+
+/// Result of the traced call.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceResult {
+    /// The call has not finished yet.
+    Pending,
+    /// The call succeeded, with optional returned data.
+    Ok {
+        /// Data returned from the call.
+        data: Option<Binary>,
+    },
+    /// The call failed with the error message.
+    Err(String),
+}
+
+/// Single traced call, with all the calls made while processing it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TraceNode {
+    /// Kind of the call.
+    pub kind: TraceKind,
+    /// Address of the sender, if any.
+    pub sender: Option<String>,
+    /// Address of the called contract, or the name of the called module.
+    pub target: String,
+    /// Message, reply or query, serialized to JSON.
+    pub msg: String,
+    /// Identifier of the submessage and its reply.
+    pub reply_id: Option<u64>,
+    /// Result of the call.
+    pub result: TraceResult,
+    /// Events emitted by the call, including events of all nested calls.
+    pub events: Vec<Event>,
+    /// Calls made while processing this call.
+    pub children: Vec<TraceNode>,
+}
+
+impl TraceNode {
+    pub(crate) fn new(
+        kind: TraceKind,
+        sender: Option<String>,
+        target: impl Into<String>,
+        msg: impl Into<String>,
+        reply_id: Option<u64>,
+    ) -> Self {
+        Self {
+            kind,
+            sender,
+            target: target.into(),
+            msg: msg.into(),
+            reply_id,
+            result: TraceResult::Pending,
+            events: vec![],
+            children: vec![],
+        }
+    }
+
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let kind = match self.kind {
+            TraceKind::Execute => "execute",
+            TraceKind::SubMsg => "submsg",
+            TraceKind::Reply => "reply",
+            TraceKind::Sudo => "sudo",
+            TraceKind::Query => "query",
+        };
+        write!(f, "{:indent$}{}", "", kind, indent = depth * 2)?;
+        if let Some(reply_id) = self.reply_id {
+            write!(f, " #{}", reply_id)?;
+        }
+        if let Some(sender) = &self.sender {
+            write!(f, " {} ->", sender)?;
+        }
+        write!(f, " {} {}", self.target, self.msg)?;
+        match &self.result {
+            TraceResult::Pending => writeln!(f, " => pending")?,
+            TraceResult::Ok { .. } => writeln!(f, " => ok ({} events)", self.events.len())?,
+            TraceResult::Err(err) => writeln!(f, " => error: {}", err)?,
+        }
+        for child in &self.children {
+            child.fmt_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TraceNode {
+    /// Pretty-prints the call tree, one call per line, nested calls indented.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+/// Records the call tree of all processed messages, see [TraceNode].
+#[derive(Default, Debug)]
+pub struct Tracer {
+    /// Calls that are being processed, the innermost one last.
+    stack: RefCell<Vec<TraceNode>>,
+    /// Finished top-level calls.
+    traces: RefCell<Vec<TraceNode>>,
+}
+
+impl Tracer {
+    /// Returns all finished top-level calls, in order of execution.
+    pub fn traces(&self) -> Vec<TraceNode> {
+        self.traces.borrow().clone()
+    }
+
+    /// Removes all recorded calls.
+    pub fn clear(&self) {
+        self.traces.borrow_mut().clear();
+    }
+
+    /// Exports all recorded calls as JSON.
+    pub fn to_json(&self) -> AnyResult<String> {
+        Ok(to_json_string(&*self.traces.borrow())?)
+    }
+
+    /// Starts recording the call, nested calls are recorded as its children.
+    pub(crate) fn enter(&self, node: TraceNode) {
+        self.stack.borrow_mut().push(node);
+    }
+
+    /// Finishes recording the innermost call with the result of the execution.
+    pub(crate) fn exit_response(&self, result: &AnyResult<AppResponse>) {
+        match result {
+            Ok(response) => self.exit(
+                TraceResult::Ok {
+                    data: response.data.clone(),
+                },
+                response.events.clone(),
+            ),
+            Err(err) => self.exit(TraceResult::Err(error_message(err)), vec![]),
+        }
+    }
+
+    /// Finishes recording the innermost call with the result of the query.
+    pub(crate) fn exit_query(&self, result: &AnyResult<Binary>) {
+        match result {
+            Ok(data) => self.exit(
+                TraceResult::Ok {
+                    data: Some(data.clone()),
+                },
+                vec![],
+            ),
+            Err(err) => self.exit(TraceResult::Err(error_message(err)), vec![]),
+        }
+    }
+
+    fn exit(&self, result: TraceResult, events: Vec<Event>) {
+        let mut stack = self.stack.borrow_mut();
+        let Some(mut node) = stack.pop() else {
+            return;
+        };
+        node.result = result;
+        node.events = events;
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => self.traces.borrow_mut().push(node),
+        }
+    }
+}
+
+impl fmt::Display for Tracer {
+    /// Pretty-prints all recorded calls.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for trace in self.traces.borrow().iter() {
+            write!(f, "{}", trace)?;
+        }
+        Ok(())
+    }
+}
+
+fn error_message(err: &AnyError) -> String {
+    format!("{:#}", err)
+}
+
+/// Returns the address of the contract or the name of the module processing the message.
+pub(crate) fn msg_target<T>(msg: &CosmosMsg<T>) -> String {
+    match msg {
+        CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. })
+        | CosmosMsg::Wasm(WasmMsg::Migrate { contract_addr, .. })
+        | CosmosMsg::Wasm(WasmMsg::UpdateAdmin { contract_addr, .. })
+        | CosmosMsg::Wasm(WasmMsg::ClearAdmin { contract_addr }) => contract_addr.as_str(),
+        CosmosMsg::Wasm(_) => "wasm",
+        CosmosMsg::Bank(_) => "bank",
+        CosmosMsg::Custom(_) => "custom",
+        CosmosMsg::Staking(_) => "staking",
+        CosmosMsg::Distribution(_) => "distribution",
+        CosmosMsg::Ibc(_) => "ibc",
+        CosmosMsg::Gov(_) => "gov",
+        CosmosMsg::Stargate { .. } => "stargate",
+        _ => "unknown",
+    }
+    .to_string()
+}
+
+/// Returns the address of the contract or the name of the module processing the query.
+pub(crate) fn query_target<T>(request: &QueryRequest<T>) -> String {
+    match request {
+        QueryRequest::Wasm(WasmQuery::Smart { contract_addr, .. })
+        | QueryRequest::Wasm(WasmQuery::Raw { contract_addr, .. })
+        | QueryRequest::Wasm(WasmQuery::ContractInfo { contract_addr }) => contract_addr.as_str(),
+        QueryRequest::Wasm(_) => "wasm",
+        QueryRequest::Bank(_) => "bank",
+        QueryRequest::Custom(_) => "custom",
+        QueryRequest::Staking(_) => "staking",
+        QueryRequest::Ibc(_) => "ibc",
+        QueryRequest::Stargate { .. } => "stargate",
+        _ => "unknown",
+    }
+    .to_string()
+}
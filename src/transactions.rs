@@ -1,6 +1,8 @@
 use crate::error::AnyResult;
 use cosmwasm_std::Storage;
-use cosmwasm_std::{Order, Record};
+use cosmwasm_std::{Binary, Order, Record};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::iter;
@@ -43,6 +45,36 @@ impl<'a> StorageTransaction<'a> {
     pub fn prepare(self) -> RepLog {
         self.rep_log
     }
+
+    /// returns the changes made in this transaction compared to backing storage, ordered by key
+    pub fn changes(&self) -> Vec<StorageChange> {
+        self.local_state
+            .iter()
+            .filter_map(|(key, delta)| {
+                let old_value = self.storage.get(key);
+                let new_value = match delta {
+                    Delta::Set { value } => Some(value.clone()),
+                    Delta::Delete {} => None,
+                };
+                (old_value != new_value).then(|| StorageChange {
+                    key: key.clone().into(),
+                    old_value: old_value.map(Into::into),
+                    new_value: new_value.map(Into::into),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Change of a single key-value pair in the storage.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct StorageChange {
+    /// Changed key.
+    pub key: Binary,
+    /// Value before the change, `None` when the key was added.
+    pub old_value: Option<Binary>,
+    /// Value after the change, `None` when the key was removed.
+    pub new_value: Option<Binary>,
 }
 
 impl<'a> Storage for StorageTransaction<'a> {
@@ -0,0 +1,236 @@
+//! Transactions, modelled after transactions processed by cosmos-sdk based chains.
+//!
+//! A [Tx] carries messages, signers, memo, fee and gas limit. It is executed atomically
+//! with [App::deliver_tx](crate::App::deliver_tx), which returns a [TxResponse],
+//! also stored in the transaction history, see [App::query_tx](crate::App::query_tx).
+
+use crate::executor::AppResponse;
+use crate::transactions::StorageChange;
+use cosmwasm_std::{Addr, Coin, CosmosMsg, Empty, Event, Order, Record, Storage, Timestamp};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::collections::HashMap;
+
+/// Default gas limit of a transaction, the same as in `wasmd` command line interface.
+pub const DEFAULT_GAS_LIMIT: u64 = 200_000;
+
+/// Codespace of errors reported by cosmos-sdk.
+pub(crate) const CODESPACE_SDK: &str = "sdk";
+
+/// Codespace of errors that are not registered, like in cosmos-sdk.
+pub(crate) const CODESPACE_UNDEFINED: &str = "undefined";
+
+/// Code of the error reported when the fee can not be paid.
+pub(crate) const CODE_INSUFFICIENT_FEE: u32 = 13;
+
+/// Code of the error reported when the transaction exceeds its gas limit.
+pub(crate) const CODE_OUT_OF_GAS: u32 = 11;
+
+/// Code of errors that are not registered, like in cosmos-sdk.
+pub(crate) const CODE_UNDEFINED: u32 = 1;
+
+/// Transaction with multiple messages, executed atomically.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Tx<ExecC = Empty> {
+    /// Messages executed in order, all of them or none.
+    pub msgs: Vec<CosmosMsg<ExecC>>,
+    /// Addresses of accounts that signed the transaction.
+    /// The first signer sends all messages and pays the fee.
+    pub signers: Vec<Addr>,
+    /// Arbitrary note attached to the transaction.
+    pub memo: String,
+    /// Fee paid to the fee collector, even when the messages fail.
+    pub fee: Vec<Coin>,
+    /// Maximum amount of gas the transaction can use.
+    pub gas_limit: u64,
+}
+
+impl<ExecC> Tx<ExecC> {
+    /// Creates a transaction signed by a single signer, without memo and fee,
+    /// with [DEFAULT_GAS_LIMIT].
+    pub fn new(signer: Addr, msgs: Vec<CosmosMsg<ExecC>>) -> Self {
+        Self {
+            msgs,
+            signers: vec![signer],
+            memo: String::new(),
+            fee: vec![],
+            gas_limit: DEFAULT_GAS_LIMIT,
+        }
+    }
+
+    /// Adds another signer of the transaction.
+    pub fn with_signer(mut self, signer: Addr) -> Self {
+        self.signers.push(signer);
+        self
+    }
+
+    /// Sets the memo of the transaction.
+    pub fn with_memo(mut self, memo: impl Into<String>) -> Self {
+        self.memo = memo.into();
+        self
+    }
+
+    /// Sets the fee paid for the transaction.
+    pub fn with_fee(mut self, fee: Vec<Coin>) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    /// Sets the gas limit of the transaction.
+    pub fn with_gas_limit(mut self, gas_limit: u64) -> Self {
+        self.gas_limit = gas_limit;
+        self
+    }
+}
+
+/// Result of delivering a [Tx], like the one returned by cosmos-sdk based chains.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TxResponse {
+    /// Height of the block containing the transaction.
+    pub height: u64,
+    /// Time of the block containing the transaction.
+    pub timestamp: Timestamp,
+    /// Hash of the transaction, uppercase hex encoded.
+    pub txhash: String,
+    /// Result code, zero on success.
+    pub code: u32,
+    /// Namespace of the result code, empty on success.
+    pub codespace: String,
+    /// Error message on failure, empty on success.
+    pub raw_log: String,
+    /// Gas limit of the transaction.
+    pub gas_wanted: u64,
+    /// Gas used by the transaction.
+    pub gas_used: u64,
+    /// Events emitted by the transaction. Events emitted by messages
+    /// have the `msg_index` attribute with the position of the message in the transaction.
+    pub events: Vec<Event>,
+    /// Responses of executed messages, empty on failure.
+    pub msg_responses: Vec<AppResponse>,
+}
+
+impl TxResponse {
+    /// Returns `true` when the transaction succeeded.
+    pub fn is_ok(&self) -> bool {
+        self.code == 0
+    }
+}
+
+/// History of transactions delivered with [App::deliver_tx](crate::App::deliver_tx)
+/// and of recorded executions of messages, in order of delivery.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TxHistory {
+    /// Delivered transactions.
+    txs: Vec<TxResponse>,
+    /// Positions of delivered transactions in the history, by transaction hash.
+    positions: HashMap<String, usize>,
+}
+
+impl TxHistory {
+    /// Returns the number of delivered transactions.
+    pub(crate) fn count(&self) -> u64 {
+        self.txs.len() as u64
+    }
+
+    /// Appends the delivered transaction to the history.
+    pub(crate) fn record(&mut self, response: TxResponse) {
+        self.positions
+            .insert(response.txhash.clone(), self.txs.len());
+        self.txs.push(response);
+    }
+
+    /// Returns the delivered transaction with specified hash.
+    pub(crate) fn get(&self, txhash: &str) -> Option<&TxResponse> {
+        self.positions
+            .get(txhash)
+            .map(|position| &self.txs[*position])
+    }
+
+    /// Returns all delivered transactions, in order of delivery.
+    pub(crate) fn txs(&self) -> &[TxResponse] {
+        &self.txs
+    }
+}
+
+/// Result of simulating messages with [App::simulate](crate::App::simulate).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulationResponse {
+    /// Responses of simulated messages.
+    pub responses: Vec<AppResponse>,
+    /// Gas used by storage operations of simulated messages.
+    pub gas_used: u64,
+    /// Changes of the storage that would have been made by simulated messages, ordered by key.
+    pub changes: Vec<StorageChange>,
+}
+
+// Gas costs of storage operations, the same as the defaults in cosmos-sdk,
+// see https://github.com/cosmos/cosmos-sdk/blob/v0.47.5/store/types/gas.go
+const DELETE_COST: u64 = 1000;
+const READ_COST_FLAT: u64 = 1000;
+const READ_COST_PER_BYTE: u64 = 3;
+const WRITE_COST_FLAT: u64 = 2000;
+const WRITE_COST_PER_BYTE: u64 = 30;
+const ITER_NEXT_COST_FLAT: u64 = 30;
+
+/// Storage consuming gas for every operation, like the gas metered store in cosmos-sdk.
+pub(crate) struct GasMeteredStorage<'a> {
+    storage: &'a mut dyn Storage,
+    gas_used: &'a Cell<u64>,
+}
+
+impl<'a> GasMeteredStorage<'a> {
+    pub(crate) fn new(storage: &'a mut dyn Storage, gas_used: &'a Cell<u64>) -> Self {
+        Self { storage, gas_used }
+    }
+
+    fn consume(&self, gas: u64) {
+        self.gas_used.set(self.gas_used.get().saturating_add(gas));
+    }
+}
+
+impl<'a> Storage for GasMeteredStorage<'a> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        let value = self.storage.get(key);
+        let value_len = value.as_ref().map(Vec::len).unwrap_or_default();
+        self.consume(READ_COST_FLAT + READ_COST_PER_BYTE * (key.len() + value_len) as u64);
+        value
+    }
+
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        let gas_used = self.gas_used;
+        Box::new(
+            self.storage
+                .range(start, end, order)
+                .map(move |(key, value)| {
+                    let gas =
+                        ITER_NEXT_COST_FLAT + READ_COST_PER_BYTE * (key.len() + value.len()) as u64;
+                    gas_used.set(gas_used.get().saturating_add(gas));
+                    (key, value)
+                }),
+        )
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.consume(WRITE_COST_FLAT + WRITE_COST_PER_BYTE * (key.len() + value.len()) as u64);
+        self.storage.set(key, value);
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.consume(DELETE_COST);
+        self.storage.remove(key);
+    }
+}
+
+/// Adds the `msg_index` attribute to all events emitted by the message.
+pub(crate) fn with_msg_index(events: Vec<Event>, msg_index: usize) -> Vec<Event> {
+    events
+        .into_iter()
+        .map(|event| event.add_attribute("msg_index", msg_index.to_string()))
+        .collect()
+}
@@ -4,14 +4,18 @@ use crate::checksums::{ChecksumGenerator, SimpleChecksumGenerator};
 use crate::contracts::Contract;
 use crate::error::{bail, AnyContext, AnyError, AnyResult, Error};
 use crate::executor::AppResponse;
+use crate::genesis::Model;
 use crate::prefixed_storage::{prefixed, prefixed_read, PrefixedStorage, ReadonlyPrefixedStorage};
+use crate::tracer::{msg_target, TraceKind, TraceNode};
 use crate::transactions::transactional;
+use crate::tx::{CODESPACE_UNDEFINED, CODE_UNDEFINED};
 use cosmwasm_std::testing::mock_wasmd_attr;
 use cosmwasm_std::{
-    to_json_binary, Addr, Api, Attribute, BankMsg, Binary, BlockInfo, Coin, ContractInfo,
-    ContractInfoResponse, CustomMsg, CustomQuery, Deps, DepsMut, Env, Event, HexBinary,
-    MessageInfo, Order, Querier, QuerierWrapper, Record, Reply, ReplyOn, Response, StdResult,
-    Storage, SubMsg, SubMsgResponse, SubMsgResult, TransactionInfo, WasmMsg, WasmQuery,
+    to_json_binary, to_json_string, Addr, Api, Attribute, BankMsg, Binary, BlockInfo, Coin,
+    ContractInfo, ContractInfoResponse, CosmosMsg, CustomMsg, CustomQuery, Deps, DepsMut, Env,
+    Event, HexBinary, MessageInfo, Order, Querier, QuerierWrapper, Record, Reply, ReplyOn,
+    Response, StdResult, Storage, SubMsg, SubMsgResponse, SubMsgResult, TransactionInfo, WasmMsg,
+    WasmQuery,
 };
 use cw_storage_plus::Map;
 use prost::Message;
@@ -20,6 +24,7 @@ use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
 use std::fmt::Debug;
+use std::rc::Rc;
 
 //TODO Make `CONTRACTS` private in version 1.0 when the function AddressGenerator::next_address will be removed.
 /// Contract state kept in storage, separate from the contracts themselves (contract code).
@@ -27,6 +32,10 @@ pub(crate) const CONTRACTS: Map<&Addr, ContractData> = Map::new("contracts");
 
 //TODO Make `NAMESPACE_WASM` private in version 1.0 when the function AddressGenerator::next_address will be removed.
 pub(crate) const NAMESPACE_WASM: &[u8] = b"wasm";
+
+/// Prefix of the namespace of contract's storage, followed by the contract address.
+pub(crate) const CONTRACT_NAMESPACE_PREFIX: &[u8] = b"contract_data/";
+
 /// See <https://github.com/chipshort/wasmd/blob/d0e3ed19f041e65f112d8e800416b3230d0005a2/x/wasm/types/events.go#L58>
 const CONTRACT_ATTR: &str = "_contract_address";
 
@@ -66,6 +75,7 @@ pub struct ContractData {
 }
 
 /// Contract code base data.
+#[derive(Clone)]
 struct CodeData {
     /// Address of an account that initially stored the contract code.
     creator: Addr,
@@ -73,6 +83,8 @@ struct CodeData {
     checksum: HexBinary,
     /// Identifier of the code base where the contract code is stored in memory.
     code_base_id: usize,
+    /// Optional name of the code, used to bind the code to the contract when importing the state.
+    name: Option<String>,
 }
 
 /// Acts as the interface for interacting with WebAssembly (Wasm) modules.
@@ -126,27 +138,45 @@ pub trait Wasm<ExecC, QueryC> {
 }
 
 /// A structure representing a default wasm keeper.
+///
+/// Cloning the keeper is cheap, the stored contract codes are shared between clones.
 pub struct WasmKeeper<ExecC, QueryC> {
     /// Contract codes that stand for wasm code in real-life blockchain.
-    code_base: Vec<Box<dyn Contract<ExecC, QueryC>>>,
+    code_base: Vec<Rc<dyn Contract<ExecC, QueryC>>>,
     /// Code data with code base identifier and additional attributes.
     code_data: Vec<CodeData>,
     /// Contract's address generator.
-    address_generator: Box<dyn AddressGenerator>,
+    address_generator: Rc<dyn AddressGenerator>,
     /// Contract's code checksum generator.
-    checksum_generator: Box<dyn ChecksumGenerator>,
+    checksum_generator: Rc<dyn ChecksumGenerator>,
+    /// Flag indicating if errors passed to replies are redacted like in `wasmd`.
+    redact_errors: bool,
     /// Just markers to make type elision fork when using it as `Wasm` trait
     _p: std::marker::PhantomData<QueryC>,
 }
 
+impl<ExecC, QueryC> Clone for WasmKeeper<ExecC, QueryC> {
+    fn clone(&self) -> Self {
+        Self {
+            code_base: self.code_base.clone(),
+            code_data: self.code_data.clone(),
+            address_generator: self.address_generator.clone(),
+            checksum_generator: self.checksum_generator.clone(),
+            redact_errors: self.redact_errors,
+            _p: std::marker::PhantomData,
+        }
+    }
+}
+
 impl<ExecC, QueryC> Default for WasmKeeper<ExecC, QueryC> {
     /// Returns the default value for [WasmKeeper].
     fn default() -> Self {
         Self {
             code_base: Vec::default(),
             code_data: Vec::default(),
-            address_generator: Box::new(SimpleAddressGenerator),
-            checksum_generator: Box::new(SimpleChecksumGenerator),
+            address_generator: Rc::new(SimpleAddressGenerator),
+            checksum_generator: Rc::new(SimpleChecksumGenerator),
+            redact_errors: false,
             _p: std::marker::PhantomData,
         }
     }
@@ -192,7 +222,7 @@ where
                 res.checksum = code_data.checksum.clone();
                 to_json_binary(&res).map_err(Into::into)
             }
-            _ => unimplemented!("{}", Error::unsupported_wasm_query(request)),
+            _ => bail!(Error::unsupported_wasm_query(request)),
         }
     }
 
@@ -232,13 +262,14 @@ where
     /// Returns an identifier of the stored contract code.
     fn store_code(&mut self, creator: Addr, code: Box<dyn Contract<ExecC, QueryC>>) -> u64 {
         let code_base_id = self.code_base.len();
-        self.code_base.push(code);
+        self.code_base.push(Rc::from(code));
         let code_id = (self.code_data.len() + 1) as u64;
         let checksum = self.checksum_generator.checksum(&creator, code_id);
         self.code_data.push(CodeData {
             creator,
             checksum,
             code_base_id,
+            name: None,
         });
         code_id
     }
@@ -251,6 +282,7 @@ where
             creator: code_data.creator.clone(),
             checksum: code_data.checksum.clone(),
             code_base_id: code_data.code_base_id,
+            name: code_data.name.clone(),
         });
         Ok(code_id + 1)
     }
@@ -259,7 +291,7 @@ where
     fn contract_data(&self, storage: &dyn Storage, address: &Addr) -> AnyResult<ContractData> {
         CONTRACTS
             .load(&prefixed_read(storage, NAMESPACE_WASM), address)
-            .map_err(Into::into)
+            .context(Error::unknown_contract(address))
     }
 
     /// Returns a raw state dump of all key-values held by a contract with specified address.
@@ -288,7 +320,7 @@ impl<ExecC, QueryC> WasmKeeper<ExecC, QueryC> {
     }
 
     fn contract_namespace(&self, contract: &Addr) -> Vec<u8> {
-        let mut name = b"contract_data/".to_vec();
+        let mut name = CONTRACT_NAMESPACE_PREFIX.to_vec();
         name.extend_from_slice(contract.as_bytes());
         name
     }
@@ -390,7 +422,7 @@ where
         address_generator: impl AddressGenerator + 'static,
     ) -> Self {
         Self {
-            address_generator: Box::new(address_generator),
+            address_generator: Rc::new(address_generator),
             ..Default::default()
         }
     }
@@ -429,7 +461,7 @@ where
         mut self,
         address_generator: impl AddressGenerator + 'static,
     ) -> Self {
-        self.address_generator = Box::new(address_generator);
+        self.address_generator = Rc::new(address_generator);
         self
     }
 
@@ -460,10 +492,64 @@ where
         mut self,
         checksum_generator: impl ChecksumGenerator + 'static,
     ) -> Self {
-        self.checksum_generator = Box::new(checksum_generator);
+        self.checksum_generator = Rc::new(checksum_generator);
+        self
+    }
+
+    /// Sets whether errors of failed submessages are redacted before being passed to replies.
+    ///
+    /// By default, the reply receives the full debug representation of the error.
+    /// With redaction enabled, the reply receives the error redacted like in `wasmd`,
+    /// e.g. `codespace: wasm, code: 5` for an error returned by the executed contract
+    /// or `codespace: sdk, code: 5` for insufficient funds. The full error is still
+    /// recorded in the execution trace, see [App::enable_tracing](crate::App::enable_tracing).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cw_multi_test::{AppBuilder, no_init, WasmKeeper};
+    ///
+    /// // populate wasm keeper redacting errors passed to replies
+    /// let wasm_keeper = WasmKeeper::new().with_redacted_errors(true);
+    ///
+    /// // create and use the application with customized wasm keeper
+    /// let mut app = AppBuilder::default().with_wasm(wasm_keeper).build(no_init);
+    /// ```
+    pub fn with_redacted_errors(mut self, redact_errors: bool) -> Self {
+        self.redact_errors = redact_errors;
         self
     }
 
+    /// Returns the name, creator and checksum of all stored codes, ordered by code identifier.
+    pub(crate) fn code_infos(&self) -> Vec<(Option<String>, Addr, HexBinary)> {
+        self.code_data
+            .iter()
+            .map(|code_data| {
+                (
+                    code_data.name.clone(),
+                    code_data.creator.clone(),
+                    code_data.checksum.clone(),
+                )
+            })
+            .collect()
+    }
+
+    /// Sets the name and checksum of the code with specified identifier.
+    pub(crate) fn set_code_info(
+        &mut self,
+        code_id: u64,
+        name: Option<String>,
+        checksum: Option<HexBinary>,
+    ) -> AnyResult<()> {
+        self.code_data(code_id)?;
+        let code_data = &mut self.code_data[(code_id - 1) as usize];
+        code_data.name = name;
+        if let Some(checksum) = checksum {
+            code_data.checksum = checksum;
+        }
+        Ok(())
+    }
+
     /// Executes contract's `query` entry-point.
     pub fn query_smart(
         &self,
@@ -532,7 +618,10 @@ where
         // check admin status
         let mut data = self.contract_data(storage, &contract_addr)?;
         if data.admin != Some(sender) {
-            bail!("Only admin can update the contract admin: {:?}", data.admin);
+            bail!(Error::unauthorized_admin(
+                "update the contract admin",
+                data.admin
+            ));
         }
         // update admin field
         data.admin = admin;
@@ -637,7 +726,7 @@ where
                 }
                 let mut data = self.contract_data(storage, &contract_addr)?;
                 if data.admin != Some(sender) {
-                    bail!("Only admin can migrate contract: {:?}", data.admin);
+                    bail!(Error::unauthorized_admin("migrate contract", data.admin));
                 }
                 data.code_id = new_code_id;
                 self.save_contract(storage, &contract_addr, &data)?;
@@ -668,7 +757,7 @@ where
             WasmMsg::ClearAdmin { contract_addr } => {
                 self.update_admin(api, storage, sender, &contract_addr, None)
             }
-            _ => unimplemented!("{}", Error::unsupported_wasm_message(msg)),
+            _ => bail!(Error::unsupported_wasm_message(msg)),
         }
     }
 
@@ -688,7 +777,7 @@ where
         salt: Option<Binary>,
     ) -> AnyResult<AppResponse> {
         if label.is_empty() {
-            bail!("Label is required on all contracts");
+            bail!(Error::label_missing());
         }
 
         let contract_addr = self.register_contract(
@@ -760,10 +849,35 @@ where
         block: &BlockInfo,
         contract: Addr,
         msg: SubMsg<ExecC>,
+    ) -> AnyResult<AppResponse> {
+        let Some(tracer) = router.tracer() else {
+            return self.execute_submsg_untraced(api, router, storage, block, contract, msg);
+        };
+        tracer.enter(TraceNode::new(
+            TraceKind::SubMsg,
+            Some(contract.to_string()),
+            msg_target(&msg.msg),
+            to_json_string(&msg.msg).unwrap_or_default(),
+            Some(msg.id),
+        ));
+        let res = self.execute_submsg_untraced(api, router, storage, block, contract, msg);
+        tracer.exit_response(&res);
+        res
+    }
+
+    fn execute_submsg_untraced(
+        &self,
+        api: &dyn Api,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        contract: Addr,
+        msg: SubMsg<ExecC>,
     ) -> AnyResult<AppResponse> {
         let SubMsg {
             msg, id, reply_on, ..
         } = msg;
+        let default_abci_info = default_abci_info(&msg);
 
         // execute in cache
         let res = transactional(storage, |write_cache, _| {
@@ -796,7 +910,11 @@ where
             if matches!(reply_on, ReplyOn::Always | ReplyOn::Error) {
                 let reply = Reply {
                     id,
-                    result: SubMsgResult::Err(format!("{:?}", e)),
+                    result: SubMsgResult::Err(if self.redact_errors {
+                        redact_error(&e, default_abci_info)
+                    } else {
+                        format!("{:?}", e)
+                    }),
                 };
                 self.reply(api, router, storage, block, contract, reply)
             } else {
@@ -815,6 +933,30 @@ where
         block: &BlockInfo,
         contract: Addr,
         reply: Reply,
+    ) -> AnyResult<AppResponse> {
+        let Some(tracer) = router.tracer() else {
+            return self.reply_untraced(api, router, storage, block, contract, reply);
+        };
+        tracer.enter(TraceNode::new(
+            TraceKind::Reply,
+            None,
+            contract.to_string(),
+            to_json_string(&reply).unwrap_or_default(),
+            Some(reply.id),
+        ));
+        let res = self.reply_untraced(api, router, storage, block, contract, reply);
+        tracer.exit_response(&res);
+        res
+    }
+
+    fn reply_untraced(
+        &self,
+        api: &dyn Api,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        contract: Addr,
+        reply: Reply,
     ) -> AnyResult<AppResponse> {
         let ok_attr = if reply.result.is_ok() {
             "handle_success"
@@ -1060,13 +1202,15 @@ where
         )?)
     }
 
-    fn get_env<T: Into<Addr>>(&self, address: T, block: &BlockInfo) -> Env {
+    fn get_env<T: Into<Addr>>(&self, address: T, block: &BlockInfo, transaction_index: u32) -> Env {
         Env {
             block: block.clone(),
             contract: ContractInfo {
                 address: address.into(),
             },
-            transaction: Some(TransactionInfo { index: 0 }),
+            transaction: Some(TransactionInfo {
+                index: transaction_index,
+            }),
         }
     }
 
@@ -1085,7 +1229,7 @@ where
         let contract = self.contract_data(storage, &address)?;
         let handler = self.contract_code(contract.code_id)?;
         let storage = self.contract_storage_readonly(storage, &address);
-        let env = self.get_env(address, block);
+        let env = self.get_env(address, block, 0);
 
         let deps = Deps {
             storage: storage.as_ref(),
@@ -1118,7 +1262,7 @@ where
         transactional(storage, |write_cache, read_store| {
             let mut contract_storage = self.contract_storage(write_cache, &address);
             let querier = RouterQuerier::new(router, api, read_store, block);
-            let env = self.get_env(address, block);
+            let env = self.get_env(address, block, router.transaction_index());
 
             let deps = DepsMut {
                 storage: contract_storage.as_mut(),
@@ -1141,6 +1285,44 @@ where
             .map_err(Into::into)
     }
 
+    /// Saves contract data and replaces the whole contract's state with the specified key-value pairs.
+    pub(crate) fn import_contract(
+        &self,
+        storage: &mut dyn Storage,
+        address: &Addr,
+        contract: &ContractData,
+        state: &[Model],
+    ) -> AnyResult<()> {
+        self.code_data(contract.code_id)?;
+        self.save_contract(storage, address, contract)?;
+        let mut contract_storage = self.contract_storage(storage, address);
+        let keys: Vec<Vec<u8>> = contract_storage
+            .range_keys(None, None, Order::Ascending)
+            .collect();
+        for key in keys {
+            contract_storage.remove(&key);
+        }
+        for model in state {
+            contract_storage.set(model.key.as_slice(), model.value.as_slice());
+        }
+        Ok(())
+    }
+
+    /// Checks that every instantiated contract refers to a stored code.
+    pub(crate) fn check_code_ids(&self, storage: &dyn Storage) -> AnyResult<()> {
+        for item in CONTRACTS.range(
+            &prefixed_read(storage, NAMESPACE_WASM),
+            None,
+            None,
+            Order::Ascending,
+        ) {
+            let (address, contract) = item?;
+            self.code_data(contract.code_id)
+                .with_context(|| format!("contract {}", address))?;
+        }
+        Ok(())
+    }
+
     /// Returns the number of all contract instances.
     fn instance_count(&self, storage: &dyn Storage) -> usize {
         CONTRACTS
@@ -1194,6 +1376,28 @@ fn execute_response(data: Option<Binary>) -> Option<Binary> {
     })
 }
 
+/// Returns the codespace and code reported by `wasmd` for an untyped error
+/// that occurred while executing the message, like an error returned by a contract.
+fn default_abci_info<ExecC>(msg: &CosmosMsg<ExecC>) -> (&'static str, u32) {
+    match msg {
+        CosmosMsg::Wasm(WasmMsg::Execute { .. }) => ("wasm", 5),
+        CosmosMsg::Wasm(WasmMsg::Instantiate { .. }) => ("wasm", 4),
+        #[cfg(feature = "cosmwasm_1_2")]
+        CosmosMsg::Wasm(WasmMsg::Instantiate2 { .. }) => ("wasm", 4),
+        CosmosMsg::Wasm(WasmMsg::Migrate { .. }) => ("wasm", 11),
+        _ => (CODESPACE_UNDEFINED, CODE_UNDEFINED),
+    }
+}
+
+/// Redacts the error like `wasmd` does before passing it to a reply,
+/// keeping only the codespace and code of the error.
+fn redact_error(err: &AnyError, default_abci_info: (&'static str, u32)) -> String {
+    let (codespace, code) = err
+        .downcast_ref::<Error>()
+        .map_or(default_abci_info, Error::abci_info);
+    format!("codespace: {}, code: {}", codespace, code)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1237,6 +1441,9 @@ mod test {
             ibc: IbcFailingModule::new(),
             gov: GovFailingModule::new(),
             stargate: StargateFailingModule::new(),
+            tracer: None,
+            registry: Default::default(),
+            transaction_index: 0,
         }
     }
 
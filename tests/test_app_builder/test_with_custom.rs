@@ -0,0 +1,32 @@
+use crate::test_app_builder::{MyKeeper, NO_MESSAGE};
+use cosmwasm_std::Empty;
+use cw_multi_test::{no_init, AppBuilder, SudoMsg};
+
+#[derive(Debug)]
+struct MyCustomSudo {
+    _action: String,
+}
+
+type MyCustomKeeper = MyKeeper<Empty, Empty, MyCustomSudo>;
+
+const SUDO_MSG: &str = "custom sudo called";
+
+#[test]
+fn building_app_with_custom_module_should_work() {
+    // build custom keeper handling privileged actions of its own type
+    let custom_keeper = MyCustomKeeper::new(NO_MESSAGE, NO_MESSAGE, SUDO_MSG);
+
+    // build the application with custom keeper
+    let app_builder = AppBuilder::new_custom();
+    let mut app = app_builder.with_custom(custom_keeper).build(no_init);
+
+    // custom privileged actions should be routed to the custom keeper
+    assert_eq!(
+        SUDO_MSG,
+        app.sudo(SudoMsg::Custom(MyCustomSudo {
+            _action: "mint".to_string()
+        }))
+        .unwrap_err()
+        .to_string()
+    );
+}
@@ -1,17 +1,18 @@
 use crate::test_app_builder::{MyKeeper, NO_MESSAGE};
 use cosmwasm_std::{Addr, Empty, GovMsg, VoteOption};
-use cw_multi_test::{no_init, AppBuilder, Executor, Gov};
+use cw_multi_test::{no_init, AppBuilder, Executor, Gov, SudoMsg};
 
 type MyGovKeeper = MyKeeper<GovMsg, Empty, Empty>;
 
 impl Gov for MyGovKeeper {}
 
 const EXECUTE_MSG: &str = "gov execute called";
+const SUDO_MSG: &str = "gov sudo called";
 
 #[test]
 fn building_app_with_custom_gov_should_work() {
-    // build custom gov keeper (no query and sudo handling for gov)
-    let gov_keeper = MyGovKeeper::new(EXECUTE_MSG, NO_MESSAGE, NO_MESSAGE);
+    // build custom gov keeper (no query handling for gov)
+    let gov_keeper = MyGovKeeper::new(EXECUTE_MSG, NO_MESSAGE, SUDO_MSG);
 
     // build the application with custom gov keeper
     let app_builder = AppBuilder::default();
@@ -31,4 +32,10 @@ fn building_app_with_custom_gov_should_work() {
         .unwrap_err()
         .to_string()
     );
+
+    // executing gov sudo should return an error defined in custom keeper
+    assert_eq!(
+        SUDO_MSG,
+        app.sudo(SudoMsg::Gov(Empty {})).unwrap_err().to_string()
+    );
 }